@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+
+content="test data"
+
+setup() {
+  echo "$content" > file
+}
+
+run_test() {
+  file_url=$(curl -s -F "file=@file" localhost:8000)
+  test "$file_url" = "http://localhost:8000/file.txt"
+  test "$content" != "$(cat upload/file.txt)"
+  test "$content" = "$(curl -s $file_url)"
+  url_url=$(curl -s -F "url=https://orhun.dev/" localhost:8000)
+  test "https://orhun.dev/" = "$(curl -s -o /dev/null -w '%{redirect_url}' $url_url)"
+}
+
+teardown() {
+  rm file
+  rm -r upload
+}
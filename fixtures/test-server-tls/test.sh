@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+
+content="test data"
+
+setup() {
+  echo "$content" > file
+}
+
+run_test() {
+  file_url=$(curl -sk -F "file=@file" https://localhost:8000)
+  test "$file_url" = "https://localhost:8000/file.txt"
+  test "$content" = "$(cat upload/file.txt)"
+  test "$content" = "$(curl -sk $file_url)"
+  test -z "$(curl -s -F "file=@file" http://localhost:8000)"
+}
+
+teardown() {
+  rm file
+  rm -r upload
+}
@@ -0,0 +1,71 @@
+use crate::storage::StorageBackend;
+use crate::util;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+
+/// Directory of the aliases in the upload path.
+///
+/// Each alias is stored as a file that contains the name of the file it points to.
+pub const ALIAS_DIR: &str = ".aliases";
+
+/// Returns the name of the file that the alias points to.
+///
+/// Returns `None` if there is no such alias.
+pub fn read(
+    storage: &dyn StorageBackend,
+    upload_path: &Path,
+    alias: &str,
+) -> IoResult<Option<String>> {
+    let path = get_path(upload_path, alias)?;
+    if !storage.exists(&path)? {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&storage.fetch(&path)?).to_string(),
+    ))
+}
+
+/// Creates an alias that points to the given file name.
+///
+/// Returns an error if the alias already exists.
+pub fn write(
+    storage: &dyn StorageBackend,
+    upload_path: &Path,
+    alias: &str,
+    file_name: &str,
+) -> IoResult<()> {
+    let path = get_path(upload_path, alias)?;
+    if storage.is_local() {
+        std::fs::create_dir_all(upload_path.join(ALIAS_DIR))?;
+    }
+    storage.store_new(&path, file_name.as_bytes())
+}
+
+/// Returns the path of the given alias.
+pub fn get_path(upload_path: &Path, alias: &str) -> IoResult<PathBuf> {
+    util::safe_path_join(upload_path.join(ALIAS_DIR), alias)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_alias() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_alias_upload");
+        assert_eq!(None, read(&LocalStorage, &upload_path, "alias.txt")?);
+        write(&LocalStorage, &upload_path, "alias.txt", "file.txt")?;
+        assert_eq!(
+            Some(String::from("file.txt")),
+            read(&LocalStorage, &upload_path, "alias.txt")?
+        );
+        assert!(write(&LocalStorage, &upload_path, "alias.txt", "other.txt").is_err());
+        assert!(get_path(&upload_path, "../file.txt").is_err());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,18 @@
 use crate::config::{Config, TokenType};
+use crate::header::DELETE_TOKEN;
+use crate::middleware::get_client_host;
+use crate::util;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::http::header::AUTHORIZATION;
-use actix_web::http::Method;
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
+use actix_web::http::{Method, StatusCode};
 use actix_web::middleware::ErrorHandlerResponse;
-use actix_web::{error, web, Error};
+use actix_web::{error, web, Error, HttpResponse, ResponseError};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use ring::constant_time;
 use std::collections::HashSet;
+use std::fmt;
 use std::sync::RwLock;
 
 /// Extracts the tokens from the authorization header by token type.
@@ -19,11 +27,7 @@ pub(crate) async fn extract_tokens(req: &ServiceRequest) -> Result<HashSet<Token
 
     let mut user_tokens = HashSet::with_capacity(2);
 
-    let auth_header = req
-        .headers()
-        .get(AUTHORIZATION)
-        .map(|v| v.to_str().unwrap_or_default())
-        .map(|v| v.split_whitespace().last().unwrap_or_default());
+    let auth_header = get_auth_token(req.headers());
 
     for token_type in [TokenType::Auth, TokenType::Delete] {
         let maybe_tokens = config.get_tokens(token_type);
@@ -34,7 +38,10 @@ pub(crate) async fn extract_tokens(req: &ServiceRequest) -> Result<HashSet<Token
         } else if token_type == TokenType::Auth {
             // not configured `auth_tokens` means that the user is allowed to access the endpoints
             user_tokens.insert(token_type);
-        } else if token_type == TokenType::Delete && req.method() == Method::DELETE {
+        } else if token_type == TokenType::Delete
+            && req.method() == Method::DELETE
+            && config.get_delete_token_secret().is_none()
+        {
             // explicitly disable `DELETE` methods if no `delete_tokens` are set
             warn!("delete endpoint is not served because there are no delete_tokens set");
             Err(error::ErrorNotFound(""))?;
@@ -44,44 +51,172 @@ pub(crate) async fn extract_tokens(req: &ServiceRequest) -> Result<HashSet<Token
     Ok(user_tokens)
 }
 
-/// Returns `HttpResponse` with unauthorized (`401`) error and `unauthorized\n` as body.
+/// Checks if the request has one of the configured auth or delete tokens.
+///
+/// Returns `false` if there are no tokens configured.
+pub(crate) fn has_configured_token(config: &Config, headers: &HeaderMap) -> bool {
+    let token = get_auth_token(headers).unwrap_or_default();
+    [TokenType::Auth, TokenType::Delete]
+        .into_iter()
+        .filter_map(|token_type| config.get_tokens(token_type))
+        .any(|tokens| tokens.contains(token))
+}
+
+/// Returns the token from the authorization header.
+pub(crate) fn get_auth_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .map(|v| v.to_str().unwrap_or_default())
+        .map(|v| v.split_whitespace().last().unwrap_or_default())
+}
+
+/// Returns the SHA256 digest of the configured auth token in the authorization header.
+///
+/// Returns `None` if there are no auth tokens configured or the token is not one of them.
+pub(crate) fn get_token_hash(config: &Config, headers: &HeaderMap) -> Option<String> {
+    let token = get_auth_token(headers)?;
+    config
+        .get_tokens(TokenType::Auth)
+        .filter(|tokens| tokens.contains(token))
+        .and_then(|_| util::sha256_digest(token.as_bytes()).ok())
+}
+
+/// Generates the delete token of the given file using the configured secret.
+pub(crate) fn generate_delete_token(secret: &str, file_name: &str) -> Result<String, Error> {
+    util::hmac_sha256_digest(secret.as_bytes(), file_name.as_bytes())
+}
+
+/// Checks if the token in the [`delete token header`](DELETE_TOKEN) belongs to the given file.
+pub(crate) fn verify_delete_token(config: &Config, headers: &HeaderMap, file_name: &str) -> bool {
+    let (Some(secret), Some(token)) = (
+        config.get_delete_token_secret(),
+        headers.get(DELETE_TOKEN).and_then(|v| v.to_str().ok()),
+    ) else {
+        return false;
+    };
+    generate_delete_token(secret, file_name)
+        .map(|expected| {
+            constant_time::verify_slices_are_equal(expected.as_bytes(), token.as_bytes()).is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Hashes the password of a paste with Argon2.
+///
+/// Returns the hash in the PHC string format.
+pub(crate) fn hash_password(password: &str) -> Result<String, Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|v| v.to_string())
+        .map_err(|e| error::ErrorInternalServerError(format!("cannot hash password: {e}")))
+}
+
+/// Checks if the password matches the given hash.
+pub(crate) fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash)
+        .and_then(|hash| Argon2::default().verify_password(password.as_bytes(), &hash))
+        .is_ok()
+}
+
+/// Body of the responses for the invalid or missing tokens.
+pub(crate) const UNAUTHORIZED: &str = "unauthorized\n";
+
+/// Error for the invalid or missing tokens.
+///
+/// Only the responses of this error are replaced by [`handle_unauthorized_error`].
+#[derive(Debug)]
+pub(crate) struct TokenError;
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(UNAUTHORIZED)
+    }
+}
+
+impl ResponseError for TokenError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+/// Returns `HttpResponse` with unauthorized (`401`) error and [`UNAUTHORIZED`] as body.
 pub(crate) fn unauthorized_error() -> actix_web::HttpResponse {
-    error::ErrorUnauthorized("unauthorized\n").into()
+    Error::from(TokenError).into()
 }
 
 /// Log all unauthorized requests.
+///
+/// The responses for the invalid or missing tokens are replaced according to
+/// [`auth_failure_status`] and [`auth_failure_body`].
+///
+/// [`auth_failure_status`]: crate::config::ServerConfig::auth_failure_status
+/// [`auth_failure_body`]: crate::config::ServerConfig::auth_failure_body
 pub(crate) fn handle_unauthorized_error<B>(
     res: ServiceResponse<B>,
 ) -> actix_web::Result<ErrorHandlerResponse<B>> {
-    let connection = res.request().connection_info().clone();
-    let host = connection.realip_remote_addr().unwrap_or("unknown host");
-
-    #[cfg(debug_assertions)]
-    {
-        let auth_header = res
-            .request()
-            .headers()
-            .get(AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("none");
-
-        warn!("authorization failure for {host} (token: {auth_header})",);
-    }
-    #[cfg(not(debug_assertions))]
+    let trusted_proxies = res
+        .request()
+        .app_data::<web::Data<RwLock<Config>>>()
+        .and_then(|v| v.read().ok())
+        .map(|v| v.server.trusted_proxies.clone())
+        .unwrap_or_default();
+    let host = get_client_host(res.request(), &trusted_proxies);
+    // The tokens are never logged.
     warn!("authorization failure for {host}");
 
-    Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
+    // The other failures (e.g. wrong passwords of the pastes) are returned as is.
+    let is_token_failure = res
+        .response()
+        .error()
+        .is_some_and(|e| e.as_error::<TokenError>().is_some());
+    let Some((status, body)) = res
+        .request()
+        .app_data::<web::Data<RwLock<Config>>>()
+        .and_then(|v| v.read().ok())
+        .map(|v| {
+            (
+                v.server.auth_failure_status,
+                v.server.auth_failure_body.clone(),
+            )
+        })
+        .filter(|(status, body)| is_token_failure && (status.is_some() || body.is_some()))
+    else {
+        return Ok(ErrorHandlerResponse::Response(res.map_into_left_body()));
+    };
+    let status = status
+        .and_then(|v| StatusCode::from_u16(v).ok())
+        .unwrap_or(StatusCode::UNAUTHORIZED);
+    let body = body.unwrap_or_else(|| match status {
+        StatusCode::UNAUTHORIZED => String::from(UNAUTHORIZED),
+        _ => String::new(),
+    });
+    let (request, _) = res.into_parts();
+    Ok(ErrorHandlerResponse::Response(
+        ServiceResponse::new(request, HttpResponse::build(status).body(body)).map_into_right_body(),
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use actix_web::http::header::HeaderValue;
+    use actix_web::http::header::{HeaderName, HeaderValue};
     use actix_web::test::TestRequest;
     use actix_web::web::Data;
     use actix_web::HttpResponse;
     use awc::http::StatusCode;
 
+    #[test]
+    fn test_password_hash() -> Result<(), Error> {
+        let hash = hash_password("hunter2")?;
+        assert!(hash.starts_with("$argon2"));
+        assert_ne!(hash, hash_password("hunter2")?);
+        assert!(verify_password(&hash, "hunter2"));
+        assert!(!verify_password(&hash, "hunter3"));
+        assert!(!verify_password("invalid hash", "hunter2"));
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_extract_tokens() -> Result<(), Error> {
         let mut config = Config::default();
@@ -157,6 +292,37 @@ mod tests {
         let tokens = extract_tokens(&request).await?;
         assert_eq!(HashSet::new(), tokens);
 
+        // DELETE request without configured delete-tokens but with a delete token secret
+        config.server.delete_tokens = None;
+        config.server.delete_token_secret = Some("secret".to_string());
+        let request = TestRequest::default()
+            .method(Method::DELETE)
+            .app_data(Data::new(RwLock::new(config.clone())))
+            .to_srv_request();
+        let tokens = extract_tokens(&request).await?;
+        assert_eq!(HashSet::new(), tokens);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_delete_token() -> Result<(), Error> {
+        let mut config = Config::default();
+        let token = generate_delete_token("secret", "file.txt")?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static(DELETE_TOKEN),
+            HeaderValue::from_str(&token)?,
+        );
+        assert!(!verify_delete_token(&config, &headers, "file.txt"));
+
+        config.server.delete_token_secret = Some("secret".to_string());
+        assert!(verify_delete_token(&config, &headers, "file.txt"));
+        assert!(!verify_delete_token(&config, &headers, "another_file.txt"));
+        assert!(!verify_delete_token(&config, &HeaderMap::new(), "file.txt"));
+
+        config.server.delete_token_secret = Some("another_secret".to_string());
+        assert!(!verify_delete_token(&config, &headers, "file.txt"));
         Ok(())
     }
 }
@@ -0,0 +1,168 @@
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+/// Default value of [`clamav_timeout`](crate::config::SecurityConfig::clamav_timeout).
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Size of the chunks that are sent to the daemon.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Result of a virus scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    /// No virus is found.
+    Clean,
+    /// The data is infected with the virus of the given signature.
+    Infected(String),
+}
+
+impl fmt::Display for ScanResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clean => write!(f, "clean"),
+            Self::Infected(signature) => write!(f, "infected ({signature})"),
+        }
+    }
+}
+
+/// Scans the data with the ClamAV daemon at the given address via the `INSTREAM` command.
+///
+/// The address is either a TCP address (e.g. `127.0.0.1:3310`)
+/// or an absolute path of a Unix socket (e.g. `/run/clamav/clamd.ctl`).
+pub fn scan(address: &str, data: &[u8], timeout: Duration) -> IoResult<ScanResult> {
+    if Path::new(address).is_absolute() {
+        #[cfg(unix)]
+        {
+            let mut stream = UnixStream::connect(address)?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            return scan_stream(&mut stream, data);
+        }
+        #[cfg(not(unix))]
+        return Err(IoError::new(
+            IoErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        ));
+    }
+    let mut stream = connect(address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    scan_stream(&mut stream, data)
+}
+
+/// Connects to the first reachable socket address that the TCP address resolves to.
+///
+/// Each connection attempt is aborted after the given timeout.
+fn connect(address: &str, timeout: Duration) -> IoResult<TcpStream> {
+    let mut last_error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        IoError::new(
+            IoErrorKind::InvalidInput,
+            format!("cannot resolve the address: {address}"),
+        )
+    }))
+}
+
+/// Sends the data in length-prefixed chunks and parses the reply of the daemon.
+fn scan_stream<S: Read + Write>(stream: &mut S, data: &[u8]) -> IoResult<ScanResult> {
+    stream.write_all(b"zINSTREAM\0")?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let length =
+            u32::try_from(chunk.len()).map_err(|e| IoError::new(IoErrorKind::InvalidInput, e))?;
+        stream.write_all(&length.to_be_bytes())?;
+        stream.write_all(chunk)?;
+    }
+    stream.write_all(&0_u32.to_be_bytes())?;
+    stream.flush()?;
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    parse_reply(&String::from_utf8_lossy(&reply))
+}
+
+/// Parses the reply of the daemon (e.g. `stream: OK` or `stream: Eicar-Signature FOUND`).
+fn parse_reply(reply: &str) -> IoResult<ScanResult> {
+    let reply = reply.trim_end_matches(['\0', '\n']).trim();
+    let result = reply
+        .rsplit_once(": ")
+        .map(|(_, result)| result)
+        .unwrap_or(reply);
+    if result == "OK" {
+        Ok(ScanResult::Clean)
+    } else if let Some(signature) = result.strip_suffix(" FOUND") {
+        Ok(ScanResult::Infected(signature.to_string()))
+    } else {
+        Err(IoError::other(format!("unexpected ClamAV reply: {reply}")))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Starts a fake ClamAV daemon that reports the data containing `virus` as infected.
+    ///
+    /// Returns the address of the daemon.
+    pub(crate) fn spawn_daemon() -> IoResult<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().filter_map(Result::ok) {
+                let mut command = [0; 10];
+                let mut data = Vec::new();
+                let result = stream.read_exact(&mut command).and_then(|_| loop {
+                    let mut length = [0; 4];
+                    stream.read_exact(&mut length)?;
+                    let length = u32::from_be_bytes(length) as usize;
+                    if length == 0 {
+                        break Ok(());
+                    }
+                    let mut chunk = vec![0; length];
+                    stream.read_exact(&mut chunk)?;
+                    data.extend(chunk);
+                });
+                let reply: &[u8] = if result.is_err() || &command != b"zINSTREAM\0" {
+                    b"UNKNOWN COMMAND\0"
+                } else if String::from_utf8_lossy(&data).contains("virus") {
+                    b"stream: Test-Signature FOUND\0"
+                } else {
+                    b"stream: OK\0"
+                };
+                let _ = stream.write_all(reply);
+            }
+        });
+        Ok(address)
+    }
+
+    #[test]
+    fn test_scan() -> IoResult<()> {
+        let address = spawn_daemon()?;
+        assert_eq!(
+            ScanResult::Clean,
+            scan(&address, b"harmless", DEFAULT_TIMEOUT)?
+        );
+        assert_eq!(
+            ScanResult::Infected(String::from("Test-Signature")),
+            scan(
+                &address,
+                &[b"a".repeat(CHUNK_SIZE), b"virus".to_vec()].concat(),
+                DEFAULT_TIMEOUT
+            )?
+        );
+        assert_eq!(ScanResult::Clean, parse_reply("/tmp/file: OK\n")?);
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR\0").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use crate::metadata::{self, Metadata};
+use crate::storage::LocalStorage;
+use crate::thumbnail;
+use crate::util;
+use glob::{glob, Pattern};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Result of a cleanup.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanupReport {
+    /// Number of the removed expired files.
+    pub expired_files: usize,
+    /// Number of the removed metadata and thumbnail files that do not belong to any file.
+    pub orphaned_files: usize,
+    /// Number of the skipped files with an expiration timestamp that cannot be parsed.
+    pub skipped_files: usize,
+    /// Total size of the removed files in bytes.
+    pub freed_bytes: u64,
+}
+
+impl AddAssign for CleanupReport {
+    fn add_assign(&mut self, other: Self) {
+        self.expired_files += other.expired_files;
+        self.orphaned_files += other.orphaned_files;
+        self.skipped_files += other.skipped_files;
+        self.freed_bytes += other.freed_bytes;
+    }
+}
+
+/// Result of removing the files of a token.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionReport {
+    /// Number of the removed files.
+    pub deleted_files: usize,
+    /// Total size of the removed files in bytes.
+    pub freed_bytes: u64,
+}
+
+impl AddAssign for DeletionReport {
+    fn add_assign(&mut self, other: Self) {
+        self.deleted_files += other.deleted_files;
+        self.freed_bytes += other.freed_bytes;
+    }
+}
+
+/// Removes the expired and orphaned files in the upload path.
+///
+/// Only the local storage is supported.
+pub fn run(upload_path: &Path) -> CleanupReport {
+    let mut report = remove_expired_files(upload_path);
+    let orphans = remove_orphaned_files(upload_path);
+    report.orphaned_files = orphans.orphaned_files;
+    report.freed_bytes += orphans.freed_bytes;
+    report
+}
+
+/// Removes the expired files along with their metadata and thumbnails.
+///
+/// The files with an expiration timestamp that cannot be parsed are skipped.
+/// The metadata is kept if it belongs to a newer file with the same name
+/// (see [`Metadata::delete_expired`]).
+pub fn remove_expired_files(upload_path: &Path) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let Ok(now) = util::get_system_time() else {
+        return report;
+    };
+    for file in util::get_stored_files(upload_path) {
+        let Some(extension) = file.extension().and_then(|v| v.to_str()) else {
+            continue;
+        };
+        if !extension.starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(expires_at) = extension.parse::<u64>() else {
+            warn!(
+                "Skipped file with an invalid expiration timestamp: {:?}",
+                file
+            );
+            report.skipped_files += 1;
+            continue;
+        };
+        if now <= Duration::from_millis(expires_at) {
+            continue;
+        }
+        let size = fs::metadata(&file).map(|v| v.len()).unwrap_or_default();
+        if let Err(e) = fs::remove_file(&file) {
+            error!("Cannot remove expired file: {}", e);
+            continue;
+        }
+        info!("Removed expired file: {:?}", file);
+        report.expired_files += 1;
+        report.freed_bytes += size;
+        if let Err(e) = Metadata::delete_expired(&LocalStorage, upload_path, &file) {
+            error!("Cannot remove metadata: {}", e);
+        }
+        if let Err(e) = thumbnail::delete(upload_path, &file) {
+            error!("Cannot remove thumbnails: {}", e);
+        }
+    }
+    report
+}
+
+/// Removes the metadata and thumbnail files of the files that do not exist anymore.
+pub fn remove_orphaned_files(upload_path: &Path) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let orphans = find_files(&upload_path.join(metadata::METADATA_DIR), "json")
+        .into_iter()
+        .filter(|(_, file_path)| {
+            !file_path.exists() && !has_timestamped_file(&file_path.to_string_lossy())
+        })
+        .chain(
+            find_files(&upload_path.join(thumbnail::THUMBNAIL_DIR), "png")
+                .into_iter()
+                .filter_map(|(path, file_path)| {
+                    // Strip the width of the thumbnail (e.g. `image.png.200`).
+                    let file_path = file_path.with_extension("");
+                    (!file_path.exists()).then_some((path, file_path))
+                }),
+        )
+        .map(|(path, _)| path)
+        .collect::<Vec<PathBuf>>();
+    for path in orphans {
+        let size = fs::metadata(&path).map(|v| v.len()).unwrap_or_default();
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                info!("Removed orphaned file: {:?}", path);
+                report.orphaned_files += 1;
+                report.freed_bytes += size;
+            }
+            Err(e) => error!("Cannot remove orphaned file: {}", e),
+        }
+    }
+    report
+}
+
+/// Removes the files that are uploaded with the token of the given hash
+/// along with their metadata and thumbnails.
+///
+/// The token hash is read from the [`Metadata`], so the files without metadata are kept.
+pub fn remove_token_files(upload_path: &Path, token_hash: &str) -> DeletionReport {
+    let mut report = DeletionReport::default();
+    for (metadata_path, file_path) in find_files(&upload_path.join(metadata::METADATA_DIR), "json")
+    {
+        let is_token_file = fs::read(&metadata_path)
+            .ok()
+            .and_then(|v| serde_json::from_slice::<Metadata>(&v).ok())
+            .and_then(|v| v.token_hash)
+            .is_some_and(|v| v == token_hash);
+        if !is_token_file {
+            continue;
+        }
+        let timestamped_files = glob(&format!(
+            "{}.[0-9]*",
+            Pattern::escape(&file_path.to_string_lossy())
+        ))
+        .map(|paths| paths.filter_map(Result::ok).collect::<Vec<PathBuf>>())
+        .unwrap_or_default();
+        for file in std::iter::once(file_path.clone())
+            .chain(timestamped_files)
+            .filter(|v| v.is_file())
+        {
+            let size = fs::metadata(&file).map(|v| v.len()).unwrap_or_default();
+            match fs::remove_file(&file) {
+                Ok(()) => {
+                    info!("Removed file of the token: {:?}", file);
+                    report.deleted_files += 1;
+                    report.freed_bytes += size;
+                }
+                Err(e) => error!("Cannot remove file: {}", e),
+            }
+        }
+        if let Err(e) = fs::remove_file(&metadata_path) {
+            error!("Cannot remove metadata: {}", e);
+        }
+        if let Err(e) = thumbnail::delete(upload_path, &file_path) {
+            error!("Cannot remove thumbnails: {}", e);
+        }
+    }
+    report
+}
+
+/// Returns the files with the given extension in the given directory (recursively)
+/// along with the paths of the files that they belong to in the upload path.
+///
+/// e.g. `upload/.metadata/url/file.json` belongs to `upload/url/file`.
+fn find_files(dir: &Path, extension: &str) -> Vec<(PathBuf, PathBuf)> {
+    let Some(upload_path) = dir.parent() else {
+        return Vec::new();
+    };
+    let pattern = format!(
+        "{}/**/*.{extension}",
+        Pattern::escape(&dir.to_string_lossy())
+    );
+    glob(&pattern)
+        .map(|paths| {
+            paths
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .filter_map(|path| {
+                    let file_path = upload_path.join(path.strip_prefix(dir).ok()?);
+                    Some((path, file_path.with_extension("")))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Checks if there is a file with an expiration timestamp (e.g. `file.txt.1700000000000`)
+/// for the given path.
+fn has_timestamped_file(path: &str) -> bool {
+    glob(&format!("{}.[0-9]*", Pattern::escape(path)))
+        .map(|mut paths| paths.any(|v| v.is_ok_and(|v| v.is_file())))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Result as IoResult;
+
+    #[test]
+    fn test_cleanup() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_cleanup_upload");
+        let metadata_path = upload_path.join(metadata::METADATA_DIR);
+        let thumbnail_path = upload_path.join(thumbnail::THUMBNAIL_DIR);
+        fs::create_dir_all(&metadata_path)?;
+        fs::create_dir_all(&thumbnail_path)?;
+
+        fs::write(upload_path.join("expired.txt.1000000000000"), "expired")?;
+        fs::write(upload_path.join("invalid.txt.1a"), "invalid")?;
+        fs::write(upload_path.join("active.txt.99999999999999"), "active")?;
+        fs::write(upload_path.join("file.txt"), "file")?;
+        fs::write(metadata_path.join("expired.txt.json"), "{}")?;
+        fs::write(metadata_path.join("active.txt.json"), "{}")?;
+        fs::write(metadata_path.join("file.txt.json"), "{}")?;
+        fs::write(metadata_path.join("orphan.txt.json"), "{}")?;
+        fs::write(thumbnail_path.join("file.txt.200.png"), "png")?;
+        fs::write(thumbnail_path.join("orphan.png.200.png"), "png")?;
+
+        let report = run(&upload_path);
+        assert_eq!(
+            CleanupReport {
+                expired_files: 1,
+                orphaned_files: 2,
+                skipped_files: 1,
+                freed_bytes: 7 + 2 + 3,
+            },
+            report
+        );
+        assert!(!upload_path.join("expired.txt.1000000000000").exists());
+        assert!(!metadata_path.join("expired.txt.json").exists());
+        assert!(!metadata_path.join("orphan.txt.json").exists());
+        assert!(!thumbnail_path.join("orphan.png.200.png").exists());
+        assert!(upload_path.join("invalid.txt.1a").exists());
+        assert!(metadata_path.join("active.txt.json").exists());
+        assert!(metadata_path.join("file.txt.json").exists());
+        assert!(thumbnail_path.join("file.txt.200.png").exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_token_files() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_remove_token_files_upload");
+        let metadata_path = upload_path.join(metadata::METADATA_DIR);
+        fs::create_dir_all(&metadata_path)?;
+
+        let metadata = |token_hash: Option<&str>| {
+            serde_json::to_vec(&Metadata {
+                token_hash: token_hash.map(String::from),
+                ..Default::default()
+            })
+        };
+        fs::write(upload_path.join("first.txt"), "first")?;
+        fs::write(upload_path.join("second.txt.99999999999999"), "second")?;
+        fs::write(upload_path.join("other.txt"), "other")?;
+        fs::write(upload_path.join("none.txt"), "none")?;
+        fs::write(metadata_path.join("first.txt.json"), metadata(Some("abc"))?)?;
+        fs::write(
+            metadata_path.join("second.txt.json"),
+            metadata(Some("abc"))?,
+        )?;
+        fs::write(metadata_path.join("other.txt.json"), metadata(Some("def"))?)?;
+        fs::write(metadata_path.join("none.txt.json"), metadata(None)?)?;
+
+        assert_eq!(
+            DeletionReport {
+                deleted_files: 2,
+                freed_bytes: 5 + 6,
+            },
+            remove_token_files(&upload_path, "abc")
+        );
+        assert!(!upload_path.join("first.txt").exists());
+        assert!(!upload_path.join("second.txt.99999999999999").exists());
+        assert!(!metadata_path.join("first.txt.json").exists());
+        assert!(upload_path.join("other.txt").exists());
+        assert!(upload_path.join("none.txt").exists());
+        assert!(metadata_path.join("other.txt.json").exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
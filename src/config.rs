@@ -1,15 +1,35 @@
-use crate::mime::MimeMatcher;
+use crate::mime::{self as mime_util, MimeMatcher};
+use crate::paste::PasteType;
 use crate::random::RandomURLConfig;
+use crate::stats;
+use crate::storage::SharedBackend;
+use crate::util;
 use crate::{AUTH_TOKEN_ENV, DELETE_TOKEN_ENV};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::Method;
 use byte_unit::Byte;
 use config::{self, ConfigError};
-use std::collections::HashSet;
+use serde::{Deserialize, Deserializer};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt;
+use std::iter;
+use std::net::IpAddr;
+use std::num::ParseIntError;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+use url::Url;
+
+/// Placeholder for the sensitive values of the [redacted configuration](Config::redacted).
+const REDACTED: &str = "[redacted]";
 
 /// Configuration values.
+///
+/// The values that are not set are taken from the [`Default`] implementation.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct Config {
     /// Configuration settings.
     #[serde(rename = "config")]
@@ -20,6 +40,18 @@ pub struct Config {
     pub paste: PasteConfig,
     /// Landing page configuration.
     pub landing_page: Option<LandingPageConfig>,
+    /// Storage configuration.
+    pub storage: Option<StorageConfig>,
+    /// Auth tokens with their own upload policies.
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    /// Webhook configuration.
+    pub webhooks: Option<WebhookConfig>,
+    /// Security configuration.
+    pub security: Option<SecurityConfig>,
+    /// Storage backend that is shared between the requests.
+    #[serde(skip)]
+    pub storage_backend: Option<SharedBackend>,
 }
 
 /// General settings for configuration.
@@ -31,13 +63,19 @@ pub struct Settings {
 }
 
 /// Server configuration.
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct ServerConfig {
     /// The socket address to bind.
     pub address: String,
     /// URL that can be used to access the server externally.
+    ///
+    /// Returned links are built from this URL when it is set.
+    #[serde(alias = "public_url")]
     pub url: Option<String>,
     /// Number of workers to start.
+    ///
+    /// The number of logical CPUs is used if it is not set or `0`.
     pub workers: Option<usize>,
     /// Maximum content length.
     pub max_content_length: Byte,
@@ -46,11 +84,23 @@ pub struct ServerConfig {
     /// Request timeout.
     #[serde(default, with = "humantime_serde")]
     pub timeout: Option<Duration>,
+    /// Maximum time to wait for the active requests to finish when shutting down.
+    #[serde(default, with = "humantime_serde")]
+    pub shutdown_timeout: Option<Duration>,
     /// Authentication token.
     #[deprecated(note = "use [server].auth_tokens instead")]
     pub auth_token: Option<String>,
     /// Authentication tokens.
+    #[serde(default, deserialize_with = "deserialize_tokens")]
     pub auth_tokens: Option<HashSet<String>>,
+    /// Status code of the responses for the invalid or missing tokens (`401` or `404`).
+    ///
+    /// Defaults to `401 Unauthorized`.
+    pub auth_failure_status: Option<u16>,
+    /// Body of the responses for the invalid or missing tokens.
+    ///
+    /// Defaults to `unauthorized` for `401` and an empty body for `404`.
+    pub auth_failure_body: Option<String>,
     /// Expose version.
     pub expose_version: Option<bool>,
     /// Landing page text.
@@ -63,8 +113,202 @@ pub struct ServerConfig {
     pub handle_spaces: Option<SpaceHandlingConfig>,
     /// Path of the JSON index.
     pub expose_list: Option<bool>,
+    /// Expose Prometheus metrics.
+    pub expose_metrics: Option<bool>,
     /// Authentication tokens for deleting.
+    #[serde(default, deserialize_with = "deserialize_tokens")]
     pub delete_tokens: Option<HashSet<String>>,
+    /// Secret for generating the delete tokens of the uploaded files.
+    pub delete_token_secret: Option<String>,
+    /// Rate limiting configuration.
+    pub rate_limit: Option<RateLimitConfig>,
+    /// TLS configuration.
+    pub tls: Option<TlsConfig>,
+    /// CORS configuration.
+    pub cors: Option<CorsConfig>,
+    /// Logging verbosity (e.g. `debug` or `rustypaste=debug,actix_web=info`).
+    ///
+    /// `RUST_LOG` environment variable takes precedence over this option.
+    pub log_level: Option<String>,
+    /// Path of the file for writing the access logs to.
+    ///
+    /// Access logs are written to the server log if not set.
+    pub access_log: Option<PathBuf>,
+    /// Reject the uploads and deletions while still serving the files (e.g. during maintenance).
+    pub read_only: Option<bool>,
+    /// Handling of the uploads that exceed the maximum content length.
+    #[serde(default)]
+    pub on_oversize: OversizePolicy,
+    /// Path of the page to serve for the files that are not found or expired.
+    ///
+    /// The content type is inferred from the extension of the page (e.g. `404.html`).
+    pub not_found_page: Option<PathBuf>,
+    /// IP addresses or CIDR ranges that are allowed to upload and delete files.
+    ///
+    /// All addresses are allowed if it is empty.
+    pub allowed_ips: Vec<IpNetwork>,
+    /// IP addresses or CIDR ranges that are not allowed to upload and delete files.
+    pub blocked_ips: Vec<IpNetwork>,
+    /// Apply [`allowed_ips`](Self::allowed_ips) and [`blocked_ips`](Self::blocked_ips)
+    /// to the `GET` and `HEAD` requests as well.
+    pub filter_reads: Option<bool>,
+    /// Use the client address from the `Forwarded`/`X-Forwarded-For` headers for filtering the IPs.
+    #[deprecated(note = "use [server].trusted_proxies instead")]
+    pub trust_forwarded_for: Option<bool>,
+    /// IP addresses or CIDR ranges of the reverse proxies that are trusted to forward
+    /// the client address via the `X-Forwarded-For` header.
+    ///
+    /// Applies to the IP filtering, rate limiting and logs.
+    pub trusted_proxies: Vec<IpNetwork>,
+    /// Additional upload paths to use when [`upload_path`](Self::upload_path) is full.
+    ///
+    /// The files are stored in the first path where the total size of the stored files
+    /// does not exceed [`overflow_threshold`](Self::overflow_threshold).
+    pub overflow_paths: Vec<PathBuf>,
+    /// Maximum total size of the stored files in each upload path before overflowing to the next one.
+    pub overflow_threshold: Option<Byte>,
+    /// Template of the plain-text upload response for each file.
+    ///
+    /// Supports the `{url}`, `{filename}`, `{size}` and `{expires}` placeholders.
+    /// Defaults to `{url}`.
+    pub response_template: Option<String>,
+    /// Security headers of the responses.
+    pub security_headers: Option<SecurityHeadersConfig>,
+    /// `Cache-Control` header of the served files.
+    pub cache_control: Option<CacheControlConfig>,
+}
+
+impl Default for ServerConfig {
+    #[allow(deprecated)]
+    fn default() -> Self {
+        Self {
+            address: String::from("127.0.0.1:8000"),
+            url: None,
+            workers: None,
+            max_content_length: Byte::from_u64(10_000_000),
+            upload_path: PathBuf::from("./upload"),
+            timeout: None,
+            shutdown_timeout: None,
+            auth_token: None,
+            auth_tokens: None,
+            auth_failure_status: None,
+            auth_failure_body: None,
+            expose_version: None,
+            landing_page: None,
+            landing_page_content_type: None,
+            handle_spaces: None,
+            expose_list: None,
+            expose_metrics: None,
+            delete_tokens: None,
+            delete_token_secret: None,
+            rate_limit: None,
+            tls: None,
+            cors: None,
+            log_level: None,
+            access_log: None,
+            read_only: None,
+            on_oversize: OversizePolicy::default(),
+            not_found_page: None,
+            allowed_ips: Vec::new(),
+            blocked_ips: Vec::new(),
+            filter_reads: None,
+            trust_forwarded_for: None,
+            trusted_proxies: Vec::new(),
+            overflow_paths: Vec::new(),
+            overflow_threshold: None,
+            response_template: None,
+            security_headers: None,
+            cache_control: None,
+        }
+    }
+}
+
+/// Maximum number of workers.
+const MAX_WORKERS: usize = 1024;
+
+impl ServerConfig {
+    /// Returns the number of workers to start.
+    ///
+    /// Defaults to the number of logical CPUs if [`workers`](Self::workers) is not set or `0`.
+    /// Values greater than 1024 are clamped.
+    pub fn get_workers(&self) -> usize {
+        match self.workers {
+            Some(workers) if workers > MAX_WORKERS => {
+                warn!(
+                    "[server].workers ({}) is too large, using {} workers",
+                    workers, MAX_WORKERS
+                );
+                MAX_WORKERS
+            }
+            Some(workers) if workers > 0 => workers,
+            _ => std::thread::available_parallelism()
+                .map(|v| v.get())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Returns the upload path followed by the [`overflow_paths`](Self::overflow_paths).
+    pub fn get_upload_paths(&self) -> impl Iterator<Item = &Path> {
+        iter::once(self.upload_path.as_path())
+            .chain(self.overflow_paths.iter().map(PathBuf::as_path))
+    }
+
+    /// Restores the options that cannot be changed at runtime (e.g. the bind address)
+    /// from the current configuration.
+    ///
+    /// Returns the names of the options that are changed.
+    pub fn keep_static_options(&mut self, current: &ServerConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! keep {
+            ($($field:ident),+) => {
+                $(
+                    if serde_json::to_value(&self.$field).ok()
+                        != serde_json::to_value(&current.$field).ok()
+                    {
+                        changed.push(stringify!($field));
+                        self.$field = current.$field.clone();
+                    }
+                )+
+            };
+        }
+        keep!(
+            address,
+            workers,
+            timeout,
+            shutdown_timeout,
+            tls,
+            cors,
+            rate_limit,
+            log_level,
+            access_log,
+            allowed_ips,
+            blocked_ips,
+            filter_reads,
+            trusted_proxies,
+            on_oversize,
+            security_headers
+        );
+        changed
+    }
+}
+
+/// Deserializes the tokens from either a single string or an array of strings.
+fn deserialize_tokens<'de, D>(deserializer: D) -> Result<Option<HashSet<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Tokens {
+        Single(String),
+        Multiple(HashSet<String>),
+    }
+    Ok(
+        Option::<Tokens>::deserialize(deserializer)?.map(|tokens| match tokens {
+            Tokens::Single(token) => HashSet::from([token]),
+            Tokens::Multiple(tokens) => tokens,
+        }),
+    )
 }
 
 /// Enum representing different strategies for handling spaces in filenames.
@@ -87,6 +331,54 @@ impl SpaceHandlingConfig {
     }
 }
 
+/// Enum representing different strategies for handling file name conflicts.
+///
+/// Only applies to the file names that are not randomly generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictHandlingConfig {
+    /// Represents overwriting the existing file.
+    Overwrite,
+    /// Represents appending a numeric suffix to the file name (e.g., "file-1.txt").
+    Rename,
+    /// Represents rejecting the upload.
+    #[default]
+    Reject,
+}
+
+/// Enum representing different strategies for normalizing the case of filenames.
+///
+/// Only applies to the file names that are not randomly generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseNormalizationConfig {
+    /// Represents lowercasing the extension (e.g., "Report.PDF" to "Report.pdf").
+    Extension,
+    /// Represents lowercasing the whole filename (e.g., "Report.PDF" to "report.pdf").
+    All,
+}
+
+impl CaseNormalizationConfig {
+    /// Processes the given filename based on the specified case normalization strategy.
+    ///
+    /// The extension is everything after the first dot that is not at the start of the filename.
+    pub fn process_filename(&self, file_name: &str) -> String {
+        match self {
+            Self::Extension => {
+                let prefix_length = file_name.len() - file_name.trim_start_matches('.').len();
+                match file_name[prefix_length..].find('.') {
+                    Some(index) => {
+                        let (name, extension) = file_name.split_at(prefix_length + index);
+                        format!("{name}{}", extension.to_lowercase())
+                    }
+                    None => file_name.to_string(),
+                }
+            }
+            Self::All => file_name.to_lowercase(),
+        }
+    }
+}
+
 /// Landing page configuration.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct LandingPageConfig {
@@ -96,28 +388,511 @@ pub struct LandingPageConfig {
     pub file: Option<String>,
     /// Landing page content-type
     pub content_type: Option<String>,
+    /// Show the built-in page with the upload instructions and limits.
+    pub builtin: Option<bool>,
 }
 
 /// Paste configuration.
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct PasteConfig {
     /// Random URL configuration.
     pub random_url: Option<RandomURLConfig>,
     /// Default file extension.
     pub default_extension: String,
+    /// Replace the extension of the uploaded files with the detected one (if detected).
+    ///
+    /// The extension is inferred from the content only if there is no extension otherwise.
+    pub force_infer_extension: Option<bool>,
+    /// File name to use if it cannot be inferred from the upload.
+    pub default_filename: Option<String>,
+    /// File name to use for the uploads from the standard input (i.e. `-`).
+    pub stdin_filename: Option<String>,
+    /// Append a random suffix to the [`stdin_filename`](Self::stdin_filename) if the random URLs are disabled.
+    ///
+    /// Prevents the concurrent uploads from stdin from overwriting each other.
+    pub unique_stdin_filename: Option<bool>,
+    /// Type of the paste for the form fields that are not recognized (e.g. `file`).
+    ///
+    /// The unrecognized form fields are rejected if this is not set.
+    pub default_type: Option<PasteType>,
     /// Media type override options.
-    #[serde(default)]
+    #[serde(default, alias = "mime_overrides")]
     pub mime_override: Vec<MimeMatcher>,
+    /// Allowed file extensions.
+    #[serde(default)]
+    pub allowed_extensions: Vec<String>,
+    /// Media type whitelist.
+    #[serde(default)]
+    pub mime_whitelist: Vec<String>,
+    /// Allowed schemes of the shortened URLs.
+    #[serde(default)]
+    pub allowed_url_schemes: Vec<String>,
+    /// Prepend `https://` to the shortened URLs without a scheme.
+    pub prepend_url_scheme: Option<bool>,
+    /// Maximum length of the shortened URLs.
+    pub max_url_length: Option<usize>,
+    /// Maximum number of the URLs that can be shortened in a single batch request.
+    pub max_batch_size: Option<usize>,
     /// Media type blacklist.
     #[serde(default)]
     pub mime_blacklist: Vec<String>,
     /// Allow duplicate uploads.
     pub duplicate_files: Option<bool>,
+    /// Allow uploading empty files.
+    pub allow_empty: Option<bool>,
+    /// File names that cannot be uploaded or generated (e.g. the names of the routes).
+    pub reserved_names: Vec<String>,
+    /// Handle the uploads with existing file names.
+    pub handle_conflicts: Option<ConflictHandlingConfig>,
+    /// Normalize the case of the file names.
+    pub normalize_case: Option<CaseNormalizationConfig>,
+    /// Number of characters of the file name to use for the shard directory.
+    pub shard_length: Option<usize>,
+    /// Maximum length of the file names in bytes.
+    pub max_filename_length: Option<usize>,
+    /// Truncate the file names that exceed [`max_filename_length`](Self::max_filename_length)
+    /// while keeping the extension, instead of rejecting them.
+    pub truncate_long_filenames: Option<bool>,
+    /// Store the metadata of the pastes in sidecar files.
+    pub store_metadata: Option<bool>,
+    /// Return the original file names of the uploads when serving them.
+    ///
+    /// The original file names are stored in the metadata (regardless of `store_metadata`).
+    pub preserve_original_name: Option<bool>,
+    /// Maximum size of the files that can be syntax highlighted.
+    pub max_highlight_size: Option<Byte>,
+    /// Maximum size of the Markdown files that can be rendered as HTML.
+    pub max_render_size: Option<Byte>,
     /// Default expiry time.
     #[serde(default, with = "humantime_serde")]
     pub default_expiry: Option<Duration>,
+    /// Default expiry times of the paste types (overrides [`default_expiry`](Self::default_expiry)).
+    pub retention: Option<RetentionConfig>,
     /// Delete expired files.
     pub delete_expired_files: Option<CleanupConfig>,
+    /// Time after which the incomplete resumable uploads are removed.
+    #[serde(default, with = "humantime_serde")]
+    pub resumable_upload_timeout: Option<Duration>,
+}
+
+impl PasteConfig {
+    /// Checks if the file name is one of the [`reserved_names`](Self::reserved_names).
+    pub fn is_reserved(&self, file_name: &str) -> bool {
+        self.reserved_names
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(file_name))
+    }
+
+    /// Returns the default expiry time of the paste type.
+    ///
+    /// Falls back to [`default_expiry`](Self::default_expiry) if the [`retention`](Self::retention)
+    /// of the type is not set.
+    pub fn get_default_expiry(&self, paste_type: PasteType) -> Option<Duration> {
+        let retention = self.retention.as_ref().and_then(|v| match paste_type {
+            PasteType::File | PasteType::RemoteFile => v.file,
+            PasteType::Oneshot => v.oneshot,
+            PasteType::Url => v.url,
+            PasteType::OneshotUrl => v.oneshot_url,
+        });
+        match retention {
+            Some(retention) if retention.is_zero() => None,
+            Some(retention) => Some(retention),
+            None => self.default_expiry,
+        }
+    }
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            random_url: None,
+            default_extension: String::from("txt"),
+            force_infer_extension: None,
+            default_filename: None,
+            stdin_filename: None,
+            unique_stdin_filename: None,
+            default_type: None,
+            mime_override: Vec::new(),
+            allowed_extensions: Vec::new(),
+            mime_whitelist: Vec::new(),
+            allowed_url_schemes: Vec::new(),
+            prepend_url_scheme: None,
+            max_url_length: None,
+            max_batch_size: None,
+            mime_blacklist: Vec::new(),
+            duplicate_files: None,
+            allow_empty: None,
+            reserved_names: [
+                "admin", "health", "list", "metrics", "uploads", "urls", "version",
+            ]
+            .map(String::from)
+            .to_vec(),
+            handle_conflicts: None,
+            normalize_case: None,
+            shard_length: None,
+            max_filename_length: None,
+            truncate_long_filenames: None,
+            store_metadata: None,
+            preserve_original_name: None,
+            max_highlight_size: None,
+            max_render_size: None,
+            default_expiry: None,
+            retention: None,
+            delete_expired_files: None,
+            resumable_upload_timeout: None,
+        }
+    }
+}
+
+/// Auth token with its own upload policy.
+///
+/// The options that are not set are inherited from the global configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TokenConfig {
+    /// The auth token.
+    pub token: String,
+    /// Maximum size of the uploaded files.
+    pub max_content_length: Option<Byte>,
+    /// Allowed file extensions.
+    pub allowed_extensions: Option<Vec<String>>,
+    /// Allow uploading oneshot files and URLs.
+    pub allow_oneshot: Option<bool>,
+    /// Allow uploading expiring files.
+    pub allow_expiry: Option<bool>,
+    /// Skip the virus scan of the uploads.
+    pub skip_virus_scan: Option<bool>,
+}
+
+impl TokenConfig {
+    /// Applies the policy of the token to the given configuration.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(max_content_length) = self.max_content_length {
+            config.server.max_content_length = max_content_length;
+        }
+        if let Some(allowed_extensions) = &self.allowed_extensions {
+            config.paste.allowed_extensions = allowed_extensions.clone();
+        }
+        if self.skip_virus_scan.unwrap_or(false) {
+            if let Some(security) = &mut config.security {
+                security.clamav = None;
+            }
+        }
+    }
+}
+
+/// TLS configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TlsConfig {
+    /// Path of the PEM encoded certificate chain.
+    pub cert_path: PathBuf,
+    /// Path of the PEM encoded private key.
+    pub key_path: PathBuf,
+}
+
+/// Security headers configuration.
+///
+/// The default values are used for the headers that are not set
+/// and the headers with an empty value are not sent.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SecurityHeadersConfig {
+    /// `Content-Security-Policy` header of the HTML and SVG files.
+    ///
+    /// Defaults to [`DEFAULT_CONTENT_SECURITY_POLICY`](Self::DEFAULT_CONTENT_SECURITY_POLICY).
+    pub content_security_policy: Option<String>,
+    /// `X-Content-Type-Options` header of the responses.
+    ///
+    /// Defaults to `nosniff`.
+    pub content_type_options: Option<String>,
+    /// `X-Frame-Options` header of the responses.
+    ///
+    /// Defaults to `DENY`.
+    pub frame_options: Option<String>,
+    /// Serve the HTML and SVG files as attachments instead of displaying them in the browser.
+    pub force_download: Option<bool>,
+}
+
+impl SecurityHeadersConfig {
+    /// Default value of [`content_security_policy`](Self::content_security_policy).
+    ///
+    /// Blocks the scripts, forms and external resources of the files.
+    pub const DEFAULT_CONTENT_SECURITY_POLICY: &'static str =
+        "default-src 'none'; img-src 'self' data:; media-src 'self'; style-src 'unsafe-inline'; sandbox";
+
+    /// Default value of [`content_type_options`](Self::content_type_options).
+    pub const DEFAULT_CONTENT_TYPE_OPTIONS: &'static str = "nosniff";
+
+    /// Default value of [`frame_options`](Self::frame_options).
+    pub const DEFAULT_FRAME_OPTIONS: &'static str = "DENY";
+
+    /// Returns the problems of the configuration.
+    pub fn validate(&self) -> Vec<String> {
+        [
+            ("content_security_policy", &self.content_security_policy),
+            ("content_type_options", &self.content_type_options),
+            ("frame_options", &self.frame_options),
+        ]
+        .into_iter()
+        .filter(|(_, value)| {
+            value
+                .as_deref()
+                .is_some_and(|v| HeaderValue::from_str(v).is_err())
+        })
+        .map(|(name, _)| format!("[server].security_headers.{name} is not a valid header value"))
+        .collect()
+    }
+}
+
+/// `Cache-Control` configuration of the served files.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CacheControlConfig {
+    /// Header value of the files (e.g. `public, max-age=3600`).
+    ///
+    /// The header is not sent for the files without a matching value.
+    pub default: Option<String>,
+    /// Header values of the files with the matching media types (e.g. `"image/*"`).
+    ///
+    /// The exact media types take precedence over the wildcards.
+    #[serde(default)]
+    pub content_types: HashMap<String, String>,
+    /// Header value of the files that are named after the digest of their content.
+    ///
+    /// Defaults to [`DEFAULT_CONTENT_ADDRESSED`](Self::DEFAULT_CONTENT_ADDRESSED).
+    pub content_addressed: Option<String>,
+    /// Header value of the oneshot files.
+    ///
+    /// Defaults to `no-store`.
+    pub oneshot: Option<String>,
+}
+
+impl CacheControlConfig {
+    /// Default value of [`content_addressed`](Self::content_addressed).
+    pub const DEFAULT_CONTENT_ADDRESSED: &'static str = "public, max-age=31536000, immutable";
+
+    /// Default value of [`oneshot`](Self::oneshot).
+    pub const DEFAULT_ONESHOT: &'static str = "no-store";
+
+    /// Returns the header value for the served file.
+    pub fn get(
+        &self,
+        content_type: &str,
+        is_oneshot: bool,
+        is_content_addressed: bool,
+    ) -> Option<&str> {
+        if is_oneshot {
+            return Some(self.oneshot.as_deref().unwrap_or(Self::DEFAULT_ONESHOT));
+        }
+        if is_content_addressed {
+            return Some(
+                self.content_addressed
+                    .as_deref()
+                    .unwrap_or(Self::DEFAULT_CONTENT_ADDRESSED),
+            );
+        }
+        self.content_types
+            .iter()
+            .filter(|(pattern, _)| mime_util::matches_mime_type(pattern, content_type))
+            .max_by_key(|(pattern, _)| match pattern.trim() {
+                "*" | "*/*" => 0,
+                v if v.ends_with("/*") => 1,
+                _ => 2,
+            })
+            .map(|(_, value)| value.as_str())
+            .or(self.default.as_deref())
+    }
+
+    /// Returns the problems of the configuration.
+    pub fn validate(&self) -> Vec<String> {
+        [
+            self.default.as_ref(),
+            self.content_addressed.as_ref(),
+            self.oneshot.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.content_types.values())
+        .filter(|v| HeaderValue::from_str(v).is_err())
+        .map(|v| format!("[server].cache_control contains an invalid header value: {v}"))
+        .collect()
+    }
+}
+
+/// CORS configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CorsConfig {
+    /// Allowed origins (`*` allows any origin).
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Allowed methods (`*` allows any method).
+    ///
+    /// Defaults to `GET`, `POST` and `DELETE`.
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+    /// Allowed request headers (`*` allows any header).
+    ///
+    /// Any header is allowed if not set.
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
+    /// Response headers that are exposed to the browser.
+    #[serde(default)]
+    pub expose_headers: Vec<String>,
+    /// Maximum time of caching the preflight responses.
+    #[serde(default, with = "humantime_serde")]
+    pub max_age: Option<Duration>,
+}
+
+impl CorsConfig {
+    /// Default value of [`allowed_methods`](Self::allowed_methods).
+    pub const DEFAULT_METHODS: [&'static str; 3] = ["GET", "POST", "DELETE"];
+
+    /// Returns the problems of the configuration.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.allowed_origins.is_empty() {
+            problems.push(String::from(
+                "[server].cors.allowed_origins must not be empty",
+            ));
+        }
+        for origin in self.allowed_origins.iter().filter(|v| *v != "*") {
+            let is_valid = url::Url::parse(origin)
+                .map(|url| url.has_host() && url.path() == "/" && !origin.ends_with('/'))
+                .unwrap_or(false);
+            if !is_valid {
+                problems.push(format!(
+                    "[server].cors.allowed_origins contains an invalid origin: {origin}"
+                ));
+            }
+        }
+        for method in self.allowed_methods.iter().filter(|v| *v != "*") {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                problems.push(format!(
+                    "[server].cors.allowed_methods contains an invalid method: {method}"
+                ));
+            }
+        }
+        for header in self
+            .allowed_headers
+            .iter()
+            .chain(self.expose_headers.iter())
+            .filter(|v| *v != "*")
+        {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                problems.push(format!(
+                    "[server].cors contains an invalid header: {header}"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// Rate limiting configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RateLimitConfig {
+    /// Maximum number of requests per client in a window.
+    pub requests: u32,
+    /// Duration of the window.
+    #[serde(default, with = "humantime_serde")]
+    pub window: Duration,
+    /// Use the client address from the `Forwarded`/`X-Forwarded-For` headers.
+    #[deprecated(note = "use [server].trusted_proxies instead")]
+    pub use_forwarded_for: Option<bool>,
+    /// Do not limit the `GET` and `HEAD` requests.
+    pub exempt_reads: Option<bool>,
+}
+
+/// IP address range in CIDR notation (e.g. `192.168.0.0/16` or `::1`).
+///
+/// The addresses without a prefix length match only themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNetwork {
+    /// Network address.
+    addr: IpAddr,
+    /// Prefix length.
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Checks if the given address is in the range.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against the IPv4 ranges.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid IP range: {s}");
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let addr = IpAddr::from_str(addr).map_err(|_| invalid())?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(invalid());
+        }
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for IpNetwork {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpNetwork> for String {
+    fn from(network: IpNetwork) -> Self {
+        network.to_string()
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Default expiry times of the paste types.
+///
+/// A zero duration (e.g. `"0s"`) means that the pastes of the type do not expire by default.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RetentionConfig {
+    /// Default expiry time of the files (including the remote files).
+    #[serde(default, with = "humantime_serde")]
+    pub file: Option<Duration>,
+    /// Default expiry time of the oneshot files.
+    #[serde(default, with = "humantime_serde")]
+    pub oneshot: Option<Duration>,
+    /// Default expiry time of the shortened URLs.
+    #[serde(default, with = "humantime_serde")]
+    pub url: Option<Duration>,
+    /// Default expiry time of the oneshot URLs.
+    #[serde(default, with = "humantime_serde")]
+    pub oneshot_url: Option<Duration>,
 }
 
 /// Cleanup configuration.
@@ -130,6 +905,134 @@ pub struct CleanupConfig {
     pub interval: Duration,
 }
 
+/// Storage configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct StorageConfig {
+    /// Type of the storage backend.
+    #[serde(rename = "type", default)]
+    pub type_: StorageType,
+    /// Name of the S3 bucket.
+    pub bucket: Option<String>,
+    /// Region of the S3 bucket.
+    pub region: Option<String>,
+    /// Custom S3 endpoint (e.g. for MinIO).
+    pub endpoint: Option<String>,
+    /// Access key of the S3 bucket.
+    pub access_key: Option<String>,
+    /// Secret key of the S3 bucket.
+    pub secret_key: Option<String>,
+    /// Use path-style URLs for the S3 bucket.
+    pub path_style: Option<bool>,
+    /// Hex encoded 256-bit key for encrypting the files at rest.
+    pub encryption_key: Option<String>,
+    /// Compression method of the stored files.
+    #[serde(default)]
+    pub compression: CompressionType,
+    /// Maximum total size of the stored files.
+    pub max_total_size: Option<Byte>,
+    /// Maximum number of the stored files.
+    pub max_files: Option<usize>,
+    /// Handling of the uploads that exceed the quota.
+    #[serde(default)]
+    pub quota_policy: QuotaPolicy,
+    /// Permissions of the uploaded files as an octal number (e.g. `"0640"`).
+    ///
+    /// Only applies to the local storage on Unix.
+    pub file_mode: Option<String>,
+}
+
+impl StorageConfig {
+    /// Parses the [`file_mode`](Self::file_mode).
+    pub fn get_file_mode(&self) -> Option<Result<u32, ParseIntError>> {
+        self.file_mode
+            .as_deref()
+            .map(|v| u32::from_str_radix(v.trim().trim_start_matches("0o"), 8))
+    }
+}
+
+/// Webhook configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct WebhookConfig {
+    /// URLs to send the upload and deletion events to.
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// Secret for signing the payloads with HMAC-SHA256.
+    pub secret: Option<String>,
+    /// Number of retries for the failed requests.
+    pub retries: Option<usize>,
+}
+
+/// Security configuration.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SecurityConfig {
+    /// Address of the ClamAV daemon for scanning the uploads.
+    ///
+    /// Either a TCP address (e.g. `127.0.0.1:3310`) or the path of a Unix socket.
+    pub clamav: Option<String>,
+    /// Maximum size of the files to scan, the larger files are stored without scanning.
+    pub clamav_max_size: Option<Byte>,
+    /// Timeout of the connection to the ClamAV daemon.
+    #[serde(default, with = "humantime_serde")]
+    pub clamav_timeout: Option<Duration>,
+}
+
+/// Handling of the uploads that exceed the maximum content length.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    /// Reject the upload.
+    #[default]
+    Reject,
+    /// Store the first bytes of the file up to the maximum content length.
+    Truncate,
+}
+
+/// Handling of the uploads that exceed the storage quota.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaPolicy {
+    /// Reject the upload.
+    #[default]
+    Reject,
+    /// Remove the oldest files until there is enough room for the upload.
+    Evict,
+}
+
+/// Type of the storage backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageType {
+    /// Store the files in the local filesystem.
+    #[default]
+    Local,
+    /// Store the files in an S3 compatible object storage.
+    S3,
+}
+
+/// Compression method of the stored files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    /// Store the files as is.
+    #[default]
+    None,
+    /// Compress the files with gzip.
+    Gzip,
+    /// Compress the files with Zstandard.
+    Zstd,
+}
+
+impl CompressionType {
+    /// Returns the name of the method as used in the `Content-Encoding` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "identity",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
 /// Type of access token.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum TokenType {
@@ -141,9 +1044,12 @@ pub enum TokenType {
 
 impl Config {
     /// Parses the config file and returns the values.
+    ///
+    /// The config file is optional, i.e. the environment variables and the defaults
+    /// are used if it does not exist.
     pub fn parse(path: &Path) -> Result<Config, ConfigError> {
         config::Config::builder()
-            .add_source(config::File::from(path))
+            .add_source(config::File::from(path).required(false))
             .add_source(config::Environment::default().separator("__"))
             .build()?
             .try_deserialize()
@@ -159,6 +1065,7 @@ impl Config {
                 if let Some(token) = &self.server.auth_token {
                     tokens.insert(token.to_string());
                 }
+                tokens.extend(self.tokens.iter().map(|v| v.token.to_string()));
                 if let Ok(env_token) = env::var(AUTH_TOKEN_ENV) {
                     tokens.insert(env_token);
                 }
@@ -179,6 +1086,263 @@ impl Config {
         Some(tokens).filter(|v| !v.is_empty())
     }
 
+    /// Returns the upload policy of the given auth token.
+    pub fn get_token_policy(&self, token: &str) -> Option<&TokenConfig> {
+        self.tokens
+            .iter()
+            .find(|v| !v.token.trim().is_empty() && v.token == token)
+    }
+
+    /// Returns the configured secret for per-file delete tokens.
+    pub fn get_delete_token_secret(&self) -> Option<&str> {
+        self.server
+            .delete_token_secret
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())
+    }
+
+    /// Returns a copy of the configuration with the tokens, keys and secrets redacted.
+    #[allow(deprecated)]
+    pub fn redacted(&self) -> Self {
+        let redact = |value: &mut Option<String>| {
+            if value.is_some() {
+                *value = Some(String::from(REDACTED));
+            }
+        };
+        let redact_tokens = |tokens: &mut Option<HashSet<String>>| {
+            if let Some(tokens) = tokens {
+                *tokens = HashSet::from([String::from(REDACTED)]);
+            }
+        };
+        let mut config = self.clone();
+        redact(&mut config.server.auth_token);
+        redact_tokens(&mut config.server.auth_tokens);
+        redact_tokens(&mut config.server.delete_tokens);
+        redact(&mut config.server.delete_token_secret);
+        for token in &mut config.tokens {
+            token.token = String::from(REDACTED);
+        }
+        if let Some(storage) = &mut config.storage {
+            redact(&mut storage.access_key);
+            redact(&mut storage.secret_key);
+            redact(&mut storage.encryption_key);
+        }
+        if let Some(webhooks) = &mut config.webhooks {
+            redact(&mut webhooks.secret);
+        }
+        config
+    }
+
+    /// Returns the configuration for storing a file with the given size in an overflow path.
+    ///
+    /// The first path that has room for the file (or the last one) becomes the upload path
+    /// and the rest of the paths are kept as the overflow paths.
+    /// The usage of the paths is cached (see [`stats::get_usage`]) and the size of the file
+    /// is added to the usage of the selected path.
+    ///
+    /// Returns `None` if the file fits in the upload path.
+    pub fn get_overflow_config(&self, size: u64) -> Option<Config> {
+        let threshold = self.server.overflow_threshold?.as_u64();
+        let paths = self.server.get_upload_paths().collect::<Vec<_>>();
+        let index = paths
+            .iter()
+            .position(|path| stats::get_usage(path).saturating_add(size) <= threshold)
+            .unwrap_or(paths.len() - 1);
+        stats::add_usage(paths[index], size);
+        if index == 0 {
+            return None;
+        }
+        let mut config = self.clone();
+        config.server.upload_path = paths[index].to_path_buf();
+        config.server.overflow_paths = paths
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .map(|(_, path)| path.to_path_buf())
+            .collect();
+        Some(config)
+    }
+
+    /// Checks the configuration for problems that would only show up at runtime.
+    ///
+    /// Returns an error that lists all of the found problems.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        if !self.server.upload_path.is_dir() {
+            problems.push(format!(
+                "[server].upload_path ({}) does not exist or is not a directory",
+                self.server.upload_path.display()
+            ));
+        } else if let Err(e) = util::check_writable(&self.server.upload_path) {
+            problems.push(format!(
+                "[server].upload_path ({}) is not writable: {e}",
+                self.server.upload_path.display()
+            ));
+        }
+        if self
+            .server
+            .auth_failure_status
+            .is_some_and(|v| v != 401 && v != 404)
+        {
+            problems.push(String::from(
+                "[server].auth_failure_status must be 401 or 404",
+            ));
+        }
+        for path in &self.server.overflow_paths {
+            if !path.is_dir() {
+                problems.push(format!(
+                    "[server].overflow_paths ({}) does not exist or is not a directory",
+                    path.display()
+                ));
+            } else if let Err(e) = util::check_writable(path) {
+                problems.push(format!(
+                    "[server].overflow_paths ({}) is not writable: {e}",
+                    path.display()
+                ));
+            }
+        }
+        if !self.server.overflow_paths.is_empty() {
+            if self.server.overflow_threshold.is_none() {
+                problems.push(String::from(
+                    "[server].overflow_threshold must be set for [server].overflow_paths",
+                ));
+            }
+            if self
+                .storage
+                .as_ref()
+                .is_some_and(|v| v.type_ != StorageType::Local)
+            {
+                problems.push(String::from(
+                    "[server].overflow_paths are only supported for the local storage",
+                ));
+            }
+        }
+        if self.server.max_content_length.as_u64() == 0 {
+            problems.push(String::from(
+                "[server].max_content_length must be greater than 0",
+            ));
+        }
+        for token in &self.tokens {
+            if token.token.trim().is_empty() {
+                problems.push(String::from("[[tokens]].token must not be empty"));
+            }
+            if token.max_content_length.map(|v| v.as_u64()) == Some(0) {
+                problems.push(String::from(
+                    "[[tokens]].max_content_length must be greater than 0",
+                ));
+            }
+        }
+        if let Some(rate_limit) = &self.server.rate_limit {
+            if rate_limit.requests == 0 || rate_limit.window.is_zero() {
+                problems.push(String::from(
+                    "[server].rate_limit.requests and [server].rate_limit.window must be greater than 0",
+                ));
+            }
+        }
+        if let Some(storage) = &self.storage {
+            if storage.max_total_size.map(|v| v.as_u64()) == Some(0) || storage.max_files == Some(0)
+            {
+                problems.push(String::from(
+                    "[storage].max_total_size and [storage].max_files must be greater than 0",
+                ));
+            }
+            if storage
+                .get_file_mode()
+                .is_some_and(|v| v.map_or(true, |mode| mode > 0o7777))
+            {
+                problems.push(String::from(
+                    "[storage].file_mode must be an octal number (e.g. \"0640\")",
+                ));
+            }
+            if (storage.max_total_size.is_some() || storage.max_files.is_some())
+                && storage.type_ != StorageType::Local
+            {
+                problems.push(String::from(
+                    "[storage].max_total_size and [storage].max_files are only supported for the local storage",
+                ));
+            }
+        }
+        if let Some(webhooks) = &self.webhooks {
+            for url in &webhooks.urls {
+                if !Url::parse(url).is_ok_and(|v| matches!(v.scheme(), "http" | "https")) {
+                    problems.push(format!("[webhooks].urls contains an invalid URL: {url}"));
+                }
+            }
+        }
+        if self
+            .security
+            .as_ref()
+            .and_then(|v| v.clamav.as_ref())
+            .is_some_and(|v| v.trim().is_empty())
+        {
+            problems.push(String::from("[security].clamav must not be empty"));
+        }
+        if let Some(log_level) = &self.server.log_level {
+            if let Err(e) = EnvFilter::try_new(log_level) {
+                problems.push(format!("[server].log_level is invalid: {e}"));
+            }
+        }
+        if self
+            .server
+            .response_template
+            .as_ref()
+            .is_some_and(|v| v.is_empty())
+        {
+            problems.push(String::from("[server].response_template must not be empty"));
+        }
+        if let Some(cors) = &self.server.cors {
+            problems.append(&mut cors.validate());
+        }
+        if let Some(security_headers) = &self.server.security_headers {
+            problems.append(&mut security_headers.validate());
+        }
+        if let Some(cache_control) = &self.server.cache_control {
+            problems.append(&mut cache_control.validate());
+        }
+        if let Some(random_url) = &self.paste.random_url {
+            problems.append(&mut random_url.validate());
+        }
+        for (key, file_name) in [
+            ("default_filename", &self.paste.default_filename),
+            ("stdin_filename", &self.paste.stdin_filename),
+        ] {
+            if let Some(file_name) = file_name {
+                if util::sanitize_filename(file_name).ok().as_ref() != Some(file_name) {
+                    problems.push(format!("[paste].{key} must be a valid file name"));
+                }
+            }
+        }
+        if self.paste.max_url_length == Some(0) {
+            problems.push(String::from(
+                "[paste].max_url_length must be greater than 0",
+            ));
+        }
+        if self.paste.max_filename_length == Some(0) {
+            problems.push(String::from(
+                "[paste].max_filename_length must be greater than 0",
+            ));
+        }
+        if let Some(cleanup_config) = &self.paste.delete_expired_files {
+            if cleanup_config.enabled && cleanup_config.interval.is_zero() {
+                problems.push(String::from(
+                    "[paste].delete_expired_files.interval must be greater than 0",
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Message(format!(
+                "invalid configuration:\n{}",
+                problems
+                    .iter()
+                    .map(|v| format!("- {v}"))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )))
+        }
+    }
+
     /// Print deprecation warnings.
     #[allow(deprecated)]
     pub fn warn_deprecation(&self) {
@@ -193,6 +1357,21 @@ impl Config {
                 "[server].landing_page_content_type is deprecated, please use [landing_page].content_type"
             );
         }
+        if self.server.trust_forwarded_for.is_some() {
+            warn!(
+                "[server].trust_forwarded_for is deprecated and ignored, please use [server].trusted_proxies"
+            );
+        }
+        if self
+            .server
+            .rate_limit
+            .as_ref()
+            .is_some_and(|v| v.use_forwarded_for.is_some())
+        {
+            warn!(
+                "[server].rate_limit.use_forwarded_for is deprecated and ignored, please use [server].trusted_proxies"
+            );
+        }
         if let Some(random_url) = &self.paste.random_url {
             if random_url.enabled.is_some() {
                 warn!(
@@ -207,6 +1386,7 @@ impl Config {
 mod tests {
     use super::*;
     use std::env;
+    use std::fs;
 
     #[test]
     fn test_parse_config() -> Result<(), ConfigError> {
@@ -217,6 +1397,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_config_defaults() -> Result<(), ConfigError> {
+        let config = Config::parse(Path::new("non_existent_config.toml"))?;
+        assert_eq!(PathBuf::from("./upload"), config.server.upload_path);
+        assert_eq!(10_000_000, config.server.max_content_length.as_u64());
+        assert_eq!("txt", config.paste.default_extension);
+
+        let config_path = env::current_dir()
+            .map_err(|e| ConfigError::Foreign(e.into()))?
+            .join("test_partial_config.toml");
+        fs::write(&config_path, "[paste]\nduplicate_files = false\n")
+            .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        let config = Config::parse(&config_path)?;
+        assert_eq!(Some(false), config.paste.duplicate_files);
+        assert_eq!("txt", config.paste.default_extension);
+        assert_eq!(PathBuf::from("./upload"), config.server.upload_path);
+        fs::remove_file(config_path).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_public_url() -> Result<(), ConfigError> {
+        let config_path = env::current_dir()
+            .map_err(|e| ConfigError::Foreign(e.into()))?
+            .join("test_public_url_config.toml");
+        fs::write(
+            &config_path,
+            "[server]\npublic_url = \"https://example.com\"\n",
+        )
+        .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        let config = Config::parse(&config_path)?;
+        assert_eq!(Some("https://example.com"), config.server.url.as_deref());
+        fs::remove_file(config_path).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        Ok(())
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_parse_deprecated_config() -> Result<(), ConfigError> {
@@ -232,6 +1448,159 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_validate_config() -> Result<(), ConfigError> {
+        let mut config = Config::default();
+        config.server.upload_path =
+            env::current_dir().map_err(|e| ConfigError::Foreign(e.into()))?;
+        config.server.max_content_length = Byte::from_u64(1);
+        config.validate()?;
+
+        config.server.upload_path = PathBuf::from("non_existent_upload_path");
+        config.server.max_content_length = Byte::from_u64(0);
+        config.paste.random_url = Some(RandomURLConfig {
+            words: Some(crate::random::WordCount::Fixed(0)),
+            ..RandomURLConfig::default()
+        });
+        config.webhooks = Some(WebhookConfig {
+            urls: vec![String::from("ftp://example.com")],
+            ..WebhookConfig::default()
+        });
+        config.paste.stdin_filename = Some(String::from("../stdin"));
+        config.paste.max_url_length = Some(0);
+        config.paste.max_filename_length = Some(0);
+        config.server.auth_failure_status = Some(403);
+        config.storage = Some(StorageConfig {
+            file_mode: Some(String::from("0999")),
+            ..StorageConfig::default()
+        });
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(error.contains("[server].upload_path (non_existent_upload_path) does not exist"));
+        assert!(error.contains("[server].max_content_length must be greater than 0"));
+        assert!(error.contains("[paste].random_url.words must be greater than 0"));
+        assert!(error.contains("[webhooks].urls contains an invalid URL: ftp://example.com"));
+        assert!(error.contains("[paste].stdin_filename must be a valid file name"));
+        assert!(error.contains("[paste].max_url_length must be greater than 0"));
+        assert!(error.contains("[paste].max_filename_length must be greater than 0"));
+        assert!(error.contains("[server].auth_failure_status must be 401 or 404"));
+        assert!(error.contains("[storage].file_mode must be an octal number"));
+        config.webhooks = None;
+        config.storage = None;
+        config.paste.stdin_filename = None;
+        config.paste.max_url_length = None;
+        config.paste.max_filename_length = None;
+        config.server.auth_failure_status = None;
+
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: crate::random::RandomURLType::Alphanumeric,
+            charset: Some(String::from("0O1lI")),
+            exclude_ambiguous: Some(true),
+            ..RandomURLConfig::default()
+        });
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(error.contains("does not result in any characters"));
+
+        config.server.cors = Some(CorsConfig {
+            allowed_origins: vec![
+                String::from("*"),
+                String::from("https://example.com"),
+                String::from("example.com/path"),
+            ],
+            allowed_methods: vec![String::from("GE T")],
+            allowed_headers: vec![String::from("x-filename")],
+            ..CorsConfig::default()
+        });
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(!error.contains("invalid origin: https://example.com"));
+        assert!(error.contains("invalid origin: example.com/path"));
+        assert!(error.contains("invalid method: GE T"));
+        assert!(!error.contains("invalid header"));
+
+        config.server.security_headers = Some(SecurityHeadersConfig {
+            frame_options: Some(String::from("DENY\n")),
+            ..SecurityHeadersConfig::default()
+        });
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(
+            error.contains("[server].security_headers.frame_options is not a valid header value")
+        );
+
+        config.server.cache_control = Some(CacheControlConfig {
+            oneshot: Some(String::from("no-store\r\n")),
+            ..CacheControlConfig::default()
+        });
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(error.contains("[server].cache_control contains an invalid header value"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_single_token() -> Result<(), ConfigError> {
+        let config_path = env::temp_dir().join("rustypaste_single_token.toml");
+        fs::write(
+            &config_path,
+            "[server]\n\
+             address = \"127.0.0.1:8000\"\n\
+             max_content_length = \"10MB\"\n\
+             upload_path = \"./upload\"\n\
+             auth_tokens = \"single_token\"\n\
+             delete_tokens = [\"first_token\", \"second_token\"]\n\
+             [paste]\n\
+             default_extension = \"txt\"\n",
+        )
+        .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        let config = Config::parse(&config_path)?;
+        assert_eq!(
+            Some(HashSet::from(["single_token".to_string()])),
+            config.server.auth_tokens
+        );
+        assert_eq!(
+            Some(HashSet::from([
+                "first_token".to_string(),
+                "second_token".to_string()
+            ])),
+            config.server.delete_tokens
+        );
+        fs::remove_file(config_path).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_normalization() {
+        let extension = CaseNormalizationConfig::Extension;
+        assert_eq!("Report.pdf", extension.process_filename("Report.PDF"));
+        assert_eq!(
+            "Archive.tar.gz",
+            extension.process_filename("Archive.TAR.GZ")
+        );
+        assert_eq!(".Bashrc.bak", extension.process_filename(".Bashrc.BAK"));
+        assert_eq!("README", extension.process_filename("README"));
+        assert_eq!(
+            "report.pdf",
+            CaseNormalizationConfig::All.process_filename("Report.PDF")
+        );
+    }
+
     #[test]
     fn test_space_handling() {
         let processed_filename =
@@ -241,6 +1610,67 @@ mod tests {
         assert_eq!("file%20with%20spaces.txt", encoded_filename);
     }
 
+    #[test]
+    fn test_get_workers() {
+        let cpus = std::thread::available_parallelism()
+            .map(|v| v.get())
+            .unwrap_or(1);
+        let mut config = ServerConfig::default();
+        assert_eq!(cpus, config.get_workers());
+        config.workers = Some(0);
+        assert_eq!(cpus, config.get_workers());
+        config.workers = Some(4);
+        assert_eq!(4, config.get_workers());
+        config.workers = Some(usize::MAX);
+        assert_eq!(MAX_WORKERS, config.get_workers());
+    }
+
+    #[test]
+    fn test_keep_static_options() {
+        let current = ServerConfig::default();
+        let mut config = ServerConfig {
+            address: String::from("0.0.0.0:8080"),
+            workers: Some(2),
+            read_only: Some(true),
+            on_oversize: OversizePolicy::Truncate,
+            ..ServerConfig::default()
+        };
+        assert_eq!(
+            vec!["address", "workers", "on_oversize"],
+            config.keep_static_options(&current)
+        );
+        assert_eq!(current.address, config.address);
+        assert_eq!(None, config.workers);
+        assert_eq!(OversizePolicy::Reject, config.on_oversize);
+        assert_eq!(Some(true), config.read_only);
+        assert!(config.keep_static_options(&current).is_empty());
+    }
+
+    #[test]
+    fn test_ip_network() -> Result<(), String> {
+        let ip = |v: &str| v.parse::<IpAddr>().map_err(|e| e.to_string());
+        let network = IpNetwork::from_str("192.168.0.0/16")?;
+        assert!(network.contains(ip("192.168.1.1")?));
+        assert!(network.contains(ip("::ffff:192.168.1.1")?));
+        assert!(!network.contains(ip("192.169.1.1")?));
+        assert!(!network.contains(ip("::1")?));
+
+        let network = IpNetwork::from_str("2001:db8::/32")?;
+        assert!(network.contains(ip("2001:db8::1")?));
+        assert!(!network.contains(ip("2001:db9::1")?));
+
+        let network = IpNetwork::from_str("127.0.0.1")?;
+        assert_eq!("127.0.0.1/32", network.to_string());
+        assert!(network.contains(ip("127.0.0.1")?));
+        assert!(!network.contains(ip("127.0.0.2")?));
+        assert!(IpNetwork::from_str("0.0.0.0/0")?.contains(ip("10.0.0.1")?));
+
+        assert!(IpNetwork::from_str("10.0.0.0/33").is_err());
+        assert!(IpNetwork::from_str("10.0.0/8").is_err());
+        assert!(IpNetwork::from_str("localhost").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_get_tokens() -> Result<(), ConfigError> {
         let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config.toml");
@@ -276,4 +1706,59 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_token_policy() {
+        let mut config = Config::default();
+        config.server.max_content_length = Byte::from_u64(100);
+        config.paste.allowed_extensions = vec![String::from("txt")];
+        config.tokens = vec![
+            TokenConfig {
+                token: String::from("limited"),
+                max_content_length: Some(Byte::from_u64(10)),
+                allowed_extensions: Some(vec![String::from("png")]),
+                ..TokenConfig::default()
+            },
+            TokenConfig {
+                token: String::from("default"),
+                ..TokenConfig::default()
+            },
+        ];
+        assert_eq!(
+            Some(HashSet::from([
+                "limited".to_string(),
+                "default".to_string()
+            ])),
+            config.get_tokens(TokenType::Auth)
+        );
+        assert!(config.get_token_policy("unknown").is_none());
+
+        let mut request_config = config.clone();
+        if let Some(policy) = config.get_token_policy("limited") {
+            policy.apply(&mut request_config);
+        }
+        assert_eq!(10, request_config.server.max_content_length.as_u64());
+        assert_eq!(vec!["png"], request_config.paste.allowed_extensions);
+
+        let mut request_config = config.clone();
+        if let Some(policy) = config.get_token_policy("default") {
+            policy.apply(&mut request_config);
+        }
+        assert_eq!(100, request_config.server.max_content_length.as_u64());
+        assert_eq!(vec!["txt"], request_config.paste.allowed_extensions);
+
+        config.tokens = vec![TokenConfig {
+            token: String::from(" "),
+            max_content_length: Some(Byte::from_u64(0)),
+            ..TokenConfig::default()
+        }];
+        assert!(config.get_token_policy(" ").is_none());
+        let error = config
+            .validate()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(error.contains("[[tokens]].token must not be empty"));
+        assert!(error.contains("[[tokens]].max_content_length must be greater than 0"));
+    }
 }
@@ -22,27 +22,54 @@ pub struct Directory {
 
 impl<'a> TryFrom<&'a Path> for Directory {
     type Error = ActixError;
+    /// Collects the files in the given directory and its [`shard directories`].
+    ///
+    /// Other subdirectories (e.g. the ones used for URLs and oneshot files) are not traversed.
+    ///
+    /// [`shard directories`]: util::get_shard_dirs
     fn try_from(directory: &'a Path) -> Result<Self, Self::Error> {
-        let files = glob(directory.join("**").join("*").to_str().ok_or_else(|| {
-            error::ErrorInternalServerError("directory contains invalid characters")
-        })?)
-        .map_err(error::ErrorInternalServerError)?
-        .filter_map(Result::ok)
-        .filter(|path| !path.is_dir())
-        .filter_map(|path| match OsFile::open(&path) {
-            Ok(file) => Some((path, file)),
-            _ => None,
-        })
-        .filter_map(|(path, file)| match util::sha256_digest(file) {
-            Ok(sha256sum) => Some(File { path, sha256sum }),
-            _ => None,
-        })
-        .collect();
+        let mut directories = util::get_shard_dirs(directory);
+        directories.insert(0, directory.to_path_buf());
+        let files = directories
+            .iter()
+            .map(|directory| {
+                glob(directory.join("*").to_str().ok_or_else(|| {
+                    error::ErrorInternalServerError("directory contains invalid characters")
+                })?)
+                .map_err(error::ErrorInternalServerError)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|path| !path.is_dir())
+            .filter_map(|path| match OsFile::open(&path) {
+                Ok(file) => Some((path, file)),
+                _ => None,
+            })
+            .filter_map(|(path, file)| match util::sha256_digest(file) {
+                Ok(sha256sum) => Some(File { path, sha256sum }),
+                _ => None,
+            })
+            .collect();
         Ok(Self { files })
     }
 }
 
 impl Directory {
+    /// Returns the file that matches the given checksum in any of the given directories.
+    pub fn find_file<'a, S: AsRef<str>>(
+        directories: impl IntoIterator<Item = &'a Path>,
+        sha256sum: S,
+    ) -> Result<Option<File>, ActixError> {
+        for directory in directories {
+            if let Some(file) = Self::try_from(directory)?.get_file(sha256sum.as_ref()) {
+                return Ok(Some(file));
+            }
+        }
+        Ok(None)
+    }
+
     /// Returns the file that matches the given checksum.
     pub fn get_file<S: AsRef<str>>(self, sha256sum: S) -> Option<File> {
         self.files.into_iter().find(|file| {
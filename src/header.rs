@@ -1,5 +1,6 @@
 use actix_web::http::header::{
     ContentDisposition as ActixContentDisposition, DispositionParam, DispositionType, HeaderMap,
+    IF_NONE_MATCH,
 };
 use actix_web::{error, Error as ActixError};
 use std::time::Duration;
@@ -8,19 +9,92 @@ use std::time::Duration;
 pub const EXPIRE: &str = "expire";
 
 /// Custom HTTP header to override filename.
-const FILENAME: &str = "filename";
+pub const FILENAME: &str = "filename";
+
+/// Custom HTTP header for per-file delete tokens.
+pub const DELETE_TOKEN: &str = "delete-token";
+
+/// Custom HTTP header for password protected pastes.
+pub const PASSWORD: &str = "x-password";
+
+/// Custom HTTP header for the length of the random URL.
+pub const URL_LENGTH: &str = "x-url-length";
+
+/// Custom HTTP header for the original file name of the served file.
+pub const ORIGINAL_FILENAME: &str = "x-original-filename";
+
+/// Custom HTTP header for validating the uploads without storing them.
+pub const DRY_RUN: &str = "x-dry-run";
+
+/// Custom HTTP header for the private pastes that require the auth token for reading.
+pub const PRIVATE: &str = "x-private";
+
+/// Header for the names of the files that are truncated due to exceeding the upload limit.
+pub const TRUNCATED: &str = "x-truncated";
+
+/// Custom HTTP header for the maximum size of the uploads in bytes.
+pub const MAX_CONTENT_LENGTH: &str = "x-max-content-length";
+
+/// Custom HTTP header for the allowed file extensions of the uploads.
+pub const ALLOWED_EXTENSIONS: &str = "x-allowed-extensions";
+
+/// Custom HTTP header for the allowed media types of the uploads.
+pub const ALLOWED_MIME_TYPES: &str = "x-allowed-mime-types";
+
+/// Custom HTTP header for the SHA256 checksum of the uploaded file.
+pub const CONTENT_SHA256: &str = "x-content-sha256";
+
+/// HTTP header for the total size of a resumable upload.
+pub const UPLOAD_LENGTH: &str = "upload-length";
+
+/// HTTP header for the number of the received bytes of a resumable upload.
+pub const UPLOAD_OFFSET: &str = "upload-offset";
+
+/// Parses the byte count of a resumable upload from the given header.
+pub fn parse_upload_size(headers: &HeaderMap, name: &str) -> Result<Option<u64>, ActixError> {
+    headers
+        .get(name)
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .ok_or_else(|| error::ErrorBadRequest(format!("invalid {name} header\n")))
+        })
+        .transpose()
+}
 
 /// Parses the expiry date from the [`custom HTTP header`](EXPIRE).
 pub fn parse_expiry_date(headers: &HeaderMap, time: Duration) -> Result<Option<u128>, ActixError> {
     if let Some(expire_time) = headers.get(EXPIRE).and_then(|v| v.to_str().ok()) {
-        let expire_time =
-            humantime::parse_duration(expire_time).map_err(error::ErrorInternalServerError)?;
-        Ok(time.checked_add(expire_time).map(|t| t.as_millis()))
+        parse_expiry_time(expire_time, time)
     } else {
         Ok(None)
     }
 }
 
+/// Parses the expiry date from a human-readable duration (e.g. `30m`, `2h`, `1d`, `1w`).
+///
+/// Values without a unit are treated as seconds.
+pub fn parse_expiry_time(expire_time: &str, time: Duration) -> Result<Option<u128>, ActixError> {
+    let expire_time = parse_duration(expire_time)?;
+    time.checked_add(expire_time)
+        .map(|t| Some(t.as_millis()))
+        .ok_or_else(|| error::ErrorBadRequest("expiry time is too large\n"))
+}
+
+/// Parses a human-readable duration.
+fn parse_duration(duration: &str) -> Result<Duration, ActixError> {
+    let duration = duration.trim();
+    if let Ok(seconds) = duration.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    humantime::parse_duration(duration).map_err(|e| {
+        error::ErrorBadRequest(format!(
+            "invalid expiry time \"{duration}\": {e} (e.g. 30m, 2h, 1d, 1w)\n"
+        ))
+    })
+}
+
 /// Parses the filename from the header.
 pub fn parse_header_filename(headers: &HeaderMap) -> Result<Option<String>, ActixError> {
     if let Some(file_name) = headers.get(FILENAME).and_then(|v| v.to_str().ok()) {
@@ -30,6 +104,72 @@ pub fn parse_header_filename(headers: &HeaderMap) -> Result<Option<String>, Acti
     }
 }
 
+/// Parses the requested length of the random URL from the [`custom HTTP header`](URL_LENGTH).
+pub fn parse_url_length(headers: &HeaderMap) -> Result<Option<usize>, ActixError> {
+    headers
+        .get(URL_LENGTH)
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .ok_or_else(|| error::ErrorBadRequest("invalid URL length\n"))
+        })
+        .transpose()
+}
+
+/// Parses the [`dry run header`](DRY_RUN) (`true` or `false`).
+pub fn parse_dry_run(headers: &HeaderMap) -> Result<bool, ActixError> {
+    match headers.get(DRY_RUN).map(|v| v.to_str().map(str::trim)) {
+        None => Ok(false),
+        Some(Ok(v)) if v.eq_ignore_ascii_case("true") => Ok(true),
+        Some(Ok(v)) if v.eq_ignore_ascii_case("false") => Ok(false),
+        Some(_) => Err(error::ErrorBadRequest("invalid dry run value\n")),
+    }
+}
+
+/// Parses the [`private header`](PRIVATE) (`true` or `false`).
+pub fn parse_private(headers: &HeaderMap) -> Result<bool, ActixError> {
+    match headers.get(PRIVATE).map(|v| v.to_str().map(str::trim)) {
+        None => Ok(false),
+        Some(Ok(v)) if v.eq_ignore_ascii_case("true") => Ok(true),
+        Some(Ok(v)) if v.eq_ignore_ascii_case("false") => Ok(false),
+        Some(_) => Err(error::ErrorBadRequest("invalid private value\n")),
+    }
+}
+
+/// Parses the hexadecimal SHA256 checksum from the [`custom HTTP header`](CONTENT_SHA256).
+///
+/// The checksum is returned in lowercase.
+pub fn parse_content_sha256(headers: &HeaderMap) -> Result<Option<String>, ActixError> {
+    headers
+        .get(CONTENT_SHA256)
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .map(str::trim)
+                .filter(|v| v.len() == 64 && v.chars().all(|c| c.is_ascii_hexdigit()))
+                .map(str::to_ascii_lowercase)
+                .ok_or_else(|| error::ErrorBadRequest("invalid SHA256 checksum\n"))
+        })
+        .transpose()
+}
+
+/// Parses the `If-None-Match` header of an upload.
+///
+/// Returns `true` for `If-None-Match: *`, which means the existing files must not be overwritten.
+pub fn parse_create_new(headers: &HeaderMap) -> Result<bool, ActixError> {
+    match headers
+        .get(IF_NONE_MATCH)
+        .map(|v| v.to_str().map(str::trim))
+    {
+        None => Ok(false),
+        Some(Ok("*")) => Ok(true),
+        Some(_) => Err(error::ErrorBadRequest(
+            "only \"If-None-Match: *\" is supported for uploads\n",
+        )),
+    }
+}
+
 /// Wrapper for Actix content disposition header.
 ///
 /// Aims to parse the file data from multipart body.
@@ -58,6 +198,11 @@ impl ContentDisposition {
                 .contains(&DispositionParam::Name(field_name.to_string()))
     }
 
+    /// Returns the name of the form field if it exists.
+    pub fn get_field_name(&self) -> Option<&str> {
+        self.inner.get_name()
+    }
+
     /// Parses the file name from parameters if it exists.
     pub fn get_file_name(&self) -> Result<&str, ActixError> {
         self.inner
@@ -75,6 +220,7 @@ mod tests {
     use super::*;
     use crate::util;
     use actix_web::http::header::{HeaderName, HeaderValue};
+    use actix_web::http::StatusCode;
     use std::thread;
 
     #[test]
@@ -89,6 +235,7 @@ mod tests {
         let content_disposition = ContentDisposition::from(actix_content_disposition);
         assert!(content_disposition.has_form_field("file"));
         assert!(!content_disposition.has_form_field("test"));
+        assert_eq!(Some("file"), content_disposition.get_field_name());
         assert_eq!("x.txt", content_disposition.get_file_name()?);
 
         let actix_content_disposition = ActixContentDisposition {
@@ -113,6 +260,26 @@ mod tests {
         assert!(expiry_time > util::get_system_time()?.as_millis());
         thread::sleep(Duration::from_millis(10));
         assert!(expiry_time < util::get_system_time()?.as_millis());
+
+        let time = util::get_system_time()?;
+        assert_eq!(
+            Some(time.as_millis() + 60_000),
+            parse_expiry_time("1min", time)?
+        );
+        assert_eq!(
+            Some(time.as_millis() + 3_600_000),
+            parse_expiry_time("3600", time)?
+        );
+        assert_eq!(
+            Some(time.as_millis() + 604_800_000),
+            parse_expiry_time("1w", time)?
+        );
+        let error = parse_expiry_time("invalid", time).expect_err("invalid expiry time");
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            error.as_response_error().status_code()
+        );
+        assert!(parse_expiry_time("1000000000000000000000d", time).is_err());
         Ok(())
     }
 }
@@ -0,0 +1,65 @@
+use lazy_regex::Lazy;
+use std::path::Path;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+/// Syntax definitions for detecting the language.
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+/// Theme of the highlighted HTML.
+static THEME: Lazy<Theme> = Lazy::new(|| {
+    ThemeSet::load_defaults()
+        .themes
+        .remove("InspiredGitHub")
+        .unwrap_or_default()
+});
+
+/// Checks if the data looks like text that can be highlighted.
+pub fn is_text(data: &[u8]) -> bool {
+    !data.contains(&0) && std::str::from_utf8(data).is_ok()
+}
+
+/// Renders the text as a syntax highlighted HTML page.
+///
+/// The language is detected from the extension of the file name.
+pub fn highlight(file_name: &str, text: &str) -> Result<String, syntect::Error> {
+    let syntax = Path::new(file_name)
+        .extension()
+        .and_then(|v| v.to_str())
+        .and_then(|v| SYNTAX_SET.find_syntax_by_extension(v))
+        .or_else(|| SYNTAX_SET.find_syntax_by_first_line(text))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+    let body = highlighted_html_for_string(text, &SYNTAX_SET, syntax, &THEME)?;
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(file_name),
+        body
+    ))
+}
+
+/// Escapes the special HTML characters.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() -> Result<(), syntect::Error> {
+        assert!(is_text(b"fn main() {}"));
+        assert!(!is_text(&[0x00, 0x01]));
+        assert!(!is_text(&[0xff, 0xfe]));
+
+        let html = highlight("<main>.rs", "fn main() {}\n")?;
+        assert!(html.contains("<title>&lt;main&gt;.rs</title>"));
+        assert!(html.contains("<pre"));
+        assert!(html.contains("main"));
+        Ok(())
+    }
+}
@@ -19,18 +19,61 @@ pub mod auth;
 /// Storage handler.
 pub mod paste;
 
+/// Storage backends.
+pub mod storage;
+
 /// File metadata handler.
 pub mod file;
 
+/// Paste metadata store.
+pub mod metadata;
+
+/// Paste aliases.
+pub mod alias;
+
+/// Storage quota handler.
+pub mod quota;
+
+/// Expired and orphaned file cleanup.
+pub mod cleanup;
+
+/// Resumable uploads.
+pub mod resumable;
+
 /// Media type handler.
 pub mod mime;
 
+/// Syntax highlighting.
+pub mod highlight;
+
+/// Markdown rendering.
+pub mod markdown;
+
+/// Image thumbnails.
+pub mod thumbnail;
+
 /// Helper functions.
 pub mod util;
 
 /// Custom middleware implementation.
 pub mod middleware;
 
+/// Prometheus metrics.
+pub mod metrics;
+
+/// Storage statistics.
+pub mod stats;
+
+/// Webhook notifications.
+pub mod webhook;
+
+/// Virus scanning via ClamAV.
+pub mod clamav;
+
+/// TLS support.
+#[cfg(feature = "rustls")]
+pub mod tls;
+
 // Use macros from tracing crate.
 #[macro_use]
 extern crate tracing;
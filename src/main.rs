@@ -1,15 +1,21 @@
-use actix_web::middleware::Logger;
+#[cfg(all(unix, not(feature = "shuttle")))]
+use actix_web::rt::signal::unix::{signal, SignalKind};
 use actix_web::web::Data;
 #[cfg(not(feature = "shuttle"))]
 use actix_web::{App, HttpServer};
 use awc::ClientBuilder;
+use config::ConfigError;
 use hotwatch::notify::event::ModifyKind;
 use hotwatch::{Event, EventKind, Hotwatch};
+use rustypaste::cleanup;
 use rustypaste::config::{Config, ServerConfig};
-use rustypaste::middleware::ContentLengthLimiter;
+use rustypaste::middleware::{
+    self, AccessLogger, ContentLengthLimiter, IpFilter, RateLimiter, SecurityHeaders,
+};
 use rustypaste::paste::PasteType;
+use rustypaste::resumable;
 use rustypaste::server;
-use rustypaste::util;
+use rustypaste::storage;
 use rustypaste::CONFIG_ENV;
 use std::env;
 use std::fs;
@@ -20,7 +26,7 @@ use std::thread;
 use std::time::Duration;
 #[cfg(not(feature = "shuttle"))]
 use tracing_subscriber::{
-    filter::LevelFilter, layer::SubscriberExt as _, util::SubscriberInitExt as _, EnvFilter,
+    filter::LevelFilter, layer::SubscriberExt as _, reload, util::SubscriberInitExt as _, EnvFilter,
 };
 #[cfg(feature = "shuttle")]
 use {
@@ -44,12 +50,14 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
 
     // Initialize logger.
     #[cfg(not(feature = "shuttle"))]
+    let (log_filter, log_filter_handle) = reload::Layer::new(
+        EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .from_env_lossy(),
+    );
+    #[cfg(not(feature = "shuttle"))]
     tracing_subscriber::registry()
-        .with(
-            EnvFilter::builder()
-                .with_default_directive(LevelFilter::INFO.into())
-                .from_env_lossy(),
-        )
+        .with(log_filter)
         .with(tracing_subscriber::fmt::layer())
         .init();
 
@@ -62,23 +70,48 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
         None => config_folder.join("config.toml"),
     };
     if !config_path.exists() {
-        error!(
-            "{} is not found, please provide a configuration file.",
+        warn!(
+            "{} is not found, using the environment variables and the default values.",
             config_path.display()
         );
-        std::process::exit(1);
     }
-    let config = Config::parse(&config_path).expect("failed to parse config");
+    let mut config = Config::parse(&config_path).expect("failed to parse config");
     trace!("{:#?}", config);
     config.warn_deprecation();
+    if let Err(e) = storage::init_backend(&mut config) {
+        error!("Failed to initialize the storage: {}", e);
+        std::process::exit(1);
+    }
     let server_config = config.server.clone();
     let paste_config = RwLock::new(config.paste.clone());
     let (config_sender, config_receiver) = mpsc::channel::<Config>();
 
     // Create necessary directories.
-    fs::create_dir_all(&server_config.upload_path)?;
-    for paste_type in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
-        fs::create_dir_all(paste_type.get_path(&server_config.upload_path)?)?;
+    for upload_path in server_config.get_upload_paths() {
+        fs::create_dir_all(upload_path)?;
+        for paste_type in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
+            fs::create_dir_all(paste_type.get_path(upload_path)?)?;
+        }
+    }
+
+    // Validate the configuration.
+    if let Err(e) = config.validate() {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+
+    // Set the log level if it is not set via the environment.
+    #[cfg(not(feature = "shuttle"))]
+    if let (Some(log_level), None) = (
+        &server_config.log_level,
+        env::var_os(EnvFilter::DEFAULT_ENV),
+    ) {
+        if let Err(e) = EnvFilter::try_new(log_level)
+            .map_err(|e| e.to_string())
+            .and_then(|v| log_filter_handle.reload(v).map_err(|e| e.to_string()))
+        {
+            error!("Failed to set the log level: {}", e);
+        }
     }
 
     // Set up a watcher for the configuration file changes.
@@ -94,36 +127,37 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
     // Hot-reload the configuration file.
     let config = Data::new(RwLock::new(config));
     let cloned_config = Data::clone(&config);
+    let cloned_sender = config_sender.clone();
     let config_watcher = move |event: Event| {
         if let (EventKind::Modify(ModifyKind::Data(_)), Some(path)) =
             (event.kind, event.paths.first())
         {
-            match Config::parse(path) {
-                Ok(config) => match cloned_config.write() {
-                    Ok(mut cloned_config) => {
-                        *cloned_config = config.clone();
-                        info!("Configuration has been updated.");
-                        if let Err(e) = config_sender.send(config) {
-                            error!("Failed to send config for the cleanup routine: {}", e)
-                        }
-                        cloned_config.warn_deprecation();
-                    }
-                    Err(e) => {
-                        error!("Failed to acquire config: {}", e);
-                    }
-                },
-                Err(e) => {
-                    error!("Failed to update config: {}", e);
-                }
-            }
+            reload_config(path, &cloned_config, &cloned_sender);
         }
     };
-    hotwatch
-        .watch(&config_path, config_watcher)
-        .unwrap_or_else(|_| panic!("failed to watch {config_path:?}"));
+    if config_path.exists() {
+        hotwatch
+            .watch(&config_path, config_watcher)
+            .unwrap_or_else(|_| panic!("failed to watch {config_path:?}"));
+    }
+
+    // Reload the configuration file on SIGHUP.
+    #[cfg(all(unix, not(feature = "shuttle")))]
+    {
+        let cloned_config = Data::clone(&config);
+        let config_path = config_path.clone();
+        let mut hangup = signal(SignalKind::hangup())?;
+        actix_web::rt::spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("Received SIGHUP, reloading the configuration...");
+                reload_config(&config_path, &cloned_config, &config_sender);
+            }
+        });
+    }
 
     // Create a thread for cleaning up expired files.
     let upload_path = server_config.upload_path.clone();
+    let overflow_paths = server_config.overflow_paths.clone();
     thread::spawn(move || loop {
         let mut enabled = false;
         if let Some(ref cleanup_config) = paste_config
@@ -133,12 +167,18 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
         {
             if cleanup_config.enabled {
                 debug!("Running cleanup...");
-                for file in util::get_expired_files(&upload_path) {
-                    match fs::remove_file(&file) {
-                        Ok(()) => info!("Removed expired file: {:?}", file),
-                        Err(e) => error!("Cannot remove expired file: {}", e),
-                    }
+                cleanup::remove_expired_files(&upload_path);
+                for overflow_path in &overflow_paths {
+                    cleanup::remove_expired_files(overflow_path);
                 }
+                resumable::remove_stale(
+                    &upload_path,
+                    paste_config
+                        .read()
+                        .ok()
+                        .and_then(|v| v.resumable_upload_timeout)
+                        .unwrap_or(resumable::DEFAULT_TIMEOUT),
+                );
                 thread::sleep(cleanup_config.interval);
             }
             enabled = cleanup_config.enabled;
@@ -162,11 +202,53 @@ fn setup(config_folder: &Path) -> IoResult<(Data<RwLock<Config>>, ServerConfig,
     Ok((config, server_config, hotwatch))
 }
 
+/// Parses and validates the configuration file and replaces the current configuration with it.
+///
+/// The options that cannot be changed at runtime are kept as is.
+/// The new configuration is also sent to the cleanup routine.
+fn reload_config(path: &Path, config: &RwLock<Config>, config_sender: &mpsc::Sender<Config>) {
+    match Config::parse(path).and_then(|mut config| {
+        config.validate()?;
+        storage::init_backend(&mut config).map_err(|e| ConfigError::Message(e.to_string()))?;
+        Ok(config)
+    }) {
+        Ok(mut new_config) => match config.write() {
+            Ok(mut config) => {
+                for option in new_config.server.keep_static_options(&config.server) {
+                    warn!(
+                        "[server].{} cannot be changed at runtime, restart the server to apply it.",
+                        option
+                    );
+                }
+                *config = new_config.clone();
+                info!("Configuration has been updated.");
+                if let Err(e) = config_sender.send(new_config) {
+                    error!("Failed to send config for the cleanup routine: {}", e)
+                }
+                config.warn_deprecation();
+            }
+            Err(e) => {
+                error!("Failed to acquire config: {}", e);
+            }
+        },
+        Err(e) => {
+            error!("Failed to update config: {}", e);
+        }
+    }
+}
+
 #[cfg(not(feature = "shuttle"))]
 #[actix_web::main]
 async fn main() -> IoResult<()> {
     // Set up the application.
     let (config, server_config, _hotwatch) = setup(&PathBuf::new())?;
+    let rate_limiter = RateLimiter::new(server_config.rate_limit.clone())
+        .with_trusted_proxies(&server_config.trusted_proxies);
+    let ip_filter = IpFilter::new(&server_config);
+    let security_headers = SecurityHeaders::new(server_config.security_headers.as_ref());
+    let access_logger = AccessLogger::new(server_config.access_log.as_deref())?
+        .with_trusted_proxies(&server_config.trusted_proxies);
+    let workers = server_config.get_workers();
 
     // Create an HTTP server.
     let mut http_server = HttpServer::new(move || {
@@ -181,22 +263,64 @@ async fn main() -> IoResult<()> {
         App::new()
             .app_data(Data::clone(&config))
             .app_data(Data::new(http_client))
-            .wrap(Logger::new(
-                "%{r}a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
+            .wrap(ContentLengthLimiter::new(
+                server_config.max_content_length,
+                server_config.on_oversize,
             ))
-            .wrap(ContentLengthLimiter::new(server_config.max_content_length))
+            .wrap(rate_limiter.clone())
+            .wrap(ip_filter.clone())
+            .wrap(security_headers.clone())
+            .wrap(middleware::cors(server_config.cors.as_ref()))
+            // Wrapped last to log the requests that are rejected by the other middlewares.
+            .wrap(access_logger.clone())
             .configure(server::configure_routes)
-    })
-    .bind(&server_config.address)?;
+    });
+
+    // Bind the address with TLS if it is configured.
+    http_server = match &server_config.tls {
+        #[cfg(feature = "rustls")]
+        Some(tls_config) => match rustypaste::tls::load_server_config(tls_config) {
+            Ok(rustls_config) => {
+                http_server.bind_rustls_021(&server_config.address, rustls_config)?
+            }
+            Err(e) => {
+                error!("Failed to load the TLS configuration: {e}");
+                std::process::exit(1);
+            }
+        },
+        #[cfg(not(feature = "rustls"))]
+        Some(_) => {
+            error!("TLS is configured but the rustls feature is not enabled");
+            std::process::exit(1);
+        }
+        None => http_server.bind(&server_config.address)?,
+    };
 
     // Set worker count for the server.
-    if let Some(workers) = server_config.workers {
-        http_server = http_server.workers(workers);
-    }
+    debug!("Starting {} workers", workers);
+    http_server = http_server.workers(workers);
+
+    // Stop accepting new connections on SIGINT/SIGTERM and let the active requests finish.
+    http_server = http_server.shutdown_timeout(
+        server_config
+            .shutdown_timeout
+            .unwrap_or_else(|| Duration::from_secs(30))
+            .as_secs(),
+    );
 
     // Run the server.
-    info!("Server is running at {}", server_config.address);
-    http_server.run().await
+    info!(
+        "Server is running at {}://{}",
+        if server_config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        },
+        server_config.address
+    );
+    http_server.run().await?;
+    info!("Server has been shut down");
+    Ok(())
 }
 
 #[cfg(feature = "shuttle")]
@@ -204,6 +328,12 @@ async fn main() -> IoResult<()> {
 async fn actix_web() -> ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Send + Clone + 'static> {
     // Set up the application.
     let (config, server_config, _hotwatch) = setup(Path::new("shuttle"))?;
+    let rate_limiter = RateLimiter::new(server_config.rate_limit.clone())
+        .with_trusted_proxies(&server_config.trusted_proxies);
+    let ip_filter = IpFilter::new(&server_config);
+    let security_headers = SecurityHeaders::new(server_config.security_headers.as_ref());
+    let access_logger = AccessLogger::new(server_config.access_log.as_deref())?
+        .with_trusted_proxies(&server_config.trusted_proxies);
 
     // Create the service.
     let service_config = move |cfg: &mut ServiceConfig| {
@@ -219,10 +349,15 @@ async fn actix_web() -> ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Send +
             web::scope("")
                 .app_data(Data::clone(&config))
                 .app_data(Data::new(http_client))
-                .wrap(Logger::new(
-                    "%{r}a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
+                .wrap(ContentLengthLimiter::new(
+                    server_config.max_content_length,
+                    server_config.on_oversize,
                 ))
-                .wrap(ContentLengthLimiter::new(server_config.max_content_length))
+                .wrap(rate_limiter.clone())
+                .wrap(ip_filter.clone())
+                .wrap(security_headers.clone())
+                .wrap(middleware::cors(server_config.cors.as_ref()))
+                .wrap(access_logger.clone())
                 .configure(server::configure_routes),
         );
     };
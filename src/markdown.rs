@@ -0,0 +1,109 @@
+use crate::highlight::escape_html;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use std::path::Path;
+
+/// URL schemes that are allowed in the links and images.
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Checks if the file is a Markdown file based on its extension.
+pub fn is_markdown(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|v| v.to_str())
+        .is_some_and(|v| v.eq_ignore_ascii_case("md") || v.eq_ignore_ascii_case("markdown"))
+}
+
+/// Renders the Markdown text as a sanitized HTML page.
+///
+/// The raw HTML is escaped and the links with the schemes other than
+/// [`ALLOWED_SCHEMES`] (e.g. `javascript:`) are removed.
+pub fn render(file_name: &str, text: &str) -> String {
+    let parser = Parser::new_ext(
+        text,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS,
+    )
+    .map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(file_name),
+        body
+    )
+}
+
+/// Returns the URL if it is relative or has one of the [`ALLOWED_SCHEMES`].
+///
+/// Otherwise, an empty URL is returned.
+fn sanitize_url(url: CowStr<'_>) -> CowStr<'_> {
+    let normalized = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>();
+    let scheme = normalized
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+    match scheme {
+        Some(scheme)
+            if !ALLOWED_SCHEMES
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(scheme)) =>
+        {
+            CowStr::Borrowed("")
+        }
+        _ => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        assert!(is_markdown("README.md"));
+        assert!(is_markdown("notes.Markdown"));
+        assert!(!is_markdown("main.rs"));
+
+        let html = render(
+            "<test>.md",
+            "# Title\n\n<script>alert(1)</script>\n\n[link](https://orhun.dev) [xss](JavaScript:alert(1)) ![image](data:image/png;base64,AA==)\n",
+        );
+        assert!(html.contains("<title>&lt;test&gt;.md</title>"));
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<a href=\"https://orhun.dev\">link</a>"));
+        assert!(html.contains("<a href=\"\">xss</a>"));
+        assert!(!html.contains("data:"));
+        assert!(
+            render("x.md", "[page](docs/page.md)").contains("<a href=\"docs/page.md\">page</a>")
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::storage::StorageBackend;
+use crate::util;
+use path_clean::PathClean;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+
+/// Directory of the metadata files in the upload path.
+pub const METADATA_DIR: &str = ".metadata";
+
+/// Metadata of a paste.
+///
+/// Stored as a JSON sidecar file in the [`metadata directory`](METADATA_DIR).
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Metadata {
+    /// Original file name sent by the client.
+    pub original_name: Option<String>,
+    /// Media type of the file at the time of the upload.
+    pub content_type: Option<String>,
+    /// Size of the file in bytes.
+    pub size: usize,
+    /// Creation timestamp in milliseconds.
+    pub created_at: u128,
+    /// Expiration timestamp in milliseconds.
+    pub expires_at: Option<u128>,
+    /// Whether if the paste can be accessed only once.
+    pub oneshot: bool,
+    /// Argon2 hash of the password that is required for accessing the paste.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    /// SHA256 digest of the auth token that is used for uploading the paste.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_hash: Option<String>,
+    /// Whether if the auth token is required for accessing the paste.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+}
+
+impl Metadata {
+    /// Reads the metadata of the file at the given path.
+    ///
+    /// Returns `None` if there is no metadata for the file.
+    pub fn read(
+        storage: &dyn StorageBackend,
+        upload_path: &Path,
+        file_path: &Path,
+    ) -> IoResult<Option<Self>> {
+        let path = get_path(upload_path, file_path)?;
+        if !storage.exists(&path)? {
+            return Ok(None);
+        }
+        serde_json::from_slice(&storage.fetch(&path)?)
+            .map(Some)
+            .map_err(|e| IoError::new(IoErrorKind::InvalidData, e))
+    }
+
+    /// Writes the metadata of the file at the given path.
+    pub fn write(
+        &self,
+        storage: &dyn StorageBackend,
+        upload_path: &Path,
+        file_path: &Path,
+    ) -> IoResult<()> {
+        let path = get_path(upload_path, file_path)?;
+        if let Some(parent) = path.parent() {
+            if storage.is_local() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        storage.store(&path, &serde_json::to_vec(self)?)
+    }
+
+    /// Removes the metadata of the file at the given path if it exists.
+    pub fn delete(
+        storage: &dyn StorageBackend,
+        upload_path: &Path,
+        file_path: &Path,
+    ) -> IoResult<()> {
+        let path = get_path(upload_path, file_path)?;
+        if storage.exists(&path)? {
+            storage.delete(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the metadata of the expired file at the given path.
+    ///
+    /// The metadata is shared by the files with the same name, so it is only removed if it
+    /// records the expiration timestamp of the file (i.e. a newer file has not replaced it).
+    pub fn delete_expired(
+        storage: &dyn StorageBackend,
+        upload_path: &Path,
+        file_path: &Path,
+    ) -> IoResult<()> {
+        let expires_at = file_path
+            .extension()
+            .and_then(|v| v.to_str())
+            .and_then(|v| v.parse::<u128>().ok());
+        match Self::read(storage, upload_path, file_path) {
+            Ok(Some(metadata)) if expires_at.is_some() && metadata.expires_at != expires_at => {
+                Ok(())
+            }
+            _ => Self::delete(storage, upload_path, file_path),
+        }
+    }
+}
+
+/// Returns the path of the metadata file for the file at the given path.
+///
+/// The expiration timestamp of the file is ignored, e.g.
+/// `upload/oneshot/file.txt.1700000000000` maps to `upload/.metadata/oneshot/file.txt.json`.
+pub fn get_path(upload_path: &Path, file_path: &Path) -> IoResult<PathBuf> {
+    let file_path = util::TIMESTAMP_EXTENSION_REGEX
+        .replacen(&file_path.to_string_lossy(), 1, "")
+        .to_string();
+    let file_path = Path::new(&file_path).clean();
+    let relative_path = file_path.strip_prefix(upload_path.clean()).map_err(|_| {
+        IoError::new(
+            IoErrorKind::InvalidInput,
+            format!(
+                "{} is outside of {}",
+                file_path.display(),
+                upload_path.display()
+            ),
+        )
+    })?;
+    util::safe_path_join(
+        upload_path.join(METADATA_DIR),
+        format!("{}.json", relative_path.to_string_lossy()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_metadata_path() -> IoResult<()> {
+        let upload_path = Path::new("upload");
+        assert_eq!(
+            PathBuf::from("upload/.metadata/file.txt.json"),
+            get_path(upload_path, Path::new("upload/file.txt"))?
+        );
+        assert_eq!(
+            PathBuf::from("upload/.metadata/oneshot/file.txt.json"),
+            get_path(
+                upload_path,
+                Path::new("upload/oneshot/file.txt.1700000000000")
+            )?
+        );
+        assert_eq!(
+            PathBuf::from("upload/.metadata/file.txt.json"),
+            get_path(Path::new("./upload"), Path::new("upload/file.txt"))?
+        );
+        assert!(get_path(upload_path, Path::new("other/file.txt")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_metadata_upload");
+        let file_path = upload_path.join("file.txt");
+        let metadata = Metadata {
+            original_name: Some(String::from("original.txt")),
+            content_type: Some(String::from("text/plain")),
+            size: 4,
+            created_at: 1_700_000_000_000,
+            expires_at: None,
+            oneshot: false,
+            password_hash: None,
+            token_hash: None,
+            private: false,
+        };
+        assert_eq!(
+            None,
+            Metadata::read(&LocalStorage, &upload_path, &file_path)?
+        );
+        metadata.write(&LocalStorage, &upload_path, &file_path)?;
+        assert!(upload_path.join(".metadata/file.txt.json").is_file());
+        assert_eq!(
+            Some(&metadata),
+            Metadata::read(&LocalStorage, &upload_path, &file_path)?.as_ref()
+        );
+        Metadata::delete(&LocalStorage, &upload_path, &file_path)?;
+        assert_eq!(
+            None,
+            Metadata::read(&LocalStorage, &upload_path, &file_path)?
+        );
+
+        let expired_path = upload_path.join("file.txt.1700000000000");
+        metadata.write(&LocalStorage, &upload_path, &file_path)?;
+        Metadata::delete_expired(&LocalStorage, &upload_path, &expired_path)?;
+        assert_eq!(
+            Some(&metadata),
+            Metadata::read(&LocalStorage, &upload_path, &file_path)?.as_ref()
+        );
+        Metadata {
+            expires_at: Some(1_700_000_000_000),
+            ..metadata.clone()
+        }
+        .write(&LocalStorage, &upload_path, &expired_path)?;
+        Metadata::delete_expired(&LocalStorage, &upload_path, &expired_path)?;
+        assert_eq!(
+            None,
+            Metadata::read(&LocalStorage, &upload_path, &file_path)?
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use crate::util;
+use actix_web::{error, Error as ActixError};
+use lazy_regex::Lazy;
+use prometheus::{Encoder, IntCounter, IntGauge, Registry, TextEncoder};
+use std::path::Path;
+
+/// Global metrics of the server.
+pub static METRICS: Lazy<Metrics> =
+    Lazy::new(|| Metrics::new().expect("failed to register metrics"));
+
+/// Prometheus metrics.
+pub struct Metrics {
+    /// Registry of the metrics.
+    registry: Registry,
+    /// Total number of uploaded files.
+    pub uploads: IntCounter,
+    /// Total number of stored bytes.
+    pub uploaded_bytes: IntCounter,
+    /// Total number of failed uploads.
+    pub upload_failures: IntCounter,
+    /// Total number of served files.
+    pub served_files: IntCounter,
+    /// Current number of files in the upload directory.
+    pub file_count: IntGauge,
+}
+
+impl Metrics {
+    /// Constructs a new instance and registers the metrics.
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some(env!("CARGO_PKG_NAME").to_string()), None)?;
+        let uploads = IntCounter::new("uploads_total", "Total number of uploaded files")?;
+        let uploaded_bytes =
+            IntCounter::new("uploaded_bytes_total", "Total number of stored bytes")?;
+        let upload_failures =
+            IntCounter::new("upload_failures_total", "Total number of failed uploads")?;
+        let served_files = IntCounter::new("served_files_total", "Total number of served files")?;
+        let file_count = IntGauge::new("files", "Current number of files in the upload directory")?;
+        registry.register(Box::new(uploads.clone()))?;
+        registry.register(Box::new(uploaded_bytes.clone()))?;
+        registry.register(Box::new(upload_failures.clone()))?;
+        registry.register(Box::new(served_files.clone()))?;
+        registry.register(Box::new(file_count.clone()))?;
+        Ok(Self {
+            registry,
+            uploads,
+            uploaded_bytes,
+            upload_failures,
+            served_files,
+            file_count,
+        })
+    }
+
+    /// Updates the file count and returns the metrics in the Prometheus text format.
+    pub fn render(&self, upload_path: &Path) -> Result<String, ActixError> {
+        self.file_count
+            .set(i64::try_from(count_files(upload_path)).unwrap_or(i64::MAX));
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(error::ErrorInternalServerError)?;
+        String::from_utf8(buffer).map_err(error::ErrorInternalServerError)
+    }
+}
+
+/// Returns the number of unexpired files in the possible upload locations.
+fn count_files(upload_path: &Path) -> usize {
+    util::get_stored_files(upload_path)
+        .len()
+        .saturating_sub(util::get_expired_files(upload_path).len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_render_metrics() -> Result<(), ActixError> {
+        let upload_path = env::current_dir()?.join("test_metrics_upload");
+        fs::create_dir_all(&upload_path)?;
+        fs::write(upload_path.join("file.txt"), "test")?;
+
+        let metrics = Metrics::new().map_err(error::ErrorInternalServerError)?;
+        metrics.uploads.inc();
+        metrics.uploaded_bytes.inc_by(4);
+        let output = metrics.render(&upload_path)?;
+        assert!(output.contains("rustypaste_uploads_total 1"));
+        assert!(output.contains("rustypaste_uploaded_bytes_total 4"));
+        assert!(output.contains("rustypaste_upload_failures_total 0"));
+        assert!(output.contains("rustypaste_files 1"));
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
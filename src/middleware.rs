@@ -1,27 +1,102 @@
-use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::http::header::CONTENT_LENGTH;
-use actix_web::http::StatusCode;
+use crate::config::{
+    CorsConfig, IpNetwork, OversizePolicy, RateLimitConfig, SecurityHeadersConfig, ServerConfig,
+};
+use crate::util;
+use actix_cors::Cors;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::PayloadError;
+use actix_web::http::header::{
+    HeaderValue, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_SECURITY_POLICY, CONTENT_TYPE,
+    RETRY_AFTER, X_CONTENT_TYPE_OPTIONS, X_FORWARDED_FOR, X_FRAME_OPTIONS,
+};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Condition;
 use actix_web::{body::EitherBody, Error};
-use actix_web::{HttpMessage, HttpResponseBuilder};
+use actix_web::{HttpMessage, HttpRequest, HttpResponseBuilder};
 use byte_unit::Byte;
-use futures_util::{Future, TryStreamExt};
+use futures_util::{Future, StreamExt, TryStreamExt};
 use std::{
+    cell::Cell,
+    collections::HashMap,
+    fs::{File, OpenOptions},
     future::{ready, Ready},
+    io::{Error as IoError, Result as IoResult, Write},
+    net::{IpAddr, SocketAddr},
+    path::Path,
     pin::Pin,
     rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 
+/// Number of tracked clients before the expired windows are pruned.
+const RATE_LIMIT_PRUNE_THRESHOLD: usize = 1024;
+
+/// Returns the address of the client.
+///
+/// The client address is taken from the `X-Forwarded-For` header only if the peer is one of
+/// the [`trusted_proxies`](ServerConfig::trusted_proxies). The last address before the trusted
+/// proxies is used, so that the addresses that are prepended by the client are ignored.
+/// Otherwise, the address of the peer is used.
+pub fn get_client_addr(request: &HttpRequest, trusted_proxies: &[IpNetwork]) -> Option<IpAddr> {
+    let peer = request.peer_addr().map(|v| v.ip());
+    let is_trusted = |addr: &IpAddr| trusted_proxies.iter().any(|v| v.contains(*addr));
+    if !peer.as_ref().is_some_and(is_trusted) {
+        return peer;
+    }
+    let forwarded = request
+        .headers()
+        .get_all(X_FORWARDED_FOR)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(parse_addr)
+        .collect::<Vec<IpAddr>>();
+    forwarded
+        .iter()
+        .rev()
+        .find(|v| !is_trusted(v))
+        .or(forwarded.first())
+        .copied()
+        .or(peer)
+}
+
+/// Returns the address of the client (see [`get_client_addr`]) for logging.
+pub fn get_client_host(request: &HttpRequest, trusted_proxies: &[IpNetwork]) -> String {
+    get_client_addr(request, trusted_proxies)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| String::from("unknown host"))
+}
+
+/// Parses the IP address with an optional port.
+fn parse_addr(addr: &str) -> Option<IpAddr> {
+    let addr = addr.trim();
+    addr.parse::<IpAddr>()
+        .or_else(|_| addr.parse::<SocketAddr>().map(|v| v.ip()))
+        .ok()
+}
+
 /// Content length limiter middleware.
+///
+/// Rejects the requests with a `Content-Length` that exceeds the limit and
+/// aborts the requests that exceed the limit while streaming the body (e.g. chunked uploads).
+///
+/// The multipart uploads are not limited if the oversized files are truncated instead
+/// (see [`OversizePolicy::Truncate`]).
 #[derive(Debug)]
 pub struct ContentLengthLimiter {
     // Maximum amount of bytes to allow.
     max_bytes: Byte,
+    // Handling of the oversized uploads.
+    on_oversize: OversizePolicy,
 }
 
 impl ContentLengthLimiter {
     /// Constructs a new instance.
-    pub fn new(max_bytes: Byte) -> Self {
-        Self { max_bytes }
+    pub fn new(max_bytes: Byte, on_oversize: OversizePolicy) -> Self {
+        Self {
+            max_bytes,
+            on_oversize,
+        }
     }
 }
 
@@ -40,6 +115,7 @@ where
         ready(Ok(ContentLengthLimiterMiddleware {
             service: Rc::new(service),
             max_bytes: self.max_bytes,
+            on_oversize: self.on_oversize,
         }))
     }
 }
@@ -49,6 +125,7 @@ where
 pub struct ContentLengthLimiterMiddleware<S> {
     service: Rc<S>,
     max_bytes: Byte,
+    on_oversize: OversizePolicy,
 }
 
 impl<S, B> Service<ServiceRequest> for ContentLengthLimiterMiddleware<S>
@@ -63,16 +140,36 @@ where
     forward_ready!(service);
     fn call(&self, mut request: ServiceRequest) -> Self::Future {
         let service = Rc::clone(&self.service);
+        if self.on_oversize == OversizePolicy::Truncate && is_multipart_upload(&request) {
+            // The oversized files are truncated while processing the upload.
+            return Box::pin(async move {
+                service
+                    .call(request)
+                    .await
+                    .map(ServiceResponse::map_into_left_body)
+            });
+        }
+        // The limit applies to the decoded data of the JSON uploads.
+        let max_bytes = if request
+            .mime_type()
+            .ok()
+            .flatten()
+            .is_some_and(|v| v.essence_str() == mime::APPLICATION_JSON.essence_str())
+        {
+            Byte::from_u64(util::get_json_body_limit(self.max_bytes.as_u64()))
+        } else {
+            self.max_bytes
+        };
         if let Some(content_length) = request
             .headers()
             .get(CONTENT_LENGTH)
             .and_then(|v| v.to_str().ok())
             .and_then(|v| v.parse::<Byte>().ok())
         {
-            if content_length > self.max_bytes {
+            if content_length > max_bytes {
                 warn!(
                     "Upload rejected due to exceeded limit. ({:-#} > {:-#})",
-                    content_length, self.max_bytes
+                    content_length, max_bytes
                 );
                 return Box::pin(async move {
                     // drain the body due to https://github.com/actix/actix-web/issues/2695
@@ -86,6 +183,300 @@ where
                 });
             }
         }
+        let max_bytes = usize::try_from(max_bytes.as_u64()).unwrap_or(usize::MAX);
+        let limit_exceeded = Rc::new(Cell::new(false));
+        let payload_limit_exceeded = Rc::clone(&limit_exceeded);
+        let mut size = 0;
+        let payload = request.take_payload().map(move |chunk| {
+            let chunk = chunk?;
+            size += chunk.len();
+            if size > max_bytes {
+                payload_limit_exceeded.set(true);
+                return Err(PayloadError::Overflow);
+            }
+            Ok(chunk)
+        });
+        request.set_payload(Payload::Stream {
+            payload: Box::pin(payload),
+        });
+        Box::pin(async move {
+            let response = service.call(request).await;
+            if !limit_exceeded.get() {
+                return response.map(ServiceResponse::map_into_left_body);
+            }
+            warn!("Upload rejected due to exceeded limit. (> {max_bytes} bytes)");
+            let (request, _) = response?.into_parts();
+            Ok(ServiceResponse::new(
+                request,
+                HttpResponseBuilder::new(StatusCode::PAYLOAD_TOO_LARGE)
+                    .body("upload limit exceeded")
+                    .map_into_right_body(),
+            ))
+        })
+    }
+}
+
+/// Checks if the request is a `multipart` upload to the upload endpoint.
+fn is_multipart_upload(request: &ServiceRequest) -> bool {
+    request.method() == Method::POST
+        && request.path() == "/"
+        && request
+            .mime_type()
+            .ok()
+            .flatten()
+            .is_some_and(|v| v.type_() == mime::MULTIPART)
+}
+
+/// Access logger middleware.
+///
+/// Logs the client address, method, path, status and response time of the requests.
+/// The query strings and headers are never logged since they might contain secrets
+/// (e.g. passwords and auth tokens).
+#[derive(Debug, Clone)]
+pub struct AccessLogger {
+    /// File to write the access logs to instead of the server log.
+    file: Option<Arc<Mutex<File>>>,
+    /// Proxies that are trusted to forward the client address.
+    trusted_proxies: Arc<[IpNetwork]>,
+}
+
+impl AccessLogger {
+    /// Constructs a new instance.
+    ///
+    /// Access logs are appended to the file at `path` if it is given.
+    pub fn new(path: Option<&Path>) -> IoResult<Self> {
+        let file = path
+            .map(|path| OpenOptions::new().create(true).append(true).open(path))
+            .transpose()?;
+        Ok(Self {
+            file: file.map(|v| Arc::new(Mutex::new(v))),
+            trusted_proxies: Arc::new([]),
+        })
+    }
+
+    /// Sets the proxies that are trusted to forward the client address.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: &[IpNetwork]) -> Self {
+        self.trusted_proxies = trusted_proxies.into();
+        self
+    }
+
+    /// Writes the given access log entry.
+    fn log(&self, entry: &str) {
+        let Some(file) = &self.file else {
+            info!("{entry}");
+            return;
+        };
+        let result = file
+            .lock()
+            .map_err(|_| IoError::other("cannot acquire the file"))
+            .and_then(|mut file| {
+                writeln!(
+                    file,
+                    "{} {entry}",
+                    humantime::format_rfc3339_seconds(SystemTime::now())
+                )
+            });
+        if let Err(e) = result {
+            error!("Cannot write the access log: {}", e);
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLogger
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AccessLoggerMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLoggerMiddleware {
+            service: Rc::new(service),
+            logger: self.clone(),
+        }))
+    }
+}
+
+/// Access logger middleware implementation.
+#[derive(Debug)]
+pub struct AccessLoggerMiddleware<S> {
+    service: Rc<S>,
+    logger: AccessLogger,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLoggerMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    forward_ready!(service);
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let logger = self.logger.clone();
+        let start = Instant::now();
+        let client = get_client_host(request.request(), &logger.trusted_proxies);
+        let method = request.method().to_string();
+        let path = request.path().to_string();
+        Box::pin(async move {
+            let response = service.call(request).await;
+            let status = match &response {
+                Ok(response) => response.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            logger.log(&format!(
+                "{client} \"{method} {path}\" {} {:.6}",
+                status.as_u16(),
+                start.elapsed().as_secs_f64()
+            ));
+            response
+        })
+    }
+}
+
+/// Request counter of a client in the current window.
+#[derive(Debug)]
+struct RateLimitWindow {
+    /// Start of the window.
+    start: Instant,
+    /// Number of requests in the window.
+    requests: u32,
+}
+
+/// Rate limiter middleware.
+///
+/// Limits the number of requests per client address in a fixed time window.
+/// The state is shared between the clones so that all workers share the same limits.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    /// Rate limiting configuration.
+    config: Option<RateLimitConfig>,
+    /// Request counters of the clients.
+    windows: Arc<Mutex<HashMap<String, RateLimitWindow>>>,
+    /// Proxies that are trusted to forward the client address.
+    trusted_proxies: Arc<[IpNetwork]>,
+}
+
+impl RateLimiter {
+    /// Constructs a new instance.
+    ///
+    /// Requests are not limited if `config` is `None`.
+    pub fn new(config: Option<RateLimitConfig>) -> Self {
+        Self {
+            config,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+            trusted_proxies: Arc::new([]),
+        }
+    }
+
+    /// Sets the proxies that are trusted to forward the client address.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: &[IpNetwork]) -> Self {
+        self.trusted_proxies = trusted_proxies.into();
+        self
+    }
+
+    /// Registers a request from the client.
+    ///
+    /// Returns the duration to wait before retrying if the limit is exceeded.
+    fn check(&self, client: &str) -> Option<Duration> {
+        let config = self.config.as_ref()?;
+        let now = Instant::now();
+        let mut windows = self.windows.lock().ok()?;
+        if windows.len() > RATE_LIMIT_PRUNE_THRESHOLD {
+            windows.retain(|_, window| now.duration_since(window.start) < config.window);
+        }
+        let window = windows
+            .entry(client.to_string())
+            .or_insert(RateLimitWindow {
+                start: now,
+                requests: 0,
+            });
+        if now.duration_since(window.start) >= config.window {
+            window.start = now;
+            window.requests = 0;
+        }
+        if window.requests >= config.requests {
+            return Some(
+                config
+                    .window
+                    .saturating_sub(now.duration_since(window.start)),
+            );
+        }
+        window.requests += 1;
+        None
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware {
+            service: Rc::new(service),
+            limiter: self.clone(),
+        }))
+    }
+}
+
+/// Rate limiter middleware implementation.
+#[derive(Debug)]
+pub struct RateLimiterMiddleware<S> {
+    service: Rc<S>,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    forward_ready!(service);
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let is_exempt = match &self.limiter.config {
+            Some(config) => {
+                request.path() == "/health"
+                    || (config.exempt_reads.unwrap_or(false)
+                        && matches!(*request.method(), Method::GET | Method::HEAD))
+            }
+            None => true,
+        };
+        if !is_exempt {
+            let client = get_client_addr(request.request(), &self.limiter.trusted_proxies)
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            if let Some(retry_after) = self.limiter.check(&client) {
+                warn!("Request rejected due to exceeded rate limit. ({})", client);
+                let retry_after = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                return Box::pin(async move {
+                    Ok(request.into_response(
+                        HttpResponseBuilder::new(StatusCode::TOO_MANY_REQUESTS)
+                            .insert_header((RETRY_AFTER, retry_after.to_string()))
+                            .body("too many requests")
+                            .map_into_right_body(),
+                    ))
+                });
+            }
+        }
         Box::pin(async move {
             service
                 .call(request)
@@ -94,3 +485,282 @@ where
         })
     }
 }
+
+/// IP filter middleware.
+///
+/// Rejects the uploads and deletions from the clients that are not in
+/// [`allowed_ips`](ServerConfig::allowed_ips) or are in [`blocked_ips`](ServerConfig::blocked_ips).
+/// `GET` and `HEAD` requests are only filtered if [`filter_reads`](ServerConfig::filter_reads) is enabled.
+#[derive(Debug, Clone)]
+pub struct IpFilter {
+    /// Allowed IP ranges.
+    allowed_ips: Arc<[IpNetwork]>,
+    /// Blocked IP ranges.
+    blocked_ips: Arc<[IpNetwork]>,
+    /// Whether if the reads are filtered.
+    filter_reads: bool,
+    /// Proxies that are trusted to forward the client address.
+    trusted_proxies: Arc<[IpNetwork]>,
+}
+
+impl IpFilter {
+    /// Constructs a new instance from the server configuration.
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            allowed_ips: config.allowed_ips.as_slice().into(),
+            blocked_ips: config.blocked_ips.as_slice().into(),
+            filter_reads: config.filter_reads.unwrap_or(false),
+            trusted_proxies: config.trusted_proxies.as_slice().into(),
+        }
+    }
+
+    /// Checks if the client address is allowed.
+    ///
+    /// Unknown addresses are only allowed if there is no allowlist.
+    fn is_allowed(&self, client: Option<IpAddr>) -> bool {
+        match client {
+            Some(client) => {
+                !self.blocked_ips.iter().any(|v| v.contains(client))
+                    && (self.allowed_ips.is_empty()
+                        || self.allowed_ips.iter().any(|v| v.contains(client)))
+            }
+            None => self.allowed_ips.is_empty(),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for IpFilter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = IpFilterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IpFilterMiddleware {
+            service: Rc::new(service),
+            filter: self.clone(),
+        }))
+    }
+}
+
+/// IP filter middleware implementation.
+#[derive(Debug)]
+pub struct IpFilterMiddleware<S> {
+    service: Rc<S>,
+    filter: IpFilter,
+}
+
+impl<S, B> Service<ServiceRequest> for IpFilterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    forward_ready!(service);
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let is_exempt = (self.filter.allowed_ips.is_empty() && self.filter.blocked_ips.is_empty())
+            || request.path() == "/health"
+            || *request.method() == Method::OPTIONS
+            || (!self.filter.filter_reads
+                && matches!(*request.method(), Method::GET | Method::HEAD));
+        if !is_exempt {
+            let client = get_client_addr(request.request(), &self.filter.trusted_proxies);
+            if !self.filter.is_allowed(client) {
+                warn!(
+                    "Request rejected due to the IP filter. ({})",
+                    client.map(|v| v.to_string()).unwrap_or_default()
+                );
+                return Box::pin(async move {
+                    Ok(request.into_response(
+                        HttpResponseBuilder::new(StatusCode::FORBIDDEN)
+                            .body("forbidden")
+                            .map_into_right_body(),
+                    ))
+                });
+            }
+        }
+        Box::pin(async move {
+            service
+                .call(request)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+/// Security headers middleware.
+///
+/// Sets the `X-Content-Type-Options` and `X-Frame-Options` headers of the responses and
+/// restricts the HTML and SVG files with a `Content-Security-Policy` for preventing stored XSS.
+///
+/// The landing page is not restricted since it is set by the server owner.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    /// `Content-Security-Policy` of the HTML and SVG files.
+    content_security_policy: Option<HeaderValue>,
+    /// `X-Content-Type-Options` of the responses.
+    content_type_options: Option<HeaderValue>,
+    /// `X-Frame-Options` of the responses.
+    frame_options: Option<HeaderValue>,
+    /// Whether if the HTML and SVG files are served as attachments.
+    force_download: bool,
+}
+
+impl SecurityHeaders {
+    /// Constructs a new instance from the configuration.
+    ///
+    /// The default values are used if `config` is `None`.
+    pub fn new(config: Option<&SecurityHeadersConfig>) -> Self {
+        let header_value = |value: Option<&String>, default: &'static str| {
+            let value = value.map(String::as_str).unwrap_or(default);
+            (!value.is_empty())
+                .then(|| HeaderValue::from_str(value).ok())
+                .flatten()
+        };
+        Self {
+            content_security_policy: header_value(
+                config.and_then(|v| v.content_security_policy.as_ref()),
+                SecurityHeadersConfig::DEFAULT_CONTENT_SECURITY_POLICY,
+            ),
+            content_type_options: header_value(
+                config.and_then(|v| v.content_type_options.as_ref()),
+                SecurityHeadersConfig::DEFAULT_CONTENT_TYPE_OPTIONS,
+            ),
+            frame_options: header_value(
+                config.and_then(|v| v.frame_options.as_ref()),
+                SecurityHeadersConfig::DEFAULT_FRAME_OPTIONS,
+            ),
+            force_download: config.and_then(|v| v.force_download).unwrap_or(false),
+        }
+    }
+
+    /// Checks if the content type can run scripts in the browser.
+    fn is_active_content(content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        matches!(
+            essence.as_str(),
+            "text/html"
+                | "application/xhtml+xml"
+                | "image/svg+xml"
+                | "text/xml"
+                | "application/xml"
+        )
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersMiddleware {
+            service: Rc::new(service),
+            headers: self.clone(),
+        }))
+    }
+}
+
+/// Security headers middleware implementation.
+#[derive(Debug)]
+pub struct SecurityHeadersMiddleware<S> {
+    service: Rc<S>,
+    headers: SecurityHeaders,
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    forward_ready!(service);
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let headers = self.headers.clone();
+        let is_landing_page = request.path() == "/";
+        Box::pin(async move {
+            let mut response = service.call(request).await?;
+            let response_headers = response.headers_mut();
+            if let Some(value) = &headers.content_type_options {
+                response_headers.insert(X_CONTENT_TYPE_OPTIONS, value.clone());
+            }
+            if let Some(value) = &headers.frame_options {
+                response_headers.insert(X_FRAME_OPTIONS, value.clone());
+            }
+            let is_active_content = response_headers
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(SecurityHeaders::is_active_content);
+            if is_active_content && !is_landing_page {
+                if let Some(value) = &headers.content_security_policy {
+                    response_headers.insert(CONTENT_SECURITY_POLICY, value.clone());
+                }
+                if headers.force_download {
+                    response_headers
+                        .insert(CONTENT_DISPOSITION, HeaderValue::from_static("attachment"));
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Constructs the CORS middleware.
+///
+/// CORS requests (including the preflight requests) are not handled if `config` is `None`.
+pub fn cors(config: Option<&CorsConfig>) -> Condition<Cors> {
+    let Some(config) = config else {
+        return Condition::new(false, Cors::default());
+    };
+    let mut cors = Cors::default();
+    for origin in &config.allowed_origins {
+        cors = if origin == "*" {
+            cors.allow_any_origin()
+        } else {
+            cors.allowed_origin(origin)
+        };
+    }
+    cors = if config.allowed_methods.is_empty() {
+        cors.allowed_methods(CorsConfig::DEFAULT_METHODS)
+    } else if config.allowed_methods.iter().any(|v| v == "*") {
+        cors.allow_any_method()
+    } else {
+        cors.allowed_methods(config.allowed_methods.iter().map(String::as_str))
+    };
+    cors = if config.allowed_headers.is_empty() || config.allowed_headers.iter().any(|v| v == "*") {
+        cors.allow_any_header()
+    } else {
+        cors.allowed_headers(config.allowed_headers.iter().map(String::as_str))
+    };
+    cors = if config.expose_headers.iter().any(|v| v == "*") {
+        cors.expose_any_header()
+    } else {
+        cors.expose_headers(config.expose_headers.iter().map(String::as_str))
+    };
+    cors = cors.max_age(config.max_age.map(|v| v.as_secs() as usize));
+    Condition::new(true, cors)
+}
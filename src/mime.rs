@@ -42,6 +42,60 @@ pub fn get_mime_type(
     Ok(mime_type)
 }
 
+/// Infers the media type from the contents of a file.
+///
+/// Known file signatures (via [`infer`]) are checked first, UTF-8 text is detected as
+/// `text/plain`. The data can be the beginning of the file.
+pub fn infer_mime_type(data: &[u8]) -> Option<Mime> {
+    if let Some(mime_type) = infer::get(data).and_then(|v| Mime::from_str(v.mime_type()).ok()) {
+        return Some(mime_type);
+    }
+    // The last character might be truncated.
+    let is_text = !data.is_empty()
+        && !data.contains(&0)
+        && std::str::from_utf8(data)
+            .map(|_| true)
+            .unwrap_or_else(|e| e.error_len().is_none());
+    is_text.then_some(mime::TEXT_PLAIN_UTF_8)
+}
+
+/// Checks if the media type matches the pattern.
+///
+/// Patterns can contain wildcards such as `image/*` or `*/*`.
+pub fn matches_mime_type(pattern: &str, mime_type: &str) -> bool {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let pattern = pattern.trim().to_lowercase();
+    match pattern.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((type_, "*")) => essence
+            .split_once('/')
+            .map(|(v, _)| v == type_)
+            .unwrap_or(false),
+        _ => pattern == "*" || pattern == essence,
+    }
+}
+
+/// Checks if the media type is permitted by the whitelist and the blacklist.
+///
+/// An empty whitelist allows every media type that is not blacklisted.
+pub fn is_mime_type_allowed(mime_type: &str, whitelist: &[String], blacklist: &[String]) -> bool {
+    if blacklist
+        .iter()
+        .any(|pattern| matches_mime_type(pattern, mime_type))
+    {
+        return false;
+    }
+    whitelist.is_empty()
+        || whitelist
+            .iter()
+            .any(|pattern| matches_mime_type(pattern, mime_type))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,4 +132,48 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_infer_mime_type() {
+        assert_eq!(
+            Some(mime::IMAGE_PNG),
+            infer_mime_type(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A])
+        );
+        assert_eq!(Some(mime::TEXT_PLAIN_UTF_8), infer_mime_type(b"some text"));
+        assert_eq!(
+            Some(mime::TEXT_PLAIN_UTF_8),
+            infer_mime_type(&"\u{00e7}\u{00e7}".as_bytes()[..3])
+        );
+        assert_eq!(None, infer_mime_type(&[0x00, 0xff, 0x01]));
+        assert_eq!(None, infer_mime_type(&[]));
+    }
+
+    #[test]
+    fn test_mime_type_allowed() {
+        assert!(matches_mime_type("image/*", "image/png"));
+        assert!(matches_mime_type("*/*", "text/plain"));
+        assert!(matches_mime_type("Text/Plain", "text/plain; charset=utf-8"));
+        assert!(!matches_mime_type("image/*", "text/plain"));
+        assert!(!matches_mime_type("text/html", "text/xml"));
+
+        let whitelist = [String::from("image/*"), String::from("text/plain")];
+        let blacklist = [String::from("image/svg+xml")];
+        assert!(is_mime_type_allowed("image/png", &whitelist, &blacklist));
+        assert!(is_mime_type_allowed("text/plain", &whitelist, &blacklist));
+        assert!(!is_mime_type_allowed(
+            "image/svg+xml",
+            &whitelist,
+            &blacklist
+        ));
+        assert!(!is_mime_type_allowed(
+            "application/x-dosexec",
+            &whitelist,
+            &blacklist
+        ));
+        assert!(is_mime_type_allowed(
+            "application/x-dosexec",
+            &[],
+            &blacklist
+        ));
+    }
 }
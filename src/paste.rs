@@ -1,19 +1,47 @@
-use crate::config::Config;
+use crate::clamav::{self, ScanResult};
+use crate::config::{Config, ConflictHandlingConfig, SecurityConfig};
 use crate::file::Directory;
 use crate::header::ContentDisposition;
+use crate::metadata::Metadata;
+use crate::mime as mime_util;
+use crate::quota;
+use crate::random::{RandomURLConfig, RandomURLType};
+use crate::stats::Stats;
+use crate::storage::{self, StorageBackend};
 use crate::util;
-use actix_web::{error, Error};
+use actix_files::file_extension_to_mime;
+use actix_web::http::header::CONTENT_LENGTH;
+use actix_web::{error, web, Error};
 use awc::Client;
+use futures_util::stream::StreamExt;
 use std::convert::{TryFrom, TryInto};
-use std::fs::{self, File};
+use std::fs;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::RwLock;
+use std::time::Duration;
 use url::Url;
 
+/// Default value of [`allowed_url_schemes`](crate::config::PasteConfig::allowed_url_schemes).
+const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// Default value of [`max_url_length`](crate::config::PasteConfig::max_url_length).
+const DEFAULT_MAX_URL_LENGTH: usize = 2048;
+
+/// Default value of [`default_filename`](crate::config::PasteConfig::default_filename).
+const DEFAULT_FILENAME: &str = "file";
+
+/// Default value of [`stdin_filename`](crate::config::PasteConfig::stdin_filename).
+const DEFAULT_STDIN_FILENAME: &str = "stdin";
+
+/// Length of the random suffix for the [unique stdin file names](crate::config::PasteConfig::unique_stdin_filename).
+const UNIQUE_STDIN_SUFFIX_LENGTH: usize = 6;
+
 /// Type of the data to store.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PasteType {
     /// Any type of file.
     File,
@@ -80,18 +108,54 @@ pub struct Paste {
     pub data: Vec<u8>,
     /// Type of the data.
     pub type_: PasteType,
+    /// Run the checks without storing the data.
+    pub dry_run: bool,
+    /// Fail with `412 Precondition Failed` instead of handling the conflict
+    /// if a file with the same name already exists.
+    pub create_new: bool,
+    /// Metadata to store along with the data.
+    ///
+    /// The size and the media type are set while storing the data.
+    pub metadata: Option<Metadata>,
 }
 
 impl Paste {
     /// Writes the bytes to a file in upload directory.
     ///
+    /// - If the media type is not permitted by [`mime_whitelist`] and [`mime_blacklist`], returns an error.
+    /// - If the extension is not in [`allowed_extensions`], it is replaced with the inferred one if allowed.
+    ///   Otherwise, returns an error.
     /// - If `file_name` does not have an extension, it is replaced with [`default_extension`].
-    /// - If `file_name` is "-", it is replaced with "stdin".
+    /// - If [`force_infer_extension`] is `true`, the extension is replaced with the inferred one (if any).
+    /// - If `file_name` is "-", it is replaced with [`stdin_filename`] ("stdin" by default).
+    ///   A random suffix is appended if [`unique_stdin_filename`] is set and the random URLs are disabled.
+    /// - If `file_name` cannot be inferred, it is replaced with [`default_filename`] ("file" by default).
+    /// - If [`normalize_case`] is set, the case of `file_name` (and `header_filename`) is normalized.
     /// - If [`random_url.enabled`] is `true`, `file_name` is replaced with a pet name or random string.
-    /// - If `header_filename` is set, it will override the filename.
+    /// - If `header_filename` is set, it will override the filename (after [sanitization]).
+    /// - If the random file name is taken or reserved, a new one is generated (up to a few times).
+    /// - If any other file name is in [`reserved_names`], returns an error.
+    /// - If the file name is the [hash of the content], the existing file with the same name is reused.
+    /// - If any other file name is taken, [`handle_conflicts`] is applied
+    ///   unless [`create_new`] is `true`, in which case an error is returned.
+    /// - If [`dry_run`] is `true`, the resulting file name is returned without storing the file.
     ///
+    /// [`dry_run`]: Self::dry_run
+    /// [`create_new`]: Self::create_new
     /// [`default_extension`]: crate::config::PasteConfig::default_extension
+    /// [`force_infer_extension`]: crate::config::PasteConfig::force_infer_extension
+    /// [`default_filename`]: crate::config::PasteConfig::default_filename
+    /// [`stdin_filename`]: crate::config::PasteConfig::stdin_filename
+    /// [`unique_stdin_filename`]: crate::config::PasteConfig::unique_stdin_filename
     /// [`random_url.enabled`]: crate::random::RandomURLConfig::enabled
+    /// [`handle_conflicts`]: crate::config::PasteConfig::handle_conflicts
+    /// [`normalize_case`]: crate::config::PasteConfig::normalize_case
+    /// [`reserved_names`]: crate::config::PasteConfig::reserved_names
+    /// [`mime_whitelist`]: crate::config::PasteConfig::mime_whitelist
+    /// [`mime_blacklist`]: crate::config::PasteConfig::mime_blacklist
+    /// [`allowed_extensions`]: crate::config::PasteConfig::allowed_extensions
+    /// [sanitization]: crate::util::sanitize_filename
+    /// [hash of the content]: crate::random::RandomURLType::Hash
     pub fn store_file(
         &self,
         file_name: &str,
@@ -99,35 +163,59 @@ impl Paste {
         header_filename: Option<String>,
         config: &Config,
     ) -> Result<String, Error> {
+        if self.data.is_empty() && !config.paste.allow_empty.unwrap_or(false) {
+            return Err(error::ErrorBadRequest("empty files are not allowed\n"));
+        }
         let file_type = infer::get(&self.data);
-        if let Some(file_type) = file_type {
-            for mime_type in &config.paste.mime_blacklist {
-                if mime_type == file_type.mime_type() {
-                    return Err(error::ErrorUnsupportedMediaType(
-                        "this file type is not permitted",
-                    ));
-                }
-            }
+        let mime_type = match file_type {
+            Some(file_type) => file_type.mime_type().to_string(),
+            None => file_extension_to_mime(
+                Path::new(file_name)
+                    .extension()
+                    .and_then(|v| v.to_str())
+                    .unwrap_or_default(),
+            )
+            .to_string(),
+        };
+        if !mime_util::is_mime_type_allowed(
+            &mime_type,
+            &config.paste.mime_whitelist,
+            &config.paste.mime_blacklist,
+        ) {
+            return Err(error::ErrorUnsupportedMediaType(
+                "this file type is not permitted",
+            ));
         }
-        let mut file_name = match PathBuf::from(file_name)
-            .file_name()
-            .and_then(|v| v.to_str())
-        {
-            Some("-") => String::from("stdin"),
-            Some(".") => String::from("file"),
+        let default_filename = config
+            .paste
+            .default_filename
+            .as_deref()
+            .unwrap_or(DEFAULT_FILENAME);
+        let file_name = PathBuf::from(file_name);
+        let is_stdin = file_name.file_name().and_then(|v| v.to_str()) == Some("-");
+        let mut file_name = match file_name.file_name().and_then(|v| v.to_str()) {
+            Some("-") => config
+                .paste
+                .stdin_filename
+                .as_deref()
+                .unwrap_or(DEFAULT_STDIN_FILENAME)
+                .to_string(),
+            Some(".") | None => default_filename.to_string(),
             Some(v) => v.to_string(),
-            None => String::from("file"),
         };
         if let Some(handle_spaces_config) = config.server.handle_spaces {
             file_name = handle_spaces_config.process_filename(&file_name);
         }
+        if let Some(normalize_case) = config.paste.normalize_case {
+            file_name = normalize_case.process_filename(&file_name);
+        }
 
-        let mut path =
-            util::safe_path_join(self.type_.get_path(&config.server.upload_path)?, &file_name)?;
+        let type_path = self.type_.get_path(&config.server.upload_path)?;
+        let mut path = util::safe_path_join(&type_path, &file_name)?;
         let mut parts: Vec<&str> = file_name.split('.').collect();
         let mut dotfile = false;
         let mut lower_bound = 1;
-        let mut file_name = match parts[0] {
+        let file_name = match parts[0] {
             "" => {
                 // Index shifts one to the right in the array for the rest of the string (the extension)
                 dotfile = true;
@@ -137,62 +225,281 @@ impl Paste {
             }
             _ => parts[0].to_string(),
         };
-        let mut extension = if parts.len() > lower_bound {
-            // To get the rest (the extension), we have to remove the first element of the array, which is the filename
-            parts.remove(0);
-            if dotfile {
-                // If the filename starts with a dot, we have to remove another element, because the first element was empty
+        let extension = match file_type {
+            // Keep the extension if it already ends with the detected one (e.g. `.tar.gz`).
+            Some(file_type)
+                if config.paste.force_infer_extension.unwrap_or(false)
+                    && parts.len() > lower_bound
+                    && !parts[parts.len() - 1].eq_ignore_ascii_case(file_type.extension()) =>
+            {
+                file_type.extension().to_string()
+            }
+            _ if parts.len() > lower_bound => {
+                // To get the rest (the extension), we have to remove the first element of the array, which is the filename
                 parts.remove(0);
+                if dotfile {
+                    // If the filename starts with a dot, we have to remove another element, because the first element was empty
+                    parts.remove(0);
+                }
+                parts.join(".")
             }
-            parts.join(".")
-        } else {
-            file_type
+            _ => file_type
                 .map(|t| t.extension())
                 .unwrap_or(&config.paste.default_extension)
-                .to_string()
+                .to_string(),
         };
-        if let Some(random_url) = &config.paste.random_url {
-            if let Some(random_text) = random_url.generate() {
-                if let Some(suffix_mode) = random_url.suffix_mode {
-                    if suffix_mode {
-                        extension = format!("{}.{}", random_text, extension);
+        let extension = resolve_extension(extension, file_type, &config.paste.allowed_extensions)?;
+        let header_filename = header_filename
+            .map(|v| util::sanitize_filename(&v))
+            .transpose()?
+            .map(|v| match config.paste.normalize_case {
+                Some(normalize_case) => normalize_case.process_filename(&v),
+                None => v,
+            });
+        let header_filename = match header_filename {
+            Some(header_filename) if !config.paste.allowed_extensions.is_empty() => {
+                let mut path = PathBuf::from(header_filename);
+                let extension = resolve_extension(
+                    path.extension()
+                        .map(|v| v.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    file_type,
+                    &config.paste.allowed_extensions,
+                )?;
+                path.set_extension(extension);
+                Some(path.to_string_lossy().to_string())
+            }
+            header_filename => header_filename,
+        };
+        let storage = storage::get_backend(config)?;
+        let unique_stdin = is_stdin && config.paste.unique_stdin_filename.unwrap_or(false);
+        let collision_retries = match &config.paste.random_url {
+            Some(random_url) => random_url.get_collision_retries(),
+            None if unique_stdin => RandomURLConfig::default().get_collision_retries(),
+            None => 0,
+        };
+        let is_content_addressed = config
+            .paste
+            .random_url
+            .as_ref()
+            .is_some_and(|v| v.is_content_addressed());
+        let random_url = get_random_url(&*storage, config);
+        let mut retries = 0;
+        let (mut path, is_random) = loop {
+            let mut is_random = false;
+            let mut file_name = file_name.clone();
+            let mut extension = extension.clone();
+            match random_url
+                .as_ref()
+                .and_then(|v| v.generate_for(&self.data).map(|text| (v, text)))
+            {
+                Some((random_url, random_text)) => {
+                    is_random = header_filename.is_none();
+                    if let Some(suffix_mode) = random_url.suffix_mode {
+                        if suffix_mode {
+                            extension = format!("{}.{}", random_text, extension);
+                        } else {
+                            file_name = random_text;
+                        }
                     } else {
                         file_name = random_text;
                     }
-                } else {
-                    file_name = random_text;
                 }
+                // Concurrent uploads from stdin would overwrite each other without a random URL.
+                None if unique_stdin && header_filename.is_none() => {
+                    is_random = true;
+                    file_name = format!("{file_name}-{}", get_unique_suffix());
+                }
+                None => {}
             }
-        }
-        path.set_file_name(file_name);
-        path.set_extension(extension);
-        if let Some(header_filename) = header_filename {
-            file_name = header_filename;
             path.set_file_name(file_name);
-        }
-        let file_name = path
-            .file_name()
-            .map(|v| v.to_string_lossy())
-            .unwrap_or_default()
-            .to_string();
-        let file_path = util::glob_match_file(path.clone())
-            .map_err(|_| IoError::new(IoErrorKind::Other, String::from("path is not valid")))?;
-        if file_path.is_file() && file_path.exists() {
-            return Err(error::ErrorConflict("file already exists\n"));
-        }
+            path.set_extension(extension);
+            if let Some(header_filename) = &header_filename {
+                path.set_file_name(header_filename);
+            }
+            if let Some(max_length) = config.paste.max_filename_length {
+                path.set_file_name(limit_file_name(
+                    &get_file_name(&path),
+                    max_length,
+                    config.paste.truncate_long_filenames.unwrap_or(false),
+                )?);
+            }
+            let name = get_file_name(&path);
+            if config.paste.is_reserved(&name) {
+                if !is_random {
+                    return Err(error::ErrorConflict("file name is reserved\n"));
+                }
+                if retries < collision_retries {
+                    retries += 1;
+                    continue;
+                }
+                return Err(error::ErrorConflict("cannot generate a unique file name\n"));
+            }
+            let existing_path = find_stored_path(&*storage, config, self.type_, &name)
+                .map_err(|_| IoError::other(String::from("path is not valid")))?;
+            let mut path = util::get_shard_path(&type_path, &name, config.paste.shard_length)?;
+            if !storage.exists(&existing_path)? {
+                break (path, is_random);
+            }
+            // The same content is already stored with the hash of the content.
+            if is_random && is_content_addressed {
+                if !self.dry_run && self.metadata.is_some() {
+                    self.write_metadata(&*storage, config, &existing_path, &name)?;
+                }
+                return Ok(name);
+            }
+            if self.create_new && !is_random {
+                return Err(error::ErrorPreconditionFailed("file already exists\n"));
+            }
+            if is_random {
+                if retries < collision_retries {
+                    retries += 1;
+                    continue;
+                }
+                return Err(error::ErrorConflict("cannot generate a unique file name\n"));
+            }
+            match config.paste.handle_conflicts.unwrap_or_default() {
+                ConflictHandlingConfig::Overwrite => {
+                    if existing_path != path && !self.dry_run {
+                        storage.delete(&existing_path)?;
+                        let upload_path = config
+                            .server
+                            .get_upload_paths()
+                            .find(|v| existing_path.starts_with(v))
+                            .unwrap_or(&config.server.upload_path);
+                        Metadata::delete(&*storage, upload_path, &existing_path)?;
+                    }
+                    break (path, false);
+                }
+                ConflictHandlingConfig::Rename => {
+                    for suffix in 1.. {
+                        let name = append_suffix(&name, suffix);
+                        path = util::get_shard_path(&type_path, &name, config.paste.shard_length)?;
+                        if !storage
+                            .exists(&find_stored_path(&*storage, config, self.type_, &name)?)?
+                        {
+                            break;
+                        }
+                    }
+                    break (path, false);
+                }
+                ConflictHandlingConfig::Reject => {
+                    return Err(error::ErrorConflict("file already exists\n"));
+                }
+            }
+        };
+        let file_name = get_file_name(&path);
         if let Some(timestamp) = expiry_date {
             path.set_file_name(format!("{file_name}.{timestamp}"));
         }
-        let mut buffer = File::create(&path)?;
-        buffer.write_all(&self.data)?;
+        if self.dry_run {
+            return Ok(file_name);
+        }
+        if storage.is_local() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        quota::make_room(config, self.data.len() as u64)?;
+        // The metadata (e.g. the password) is in place before the file can be served.
+        self.write_metadata(&*storage, config, &path, &file_name)?;
+        let is_overwrite = !is_random
+            && !self.create_new
+            && config.paste.handle_conflicts.unwrap_or_default()
+                == ConflictHandlingConfig::Overwrite;
+        let result = if is_overwrite {
+            storage.store(&path, &self.data).map_err(Error::from)
+        } else if self.create_new && !is_random {
+            match storage.store_new(&path, &self.data) {
+                Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+                    Err(error::ErrorPreconditionFailed("file already exists\n"))
+                }
+                result => result.map_err(Error::from),
+            }
+        } else {
+            store_new(&*storage, &path, &self.data, is_random)
+        };
+        if let Err(e) = result {
+            self.delete_metadata(&*storage, config, &path);
+            return Err(e);
+        }
+        set_file_mode(&*storage, &path, config)?;
         Ok(file_name)
     }
 
+    /// Writes the [`metadata`](Self::metadata) of the file that is stored at the given path.
+    ///
+    /// If there is no metadata, the metadata of a previous file with the same name is removed
+    /// so that the file does not inherit its password, owner or media type.
+    fn write_metadata(
+        &self,
+        storage: &dyn StorageBackend,
+        config: &Config,
+        path: &Path,
+        file_name: &str,
+    ) -> IoResult<()> {
+        let upload_path = &config.server.upload_path;
+        let Some(metadata) = &self.metadata else {
+            return Metadata::delete(storage, upload_path, path);
+        };
+        let mut metadata = metadata.clone();
+        metadata.size = self.data.len();
+        if !matches!(self.type_, PasteType::Url | PasteType::OneshotUrl) {
+            metadata.content_type =
+                mime_util::get_mime_type(&config.paste.mime_override, file_name.to_string())
+                    .ok()
+                    .map(|v| v.to_string());
+        }
+        metadata.write(storage, upload_path, path)
+    }
+
+    /// Removes the metadata that is written for a file that cannot be stored.
+    ///
+    /// Fail-safe, logs the errors.
+    fn delete_metadata(&self, storage: &dyn StorageBackend, config: &Config, path: &Path) {
+        if self.metadata.is_some() {
+            if let Err(e) = Metadata::delete(storage, &config.server.upload_path, path) {
+                error!("cannot delete metadata: {}", e);
+            }
+        }
+    }
+
+    /// Scans the data for viruses if [`clamav`] is set.
+    ///
+    /// - The files that are larger than [`clamav_max_size`] are not scanned.
+    /// - The scan runs on the thread pool for blocking operations
+    ///   so that it does not hold the worker.
+    ///
+    /// [`clamav`]: crate::config::SecurityConfig::clamav
+    /// [`clamav_max_size`]: crate::config::SecurityConfig::clamav_max_size
+    pub async fn scan(
+        &mut self,
+        file_name: &str,
+        security: Option<&SecurityConfig>,
+    ) -> Result<(), Error> {
+        let Some((address, timeout)) = get_scan_target(self.data.len(), file_name, security)
+            .map(|(address, timeout)| (address.to_string(), timeout))
+        else {
+            return Ok(());
+        };
+        let data = mem::take(&mut self.data);
+        let (data, result) = web::block(move || {
+            let result = clamav::scan(&address, &data, timeout);
+            (data, result)
+        })
+        .await?;
+        self.data = data;
+        check_scan_result(result, file_name)
+    }
+
     /// Downloads a file from URL and stores it with [`store_file`].
     ///
     /// - File name is inferred from URL if the last URL segment is a file.
+    /// - Only HTTP(S) URLs are allowed.
     /// - Same content length configuration is applied for download limit.
+    ///   The download is aborted as soon as the limit is exceeded.
     /// - Checks SHA256 digest of the downloaded file for preventing duplication.
+    /// - The downloaded file is [scanned](Self::scan) for viruses.
     /// - Assumes `self.data` contains a valid URL, otherwise returns an error.
     ///
     /// [`store_file`]: Self::store_file
@@ -204,37 +511,59 @@ impl Paste {
     ) -> Result<String, Error> {
         let data = str::from_utf8(&self.data).map_err(error::ErrorBadRequest)?;
         let url = Url::parse(data).map_err(error::ErrorBadRequest)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(error::ErrorBadRequest("only HTTP(S) URLs are supported\n"));
+        }
         let file_name = url
             .path_segments()
-            .and_then(|segments| segments.last())
+            .and_then(|mut segments| segments.next_back())
             .and_then(|name| if name.is_empty() { None } else { Some(name) })
-            .unwrap_or("file");
+            .map(String::from);
         let mut response = client
             .get(url.as_str())
             .send()
             .await
             .map_err(error::ErrorInternalServerError)?;
-        let payload_limit = config
+        let payload_limit: usize = config
             .read()
             .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
             .server
             .max_content_length
             .try_into()
             .map_err(error::ErrorInternalServerError)?;
-        let bytes = response
-            .body()
-            .limit(payload_limit)
-            .await
-            .map_err(error::ErrorInternalServerError)?
-            .to_vec();
+        if response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok())
+            .map(|v| v > payload_limit)
+            .unwrap_or(false)
+        {
+            return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+        }
+        let mut bytes = Vec::<u8>::new();
+        while let Some(chunk) = response.next().await {
+            let chunk = chunk.map_err(error::ErrorInternalServerError)?;
+            if bytes.len() + chunk.len() > payload_limit {
+                return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        self.data = bytes;
+        let security = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .security
+            .clone();
+        self.scan(url.as_str(), security.as_ref()).await?;
         let config = config
             .read()
-            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-        let bytes_checksum = util::sha256_digest(&*bytes)?;
-        self.data = bytes;
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .clone();
+        let bytes_checksum = util::sha256_digest(&*self.data)?;
         if !config.paste.duplicate_files.unwrap_or(true) && expiry_date.is_none() {
             if let Some(file) =
-                Directory::try_from(config.server.upload_path.as_path())?.get_file(bytes_checksum)
+                Directory::find_file(config.server.get_upload_paths(), bytes_checksum)?
             {
                 return Ok(file
                     .path
@@ -244,45 +573,468 @@ impl Paste {
                     .to_string());
             }
         }
-        self.store_file(file_name, expiry_date, None, &config)
+        let file_name = file_name.unwrap_or_else(|| {
+            config
+                .paste
+                .default_filename
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_FILENAME))
+        });
+        let paste = Paste {
+            data: mem::take(&mut self.data),
+            type_: self.type_,
+            dry_run: self.dry_run,
+            create_new: self.create_new,
+            metadata: self.metadata.take(),
+        };
+        let (paste, file_name) = util::run_blocking(
+            &config,
+            &storage::get_backend(&config)?,
+            move |config, _| {
+                let file_name = paste.store_file(&file_name, expiry_date, None, config)?;
+                Ok((paste, file_name))
+            },
+        )
+        .await?;
+        self.data = paste.data;
+        self.metadata = paste.metadata;
+        Ok(file_name)
     }
 
     /// Writes an URL to a file in upload directory.
     ///
-    /// - Checks if the data is a valid URL.
+    /// - Checks if the data is a valid URL with one of the [`allowed_url_schemes`].
+    /// - If [`prepend_url_scheme`] is `true`, `https://` is prepended to the URLs without a scheme.
     /// - If [`random_url.enabled`] is `true`, file name is set to a pet name or random string.
+    /// - If [`dry_run`] is `true`, the resulting file name is returned without storing the URL.
+    /// - The [directory](PasteType::get_dir) of the paste type is created if it does not exist.
     ///
+    /// [`dry_run`]: Self::dry_run
     /// [`random_url.enabled`]: crate::random::RandomURLConfig::enabled
+    /// [`allowed_url_schemes`]: crate::config::PasteConfig::allowed_url_schemes
+    /// [`prepend_url_scheme`]: crate::config::PasteConfig::prepend_url_scheme
     #[allow(deprecated)]
-    pub fn store_url(&self, expiry_date: Option<u128>, config: &Config) -> IoResult<String> {
-        let data = str::from_utf8(&self.data)
-            .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
-        let url = Url::parse(data).map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
-        let mut file_name = self.type_.get_dir();
-        if let Some(random_url) = &config.paste.random_url {
-            if let Some(random_text) = random_url.generate() {
-                file_name = random_text;
+    pub fn store_url(&self, expiry_date: Option<u128>, config: &Config) -> Result<String, Error> {
+        let data = str::from_utf8(&self.data).map_err(error::ErrorBadRequest)?;
+        let url = parse_url(data, config)?;
+        let storage = storage::get_backend(config)?;
+        let random_url = get_random_url(&*storage, config);
+        let mut retries = 0;
+        let (file_name, mut path, is_random) = loop {
+            let mut file_name = self.type_.get_dir();
+            let mut is_random = false;
+            if let Some(random_url) = &random_url {
+                if let Some(random_text) = random_url.generate_for(&self.data) {
+                    file_name = random_text;
+                    is_random = true;
+                }
             }
-        }
-        let mut path =
-            util::safe_path_join(self.type_.get_path(&config.server.upload_path)?, &file_name)?;
+            let type_path = self.type_.get_path(&config.server.upload_path)?;
+            let path = util::get_shard_path(&type_path, &file_name, config.paste.shard_length)?;
+            let is_reserved = is_random && config.paste.is_reserved(&file_name);
+            if !is_random
+                || !is_reserved
+                    && !storage.exists(
+                        &find_stored_path(&*storage, config, self.type_, &file_name)
+                            .map_err(|e| IoError::other(e.to_string()))?,
+                    )?
+            {
+                break (file_name, path, is_random);
+            }
+            // The same URL is already stored with the hash of the URL.
+            if !is_reserved
+                && config
+                    .paste
+                    .random_url
+                    .as_ref()
+                    .is_some_and(|v| v.is_content_addressed())
+            {
+                if !self.dry_run && self.metadata.is_some() {
+                    self.write_metadata(&*storage, config, &path, &file_name)?;
+                }
+                return Ok(file_name);
+            }
+            if retries
+                >= config
+                    .paste
+                    .random_url
+                    .as_ref()
+                    .map(|v| v.get_collision_retries())
+                    .unwrap_or_default()
+            {
+                return Err(error::ErrorConflict("cannot generate a unique file name\n"));
+            }
+            retries += 1;
+        };
         if let Some(timestamp) = expiry_date {
             path.set_file_name(format!("{file_name}.{timestamp}"));
         }
-        fs::write(&path, url.to_string())?;
+        if self.dry_run {
+            return Ok(file_name);
+        }
+        if storage.is_local() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        quota::make_room(config, url.as_str().len() as u64)?;
+        self.write_metadata(&*storage, config, &path, &file_name)?;
+        let result = if is_random {
+            store_new(&*storage, &path, url.as_str().as_bytes(), true)
+        } else {
+            storage
+                .store(&path, url.as_str().as_bytes())
+                .map_err(Error::from)
+        };
+        if let Err(e) = result {
+            self.delete_metadata(&*storage, config, &path);
+            return Err(e);
+        }
+        set_file_mode(&*storage, &path, config)?;
         Ok(file_name)
     }
+
+    /// Appends the data to an existing file in the upload directory.
+    ///
+    /// - Only the files in the root of the upload directory can be appended to,
+    ///   i.e. oneshot files and URLs are rejected.
+    /// - The files that are named after the [hash of their content] are rejected
+    ///   since they are cached as immutable and reused for the same content.
+    /// - The resulting file cannot be larger than [`max_content_length`].
+    /// - The resulting file is scanned for viruses (see [`scan`](Self::scan)).
+    /// - The size in the [`metadata`](crate::metadata::Metadata) of the file is updated.
+    /// - If [`dry_run`](Self::dry_run) is `true`, the file is not modified.
+    ///
+    /// [hash of their content]: crate::random::RandomURLType::Hash
+    /// [`max_content_length`]: crate::config::ServerConfig::max_content_length
+    pub async fn append_file(&self, file_name: &str, config: &Config) -> Result<String, Error> {
+        let storage = storage::get_backend(config)?;
+        if !storage.is_local() || storage.is_encrypted() || storage.is_compressed() {
+            return Err(error::ErrorBadRequest(
+                "appending is not supported by the storage\n",
+            ));
+        }
+        if Path::new(file_name).file_name().and_then(|v| v.to_str()) != Some(file_name) {
+            return Err(error::ErrorBadRequest("invalid file name\n"));
+        }
+        if config
+            .paste
+            .random_url
+            .as_ref()
+            .is_some_and(|v| v.is_content_address(file_name))
+        {
+            return Err(error::ErrorConflict(
+                "content-addressed files cannot be appended to\n",
+            ));
+        }
+        let path = find_stored_path(&*storage, config, PasteType::File, file_name)?;
+        let upload_path = config
+            .server
+            .get_upload_paths()
+            .find(|v| path.starts_with(v))
+            .unwrap_or(&config.server.upload_path);
+        if !path.is_file() {
+            return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+        }
+        let file_size = fs::metadata(&path)?.len();
+        let total_size = file_size.saturating_add(self.data.len() as u64);
+        if total_size > config.server.max_content_length.as_u64() {
+            return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+        }
+        if self.dry_run {
+            return Ok(file_name.to_string());
+        }
+        if config.security.as_ref().is_some_and(|v| v.clamav.is_some()) {
+            let mut paste = Paste {
+                data: fs::read(&path)?,
+                type_: self.type_,
+                dry_run: self.dry_run,
+                create_new: self.create_new,
+                metadata: None,
+            };
+            paste.data.extend_from_slice(&self.data);
+            paste.scan(file_name, config.security.as_ref()).await?;
+        }
+        quota::make_room(config, self.data.len() as u64)?;
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(&self.data)?;
+        if let Some(mut metadata) = Metadata::read(&*storage, upload_path, &path)? {
+            metadata.size = usize::try_from(total_size).unwrap_or(usize::MAX);
+            metadata.write(&*storage, upload_path, &path)?;
+        }
+        Ok(file_name.to_string())
+    }
+}
+
+/// Returns the path of the stored file with the given name and type.
+///
+/// The file is searched in the [`overflow_paths`] as well.
+/// If it is not found, the path in the upload path is returned.
+///
+/// [`overflow_paths`]: crate::config::ServerConfig::overflow_paths
+fn find_stored_path(
+    storage: &dyn StorageBackend,
+    config: &Config,
+    paste_type: PasteType,
+    file_name: &str,
+) -> Result<PathBuf, Error> {
+    let mut default_path = None;
+    for upload_path in config.server.get_upload_paths() {
+        let path = util::find_file_path(
+            storage,
+            &paste_type.get_path(upload_path)?,
+            file_name,
+            config.paste.shard_length,
+        )?;
+        if storage.exists(&path)? {
+            return Ok(path);
+        }
+        default_path.get_or_insert(path);
+    }
+    Ok(default_path.unwrap_or_default())
+}
+
+/// Returns the random URL configuration.
+///
+/// The number of the stored files is set for the [`adaptive_length`] if the storage is local.
+///
+/// [`adaptive_length`]: crate::random::RandomURLConfig::adaptive_length
+fn get_random_url(storage: &dyn StorageBackend, config: &Config) -> Option<RandomURLConfig> {
+    let mut random_url = config.paste.random_url.clone()?;
+    if random_url.is_adaptive() && storage.is_local() {
+        random_url.file_count = Stats::get_all(config.server.get_upload_paths())
+            .map(|v| v.file_count)
+            .ok();
+    }
+    Some(random_url)
+}
+
+/// Stores the data at a path that is not taken yet.
+///
+/// Returns a conflict error if the path is claimed by another upload in the meantime.
+fn store_new(
+    storage: &dyn StorageBackend,
+    path: &Path,
+    data: &[u8],
+    is_random: bool,
+) -> Result<(), Error> {
+    match storage.store_new(path, data) {
+        Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+            Err(error::ErrorConflict(if is_random {
+                "cannot generate a unique file name\n"
+            } else {
+                "file already exists\n"
+            }))
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Sets the permissions of the stored file if [`file_mode`] is configured for the local storage.
+///
+/// [`file_mode`]: crate::config::StorageConfig::file_mode
+fn set_file_mode(storage: &dyn StorageBackend, path: &Path, config: &Config) -> Result<(), Error> {
+    if let Some(file_mode) = config
+        .storage
+        .as_ref()
+        .and_then(|v| v.get_file_mode())
+        .filter(|_| storage.is_local())
+    {
+        util::set_file_mode(path, file_mode.map_err(error::ErrorInternalServerError)?)?;
+    }
+    Ok(())
+}
+
+/// Parses the URL and checks if its scheme and length are allowed.
+///
+/// Only HTTP(S) URLs are allowed if [`allowed_url_schemes`] is empty.
+/// URLs that are longer than [`max_url_length`] are rejected.
+///
+/// [`allowed_url_schemes`]: crate::config::PasteConfig::allowed_url_schemes
+/// [`max_url_length`]: crate::config::PasteConfig::max_url_length
+fn parse_url(url: &str, config: &Config) -> Result<Url, Error> {
+    let url = match Url::parse(url) {
+        Err(url::ParseError::RelativeUrlWithoutBase)
+            if config.paste.prepend_url_scheme.unwrap_or(false) =>
+        {
+            Url::parse(&format!("https://{}", url.trim()))
+        }
+        url => url,
+    }
+    .map_err(error::ErrorBadRequest)?;
+    let is_allowed = if config.paste.allowed_url_schemes.is_empty() {
+        DEFAULT_URL_SCHEMES.contains(&url.scheme())
+    } else {
+        config
+            .paste
+            .allowed_url_schemes
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(url.scheme()))
+    };
+    if !is_allowed {
+        return Err(error::ErrorBadRequest(format!(
+            "URL scheme is not permitted: {}\n",
+            url.scheme()
+        )));
+    }
+    let max_url_length = config
+        .paste
+        .max_url_length
+        .unwrap_or(DEFAULT_MAX_URL_LENGTH);
+    if url.as_str().len() > max_url_length {
+        return Err(error::ErrorBadRequest(format!(
+            "URL is too long (maximum {max_url_length} characters)\n"
+        )));
+    }
+    Ok(url)
+}
+
+/// Returns the file name of the path.
+fn get_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|v| v.to_string_lossy())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Checks the extension against the allowed extensions.
+///
+/// - If `allowed_extensions` is empty, all extensions are allowed.
+/// - If the extension is not allowed but the inferred one is, the inferred extension is returned.
+/// - Otherwise, returns an error.
+fn resolve_extension(
+    extension: String,
+    file_type: Option<infer::Type>,
+    allowed_extensions: &[String],
+) -> Result<String, Error> {
+    let is_allowed = |extension: &str| {
+        let last_extension = extension.rsplit('.').next().unwrap_or_default();
+        allowed_extensions.iter().any(|v| {
+            let v = v.trim_start_matches('.');
+            v.eq_ignore_ascii_case(extension) || v.eq_ignore_ascii_case(last_extension)
+        })
+    };
+    if allowed_extensions.is_empty() || is_allowed(&extension) {
+        return Ok(extension);
+    }
+    match file_type.map(|t| t.extension()) {
+        Some(inferred_extension) if is_allowed(inferred_extension) => {
+            Ok(inferred_extension.to_string())
+        }
+        _ => Err(error::ErrorUnsupportedMediaType(
+            "this file extension is not permitted\n",
+        )),
+    }
+}
+
+/// Returns the address of the ClamAV daemon and the timeout if the data should be scanned.
+///
+/// The files that are larger than [`clamav_max_size`] are not scanned.
+///
+/// [`clamav_max_size`]: crate::config::SecurityConfig::clamav_max_size
+fn get_scan_target<'a>(
+    size: usize,
+    file_name: &str,
+    security: Option<&'a SecurityConfig>,
+) -> Option<(&'a str, Duration)> {
+    let security = security?;
+    let address = security.clamav.as_deref()?;
+    if security
+        .clamav_max_size
+        .is_some_and(|v| size as u64 > v.as_u64())
+    {
+        info!("Skipped the virus scan of {} (too large)", file_name);
+        return None;
+    }
+    let timeout = security.clamav_timeout.unwrap_or(clamav::DEFAULT_TIMEOUT);
+    Some((address, timeout))
+}
+
+/// Logs the result of the virus scan and rejects the infected files.
+fn check_scan_result(result: IoResult<ScanResult>, file_name: &str) -> Result<(), Error> {
+    match result {
+        Ok(ScanResult::Clean) => {
+            info!("Scanned {}: {}", file_name, ScanResult::Clean);
+            Ok(())
+        }
+        Ok(result) => {
+            info!("Scanned {}: {}", file_name, result);
+            Err(error::ErrorUnprocessableEntity("file is infected\n"))
+        }
+        Err(e) => {
+            error!("Cannot scan {}: {}", file_name, e);
+            Err(error::ErrorServiceUnavailable("cannot scan the file\n"))
+        }
+    }
+}
+
+/// Generates a random suffix for the unique stdin file names.
+fn get_unique_suffix() -> String {
+    RandomURLConfig {
+        length: Some(UNIQUE_STDIN_SUFFIX_LENGTH),
+        type_: RandomURLType::Alphanumeric,
+        ..Default::default()
+    }
+    .generate()
+    .unwrap_or_default()
+}
+
+/// Returns the index of the dot that starts the extension of the file name.
+///
+/// The leading dot of the dotfiles is omitted.
+fn get_extension_index(file_name: &str) -> Option<usize> {
+    file_name
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| *c == '.')
+        .map(|(i, _)| i)
+}
+
+/// Checks the length of the file name against the maximum length in bytes.
+///
+/// The long file names are either rejected or truncated while keeping the extension
+/// (e.g. `long-name.tar.gz` becomes `long.tar.gz`).
+fn limit_file_name(file_name: &str, max_length: usize, truncate: bool) -> Result<String, Error> {
+    if file_name.len() <= max_length {
+        return Ok(file_name.to_string());
+    }
+    let (stem, extension) =
+        file_name.split_at(get_extension_index(file_name).unwrap_or(file_name.len()));
+    let stem_length = max_length.saturating_sub(extension.len());
+    if !truncate || stem_length == 0 {
+        return Err(error::ErrorBadRequest("file name is too long\n"));
+    }
+    let end = (0..=stem_length)
+        .rev()
+        .find(|i| stem.is_char_boundary(*i))
+        .unwrap_or_default();
+    Ok(format!("{}{}", &stem[..end], extension))
+}
+
+/// Appends a numeric suffix to the file name while keeping the extension.
+///
+/// e.g. `file.tar.gz` becomes `file-1.tar.gz`
+fn append_suffix(file_name: &str, suffix: usize) -> String {
+    match get_extension_index(file_name) {
+        Some(i) => format!("{}-{}{}", &file_name[..i], suffix, &file_name[i..]),
+        None => format!("{file_name}-{suffix}"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::random::{RandomURLConfig, RandomURLType};
+    use crate::config::CaseNormalizationConfig;
+    use crate::random::{RandomURLConfig, RandomURLType, TimestampPosition, WordCount};
     use crate::util;
+    use actix_web::http::StatusCode;
     use actix_web::web::Data;
     use awc::ClientBuilder;
     use byte_unit::Byte;
     use std::env;
+    use std::fs;
     use std::str::FromStr;
     use std::time::Duration;
 
@@ -293,7 +1045,7 @@ mod tests {
         config.server.upload_path = env::current_dir()?;
         config.paste.random_url = Some(RandomURLConfig {
             enabled: Some(true),
-            words: Some(3),
+            words: Some(WordCount::Fixed(3)),
             separator: Some(String::from("_")),
             type_: RandomURLType::PetName,
             ..RandomURLConfig::default()
@@ -301,6 +1053,9 @@ mod tests {
         let paste = Paste {
             data: vec![65, 66, 67],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file("test.txt", None, None, &config)?;
         assert_eq!("ABC", fs::read_to_string(&file_name)?);
@@ -321,6 +1076,9 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 115, 117, 115],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file("foo.tar.gz", None, None, &config)?;
         assert_eq!("tessus", fs::read_to_string(&file_name)?);
@@ -337,6 +1095,9 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 115, 117, 115],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file(".foo.tar.gz", None, None, &config)?;
         assert_eq!("tessus", fs::read_to_string(&file_name)?);
@@ -353,17 +1114,40 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 115, 117, 115],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file("foo.tar.gz", None, None, &config)?;
         assert_eq!("tessus", fs::read_to_string(&file_name)?);
         assert!(file_name.ends_with(".tar.gz"));
         fs::remove_file(file_name)?;
 
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Uuid,
+            ..RandomURLConfig::default()
+        });
+        let paste = Paste {
+            data: vec![116, 101, 115, 115, 117, 115],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_file("foo.tar.gz", None, None, &config)?;
+        assert_eq!("tessus", fs::read_to_string(&file_name)?);
+        assert!(file_name.ends_with(".tar.gz"));
+        assert_eq!(36 + ".tar.gz".len(), file_name.len());
+        fs::remove_file(file_name)?;
+
         config.paste.default_extension = String::from("txt");
         config.paste.random_url = None;
         let paste = Paste {
             data: vec![120, 121, 122],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file(".foo", None, None, &config)?;
         assert_eq!("xyz", fs::read_to_string(&file_name)?);
@@ -379,6 +1163,9 @@ mod tests {
         let paste = Paste {
             data: vec![120, 121, 122],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file("random", None, None, &config)?;
         assert_eq!("xyz", fs::read_to_string(&file_name)?);
@@ -399,6 +1186,9 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 115, 117, 115],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file(
             "filename.txt",
@@ -419,6 +1209,9 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 115, 117, 115],
             type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_file(
             "filename.txt",
@@ -442,6 +1235,9 @@ mod tests {
         let paste = Paste {
             data: vec![116, 101, 115, 116],
             type_: PasteType::Oneshot,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let expiry_date = util::get_system_time()?.as_millis() + 100;
         let file_name = paste.store_file("test.file", Some(expiry_date), None, &config)?;
@@ -460,6 +1256,9 @@ mod tests {
         let paste = Paste {
             data: url.as_bytes().to_vec(),
             type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let file_name = paste.store_url(None, &config)?;
         let file_path = PasteType::Url
@@ -473,6 +1272,9 @@ mod tests {
         let paste = Paste {
             data: url.as_bytes().to_vec(),
             type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         assert!(paste.store_url(None, &config).is_err());
 
@@ -481,6 +1283,9 @@ mod tests {
         let mut paste = Paste {
             data: url.as_bytes().to_vec(),
             type_: PasteType::RemoteFile,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
         };
         let client_data = Data::new(
             ClientBuilder::new()
@@ -510,4 +1315,735 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_handle_conflicts() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_handle_conflicts");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!(
+            "foo.tar.gz",
+            paste.store_file("foo.tar.gz", None, None, &config)?
+        );
+        assert!(paste.store_file("foo.tar.gz", None, None, &config).is_err());
+
+        config.paste.handle_conflicts = Some(ConflictHandlingConfig::Rename);
+        assert_eq!(
+            "foo-1.tar.gz",
+            paste.store_file("foo.tar.gz", None, None, &config)?
+        );
+        assert_eq!(
+            "foo-2.tar.gz",
+            paste.store_file("foo.tar.gz", None, None, &config)?
+        );
+
+        config.paste.handle_conflicts = Some(ConflictHandlingConfig::Overwrite);
+        let paste = Paste {
+            data: vec![116, 101, 115, 115, 117, 115],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!(
+            "foo.tar.gz",
+            paste.store_file("foo.tar.gz", None, None, &config)?
+        );
+        assert_eq!(
+            "tessus",
+            fs::read_to_string(config.server.upload_path.join("foo.tar.gz"))?
+        );
+
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: true,
+            metadata: None,
+        };
+        let error = paste
+            .store_file("foo.tar.gz", None, None, &config)
+            .expect_err("file exists");
+        assert_eq!(
+            StatusCode::PRECONDITION_FAILED,
+            error.as_response_error().status_code()
+        );
+        assert_eq!(
+            "tessus",
+            fs::read_to_string(config.server.upload_path.join("foo.tar.gz"))?
+        );
+        assert_eq!(
+            "bar.tar.gz",
+            paste.store_file("bar.tar.gz", None, None, &config)?
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_filename() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_default_filename");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!("stdin.txt", paste.store_file("-", None, None, &config)?);
+        assert_eq!("file.txt", paste.store_file(".", None, None, &config)?);
+        assert_eq!("file.txt", paste.store_file("", None, None, &config)?);
+
+        config.paste.stdin_filename = Some(String::from("eingabe"));
+        config.paste.default_filename = Some(String::from("datei"));
+        assert_eq!("eingabe.txt", paste.store_file("-", None, None, &config)?);
+        assert_eq!("datei.txt", paste.store_file(".", None, None, &config)?);
+        assert_eq!("datei.txt", paste.store_file("", None, None, &config)?);
+
+        config.paste.unique_stdin_filename = Some(true);
+        let file_name = paste.store_file("-", None, None, &config)?;
+        assert!(file_name.starts_with("eingabe-"));
+        assert!(file_name.ends_with(".txt"));
+        assert_eq!("eingabe-.txt".len() + 6, file_name.len());
+        assert_ne!(file_name, paste.store_file("-", None, None, &config)?);
+        assert_eq!("datei.txt", paste.store_file(".", None, None, &config)?);
+        assert_eq!(
+            "eingabe.txt",
+            paste.store_file("-", None, Some(String::from("eingabe.txt")), &config)?
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_infer_extension() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_force_infer_extension");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A],
+            type_: PasteType::File,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!(
+            "data.bin",
+            paste.store_file("data.bin", None, None, &config)?
+        );
+
+        config.paste.force_infer_extension = Some(true);
+        assert_eq!(
+            "data.png",
+            paste.store_file("data.bin", None, None, &config)?
+        );
+        assert_eq!(
+            "data.PNG",
+            paste.store_file("data.PNG", None, None, &config)?
+        );
+        assert_eq!("data.png", paste.store_file("data", None, None, &config)?);
+
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            ..paste
+        };
+        assert_eq!(
+            "data.bin",
+            paste.store_file("data.bin", None, None, &config)?
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_mode() -> Result<(), Error> {
+        use std::os::unix::fs::PermissionsExt;
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_file_mode");
+        fs::create_dir_all(config.server.upload_path.join("url"))?;
+        config.storage = Some(crate::config::StorageConfig {
+            file_mode: Some(String::from("0604")),
+            ..Default::default()
+        });
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_file("mode.txt", None, None, &config)?;
+        let mode = fs::metadata(config.server.upload_path.join(file_name))?
+            .permissions()
+            .mode();
+        assert_eq!(0o604, mode & 0o7777);
+
+        let paste = Paste {
+            data: b"https://orhun.dev".to_vec(),
+            type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_url(None, &config)?;
+        let mode = fs::metadata(config.server.upload_path.join("url").join(file_name))?
+            .permissions()
+            .mode();
+        assert_eq!(0o604, mode & 0o7777);
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_empty_file");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: Vec::new(),
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let error = paste
+            .store_file("empty.txt", None, None, &config)
+            .expect_err("empty file");
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            error.as_response_error().status_code()
+        );
+
+        config.paste.allow_empty = Some(true);
+        assert_eq!(
+            "empty.txt",
+            paste.store_file("empty.txt", None, None, &config)?
+        );
+        assert_eq!(
+            0,
+            fs::metadata(config.server.upload_path.join("empty.txt"))?.len()
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_case() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_normalize_case");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        config.paste.normalize_case = Some(CaseNormalizationConfig::Extension);
+        assert_eq!(
+            "Report.pdf",
+            paste.store_file("Report.PDF", None, None, &config)?
+        );
+
+        config.paste.normalize_case = Some(CaseNormalizationConfig::All);
+        assert!(paste.store_file("REPORT.pdf", None, None, &config).is_ok());
+        assert!(config.server.upload_path.join("report.pdf").exists());
+        assert_eq!(
+            "header.txt",
+            paste.store_file("foo.txt", None, Some(String::from("Header.TXT")), &config)?
+        );
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(1),
+            charset: Some(String::from("A")),
+            ..RandomURLConfig::default()
+        });
+        assert_eq!("A.pdf", paste.store_file("Foo.PDF", None, None, &config)?);
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_random_url_timestamp() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_random_url_timestamp");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(1),
+            charset: Some(String::from("a")),
+            timestamp: TimestampPosition::Prefix,
+            ..RandomURLConfig::default()
+        });
+        let file_name = paste.store_file("foo.tar.gz", None, None, &config)?;
+        assert!(file_name.ends_with("-a.tar.gz"));
+        assert_eq!(
+            8,
+            file_name
+                .split('-')
+                .next()
+                .map(str::len)
+                .unwrap_or_default()
+        );
+
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(1),
+            charset: Some(String::from("a")),
+            timestamp: TimestampPosition::Suffix,
+            ..RandomURLConfig::default()
+        });
+        let file_name = paste.store_file("foo.txt", None, None, &config)?;
+        assert!(file_name.starts_with("a-"));
+        assert_eq!("a-".len() + 8 + ".txt".len(), file_name.len());
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_file_name() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_hash_file_name");
+        fs::create_dir_all(&config.server.upload_path)?;
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Hash,
+            ..RandomURLConfig::default()
+        });
+        let paste = Paste {
+            data: b"tessus".to_vec(),
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_file("foo.tar.gz", None, None, &config)?;
+        assert_eq!("5adfda0b0455efca.tar.gz", file_name);
+        assert_eq!(
+            file_name,
+            paste.store_file("bar.tar.gz", None, None, &config)?
+        );
+        assert_eq!(
+            "5adfda0b0455efca.txt",
+            paste.store_file("foo", None, None, &config)?
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_collision_retries() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_collision_retries");
+        fs::create_dir_all(config.server.upload_path.join("url"))?;
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(1),
+            charset: Some(String::from("a")),
+            collision_retries: Some(0),
+            ..RandomURLConfig::default()
+        });
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!("a.txt", paste.store_file("foo.txt", None, None, &config)?);
+        let error = paste.store_file("foo.txt", None, None, &config).err();
+        assert_eq!(
+            Some(StatusCode::CONFLICT),
+            error.map(|e| e.as_response_error().status_code())
+        );
+        assert_eq!(
+            "ABC",
+            fs::read_to_string(config.server.upload_path.join("a.txt"))?
+        );
+
+        let paste = Paste {
+            data: b"https://orhun.dev".to_vec(),
+            type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!("a", paste.store_url(None, &config)?);
+        assert!(paste.store_url(None, &config).is_err());
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserved_names() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_reserved_names");
+        fs::create_dir_all(config.server.upload_path.join("url"))?;
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        assert_eq!(
+            "health.txt",
+            paste.store_file("health", None, None, &config)?
+        );
+        let error = paste
+            .store_file("foo.txt", None, Some(String::from("Metrics")), &config)
+            .expect_err("reserved name");
+        assert_eq!(
+            StatusCode::CONFLICT,
+            error.as_response_error().status_code()
+        );
+
+        config.paste.reserved_names = vec![String::from("a"), String::from("a.txt")];
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(1),
+            charset: Some(String::from("a")),
+            ..RandomURLConfig::default()
+        });
+        let error = paste
+            .store_file("foo.txt", None, None, &config)
+            .expect_err("reserved name");
+        assert_eq!(
+            StatusCode::CONFLICT,
+            error.as_response_error().status_code()
+        );
+
+        let paste = Paste {
+            data: b"https://orhun.dev".to_vec(),
+            type_: PasteType::Url,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        let error = paste.store_url(None, &config).expect_err("reserved name");
+        assert_eq!(
+            StatusCode::CONFLICT,
+            error.as_response_error().status_code()
+        );
+
+        config.paste.reserved_names.clear();
+        assert_eq!("a", paste.store_url(None, &config)?);
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_adaptive_length() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_adaptive_length");
+        fs::create_dir_all(&config.server.upload_path)?;
+        for i in 0..38 {
+            fs::write(config.server.upload_path.join(format!("{i}.txt")), "")?;
+        }
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            adaptive_length: Some(true),
+            ..RandomURLConfig::default()
+        });
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_file("foo.txt", None, None, &config)?;
+        assert_eq!(Some(3), file_name.strip_suffix(".txt").map(str::len));
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_url() -> Result<(), Error> {
+        let mut config = Config::default();
+        assert_eq!(
+            "https://orhun.dev/",
+            parse_url("https://orhun.dev", &config)?.as_str()
+        );
+        assert_eq!(
+            "http://orhun.dev/",
+            parse_url("http://orhun.dev", &config)?.as_str()
+        );
+        assert!(parse_url("ftp://orhun.dev", &config).is_err());
+        assert!(parse_url("javascript:alert(1)", &config).is_err());
+        assert!(parse_url("testurl.com", &config).is_err());
+
+        config.paste.prepend_url_scheme = Some(true);
+        assert_eq!(
+            "https://testurl.com/",
+            parse_url("testurl.com", &config)?.as_str()
+        );
+
+        config.paste.allowed_url_schemes = vec![String::from("ftp")];
+        assert_eq!(
+            "ftp://orhun.dev/",
+            parse_url("ftp://orhun.dev", &config)?.as_str()
+        );
+        assert!(parse_url("https://orhun.dev", &config).is_err());
+
+        config.paste.allowed_url_schemes = Vec::new();
+        config.paste.max_url_length = Some(20);
+        assert!(parse_url("https://orhun.dev/ab", &config).is_ok());
+        assert!(parse_url("https://orhun.dev/abc", &config).is_err());
+        config.paste.max_url_length = None;
+        assert!(parse_url(&format!("https://orhun.dev/{}", "a".repeat(2048)), &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_url_without_extension() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_store_url_upload");
+        config.paste.default_extension = String::from("bin");
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(6),
+            suffix_mode: Some(true),
+            timestamp: TimestampPosition::Suffix,
+            ..RandomURLConfig::default()
+        });
+        let paste = Paste {
+            data: b"https://orhun.dev".to_vec(),
+            type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_url(None, &config)?;
+        assert!(!file_name.contains('.'));
+        assert!(PasteType::Url
+            .get_path(&config.server.upload_path)?
+            .join(&file_name)
+            .exists());
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_type_dirs() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_create_type_dirs");
+        for type_ in [PasteType::Url, PasteType::OneshotUrl] {
+            let paste = Paste {
+                data: b"https://orhun.dev".to_vec(),
+                type_,
+                dry_run: false,
+                create_new: false,
+                metadata: None,
+            };
+            let file_name = paste.store_url(None, &config)?;
+            assert!(type_
+                .get_path(&config.server.upload_path)?
+                .join(file_name)
+                .is_file());
+        }
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::Oneshot,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let file_name = paste.store_file("once.txt", None, None, &config)?;
+        assert!(PasteType::Oneshot
+            .get_path(&config.server.upload_path)?
+            .join(file_name)
+            .is_file());
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn test_virus_scan() -> Result<(), Error> {
+        let mut config = Config {
+            security: Some(SecurityConfig {
+                clamav: Some(crate::clamav::tests::spawn_daemon()?),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut paste = Paste {
+            data: b"harmless".to_vec(),
+            type_: PasteType::File,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        paste.scan("clean.txt", config.security.as_ref()).await?;
+        assert_eq!(b"harmless", paste.data.as_slice());
+        paste.data = b"virus".to_vec();
+        let error = paste
+            .scan("infected.txt", config.security.as_ref())
+            .await
+            .expect_err("infected file");
+        assert_eq!(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            error.as_response_error().status_code()
+        );
+        assert_eq!(b"virus", paste.data.as_slice());
+        paste.scan("unconfigured.txt", None).await?;
+
+        let mut skip_config = config.clone();
+        if let Some(security) = &mut skip_config.security {
+            security.clamav_max_size = Some(Byte::from_u64(4));
+        }
+        paste
+            .scan("large.txt", skip_config.security.as_ref())
+            .await?;
+        let mut token_config = config.clone();
+        crate::config::TokenConfig {
+            skip_virus_scan: Some(true),
+            ..Default::default()
+        }
+        .apply(&mut token_config);
+        paste
+            .scan("trusted.txt", token_config.security.as_ref())
+            .await?;
+
+        if let Some(security) = &mut config.security {
+            security.clamav = Some(String::from("127.0.0.1:1"));
+        }
+        let error = paste
+            .scan("unscanned.txt", config.security.as_ref())
+            .await
+            .expect_err("unreachable daemon");
+        assert_eq!(
+            StatusCode::SERVICE_UNAVAILABLE,
+            error.as_response_error().status_code()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_file_name() -> Result<(), Error> {
+        assert_eq!("short.txt", limit_file_name("short.txt", 9, false)?);
+        assert!(limit_file_name("longer.txt", 9, false).is_err());
+        assert_eq!(
+            "long.tar.gz",
+            limit_file_name("long-name.tar.gz", 11, true)?
+        );
+        assert_eq!(".hidd", limit_file_name(".hidden", 5, true)?);
+        assert_eq!("ö.txt", limit_file_name("öö.txt", 7, true)?);
+        assert!(limit_file_name("name.tar.gz", 7, true).is_err());
+
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_limit_file_name");
+        fs::create_dir_all(&config.server.upload_path)?;
+        config.paste.max_filename_length = Some(8);
+        let paste = Paste {
+            data: vec![65, 66, 67],
+            type_: PasteType::File,
+            dry_run: true,
+            create_new: false,
+            metadata: None,
+        };
+        let error = paste
+            .store_file("too-long.txt", None, None, &config)
+            .expect_err("long file name");
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            error.as_response_error().status_code()
+        );
+        config.paste.truncate_long_filenames = Some(true);
+        assert_eq!(
+            "too-.txt",
+            paste.store_file("too-long.txt", None, None, &config)?
+        );
+        assert_eq!(
+            "head.txt",
+            paste.store_file("x.txt", None, Some(String::from("header.txt")), &config)?
+        );
+
+        fs::remove_dir_all(config.server.upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_suffix() {
+        assert_eq!("foo-1.tar.gz", append_suffix("foo.tar.gz", 1));
+        assert_eq!(".foo-2.txt", append_suffix(".foo.txt", 2));
+        assert_eq!(".foo-3", append_suffix(".foo", 3));
+        assert_eq!("foo-4", append_suffix("foo", 4));
+    }
+
+    #[test]
+    fn test_resolve_extension() -> Result<(), Error> {
+        let allowed_extensions = [String::from("txt"), String::from(".png")];
+        assert_eq!(
+            "txt",
+            resolve_extension(String::from("txt"), None, &allowed_extensions)?
+        );
+        assert_eq!(
+            "PNG",
+            resolve_extension(String::from("PNG"), None, &allowed_extensions)?
+        );
+        assert_eq!(
+            "png",
+            resolve_extension(
+                String::from("jpg"),
+                infer::get(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]),
+                &allowed_extensions
+            )?
+        );
+        assert!(resolve_extension(String::from("exe"), None, &allowed_extensions).is_err());
+        assert_eq!("exe", resolve_extension(String::from("exe"), None, &[])?);
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn test_store_remote_file_invalid_scheme() -> Result<(), Error> {
+        let config = Config::default();
+        for url in ["ftp://example.com/file.txt", "file:///etc/passwd"] {
+            let mut paste = Paste {
+                data: url.as_bytes().to_vec(),
+                type_: PasteType::RemoteFile,
+                dry_run: false,
+                create_new: false,
+                metadata: None,
+            };
+            let error = paste
+                .store_remote_file(None, &Client::default(), &RwLock::new(config.clone()))
+                .await
+                .expect_err("non-HTTP URL should be rejected");
+            assert_eq!(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                error.as_response_error().status_code()
+            );
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,138 @@
+use crate::config::{Config, QuotaPolicy};
+use crate::metadata::Metadata;
+use crate::storage::LocalStorage;
+use crate::thumbnail;
+use crate::util;
+use actix_web::{error, Error};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+
+/// Makes room for storing a new file with the given size.
+///
+/// The upload path is scanned for the stored files and the expired files are removed first
+/// if the [`max_total_size`] or [`max_files`] limit would be exceeded. After that, the
+/// oldest files (by modification time) are evicted if the [`quota_policy`] is [`Evict`].
+///
+/// Returns an error if there is still not enough room for the file.
+///
+/// [`max_total_size`]: crate::config::StorageConfig::max_total_size
+/// [`max_files`]: crate::config::StorageConfig::max_files
+/// [`quota_policy`]: crate::config::StorageConfig::quota_policy
+/// [`Evict`]: QuotaPolicy::Evict
+pub fn make_room(config: &Config, size: u64) -> Result<(), Error> {
+    let Some(storage_config) = &config.storage else {
+        return Ok(());
+    };
+    if storage_config.max_total_size.is_none() && storage_config.max_files.is_none() {
+        return Ok(());
+    }
+    let max_total_size = storage_config
+        .max_total_size
+        .map(|v| v.as_u64())
+        .unwrap_or(u64::MAX);
+    let max_files = storage_config.max_files.unwrap_or(usize::MAX);
+    let upload_path = &config.server.upload_path;
+    let expired_files = util::get_expired_files(upload_path)
+        .into_iter()
+        .collect::<HashSet<PathBuf>>();
+    let mut files = util::get_stored_files(upload_path)
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let size = metadata.len() + thumbnail::get_cached_size(upload_path, &path).unwrap_or(0);
+            Some((path, size, metadata.modified().ok()?))
+        })
+        .collect::<Vec<_>>();
+    // Remove the expired files before the oldest ones.
+    files.sort_by_key(|(path, _, modified)| (!expired_files.contains(path), *modified));
+    let mut total_size = files.iter().map(|(_, size, _)| size).sum::<u64>();
+    let mut file_count = files.len();
+    let is_exceeded = |total_size: u64, file_count: usize| {
+        total_size.saturating_add(size) > max_total_size || file_count >= max_files
+    };
+    for (path, file_size, _) in files {
+        if !is_exceeded(total_size, file_count) {
+            break;
+        }
+        let is_expired = expired_files.contains(&path);
+        if !is_expired && storage_config.quota_policy != QuotaPolicy::Evict {
+            break;
+        }
+        remove_file(upload_path, &path, is_expired)?;
+        if is_expired {
+            info!("Removed expired file: {:?}", path);
+        } else {
+            info!("Evicted file for making room: {:?}", path);
+        }
+        total_size = total_size.saturating_sub(file_size);
+        file_count -= 1;
+    }
+    if is_exceeded(total_size, file_count) {
+        warn!("storage quota is exceeded");
+        return Err(error::ErrorPayloadTooLarge("storage quota exceeded\n"));
+    }
+    Ok(())
+}
+
+/// Removes the file along with its metadata and thumbnails.
+///
+/// The metadata of an expired file is kept if it belongs to a newer file with the same name
+/// (see [`Metadata::delete_expired`]).
+fn remove_file(upload_path: &Path, path: &Path, is_expired: bool) -> IoResult<()> {
+    fs::remove_file(path)?;
+    if is_expired {
+        Metadata::delete_expired(&LocalStorage, upload_path, path)?;
+    } else {
+        Metadata::delete(&LocalStorage, upload_path, path)?;
+    }
+    thumbnail::delete(upload_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StorageConfig;
+    use byte_unit::Byte;
+    use std::env;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_make_room() -> Result<(), Error> {
+        let upload_path = env::current_dir()?.join("test_quota_upload");
+        fs::create_dir_all(&upload_path)?;
+        let mut config = Config::default();
+        config.server.upload_path = upload_path.clone();
+        config.storage = Some(StorageConfig {
+            max_total_size: Some(Byte::from_u64(10)),
+            max_files: Some(3),
+            ..StorageConfig::default()
+        });
+        make_room(&config, 10)?;
+        assert!(make_room(&config, 11).is_err());
+
+        fs::write(upload_path.join("first.txt"), "first")?;
+        thread::sleep(Duration::from_millis(10));
+        fs::write(upload_path.join("second.txt"), "sec")?;
+        make_room(&config, 1)?;
+        assert!(make_room(&config, 3).is_err());
+        fs::write(upload_path.join("expired.txt.1"), "x")?;
+        make_room(&config, 1)?;
+        assert!(!upload_path.join("expired.txt.1").exists());
+        assert!(make_room(&config, 3).is_err());
+        assert!(upload_path.join("first.txt").exists());
+
+        if let Some(storage_config) = config.storage.as_mut() {
+            storage_config.quota_policy = QuotaPolicy::Evict;
+        }
+        make_room(&config, 3)?;
+        assert!(!upload_path.join("first.txt").exists());
+        assert!(upload_path.join("second.txt").exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
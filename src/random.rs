@@ -1,4 +1,30 @@
-use rand::{distributions::Alphanumeric, Rng};
+use crate::util;
+use rand::{distributions::Alphanumeric, seq::SliceRandom, Rng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters that are easy to confuse with each other.
+const AMBIGUOUS_CHARACTERS: &str = "0O1lI";
+
+/// Default length of the random string.
+const DEFAULT_LENGTH: usize = 8;
+
+/// Default number of the hexadecimal characters of the content hash.
+const DEFAULT_HASH_LENGTH: usize = 16;
+
+/// Number of the hexadecimal characters of a SHA256 digest.
+const MAX_HASH_LENGTH: usize = 64;
+
+/// Default number of retries for generating an unused random URL.
+const DEFAULT_COLLISION_RETRIES: usize = 5;
+
+/// Number of retries for generating a random URL without the blocked words.
+const BLOCKLIST_RETRIES: usize = 10;
+
+/// Number of the alphanumeric characters.
+const ALPHANUMERIC_LENGTH: usize = 62;
+
+/// Inverse of the maximum probability of colliding with a stored file for the adaptive length.
+const ADAPTIVE_COLLISION_RATIO: u128 = 100;
 
 /// Random URL configuration.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
@@ -7,53 +33,409 @@ pub struct RandomURLConfig {
     #[deprecated(note = "disable by commenting out [paste].random_url")]
     pub enabled: Option<bool>,
     /// Count of words that pet name will include.
-    pub words: Option<u8>,
+    ///
+    /// Either a fixed count (e.g. `2`) or a range (e.g. `[2, 4]`).
+    pub words: Option<WordCount>,
     /// Separator between the words.
     pub separator: Option<String>,
+    /// Capitalize the words of the pet name (e.g. `HappyBlueFox`).
+    pub capitalize: Option<bool>,
+    /// Count of random digits to append to the pet name.
+    pub append_number: Option<u8>,
     /// Length of the random string to generate.
     pub length: Option<usize>,
+    /// Minimum length of the random string.
+    ///
+    /// The length is chosen randomly between `min_length` and `max_length` if `length` is not set.
+    pub min_length: Option<usize>,
+    /// Maximum length of the random string.
+    ///
+    /// Also limits the length that is requested per upload.
+    pub max_length: Option<usize>,
+    /// Choose the length of the random string based on the number of the stored files.
+    ///
+    /// The shortest length that keeps the collision probability below 1% is used.
+    pub adaptive_length: Option<bool>,
+    /// Number of the stored files for the [adaptive length](Self::adaptive_length).
+    #[serde(skip)]
+    pub file_count: Option<usize>,
+    /// Characters to use in the random string (overrides the character classes).
+    pub charset: Option<String>,
+    /// Include uppercase letters in the random string.
+    pub uppercase: Option<bool>,
+    /// Include digits in the random string.
+    pub digits: Option<bool>,
+    /// Exclude the characters that look alike (e.g. `0`, `O`, `l`, `1`).
+    pub exclude_ambiguous: Option<bool>,
     /// Type of the random URL.
     #[serde(rename = "type")]
     pub type_: RandomURLType,
     /// Append a random string to the original filename.
     pub suffix_mode: Option<bool>,
+    /// Number of retries for generating a new random URL if the generated one is taken.
+    pub collision_retries: Option<usize>,
+    /// Position of the date in the random URL (e.g. `20240101-petname`).
+    #[serde(default)]
+    pub timestamp: TimestampPosition,
+    /// Words that the random URLs must not contain (case-insensitive).
+    ///
+    /// A new URL is generated (up to a few times) if the generated one contains any of them.
+    #[serde(default)]
+    pub blocklist: Vec<String>,
 }
 
 #[allow(deprecated)]
 impl RandomURLConfig {
     /// Generates and returns a random URL (if `enabled`).
+    ///
+    /// For the [`Hash`](RandomURLType::Hash) type, a random data is hashed.
+    /// Use [`generate_for`](Self::generate_for) for deriving the URL from the content.
     pub fn generate(&self) -> Option<String> {
+        self.generate_for(uuid::Uuid::new_v4().as_bytes())
+    }
+
+    /// Generates and returns a URL for the given data (if `enabled`).
+    ///
+    /// The data is only used by the [`Hash`](RandomURLType::Hash) type.
+    pub fn generate_for(&self, data: &[u8]) -> Option<String> {
         if !self.enabled.unwrap_or(true) {
             return None;
         }
+        let mut random_text = self.generate_text(data)?;
+        // The hash of the same data would not change.
+        if !matches!(self.type_, RandomURLType::Hash) {
+            let mut retries = 0;
+            while self.is_blocked(&random_text) {
+                if retries == BLOCKLIST_RETRIES {
+                    warn!("Cannot generate a random URL without the blocked words");
+                    break;
+                }
+                retries += 1;
+                random_text = self.generate_text(data)?;
+            }
+        }
+        Some(match self.timestamp {
+            TimestampPosition::None => random_text,
+            TimestampPosition::Prefix => format!("{}-{random_text}", get_date()),
+            TimestampPosition::Suffix => format!("{random_text}-{}", get_date()),
+        })
+    }
+
+    /// Generates the random text for the configured type.
+    fn generate_text(&self, data: &[u8]) -> Option<String> {
         Some(match self.type_ {
-            RandomURLType::PetName => petname::petname(
-                self.words.unwrap_or(2),
-                self.separator.as_deref().unwrap_or("-"),
-            ),
-            RandomURLType::Alphanumeric => rand::thread_rng()
-                .sample_iter(&Alphanumeric)
-                .take(self.length.unwrap_or(8))
-                .map(char::from)
-                .collect::<String>(),
+            RandomURLType::PetName => self.generate_pet_name(),
+            RandomURLType::Alphanumeric => match self.get_charset() {
+                Some(charset) => {
+                    let mut rng = rand::thread_rng();
+                    (0..self.get_length())
+                        .filter_map(|_| charset.choose(&mut rng))
+                        .collect::<String>()
+                }
+                None => rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(self.get_length())
+                    .map(char::from)
+                    .collect::<String>(),
+            },
+            RandomURLType::Uuid => uuid::Uuid::new_v4().to_string(),
+            RandomURLType::Hash => {
+                let mut digest = util::sha256_digest(data).ok()?;
+                digest.truncate(self.length.unwrap_or(DEFAULT_HASH_LENGTH));
+                digest
+            }
         })
     }
+
+    /// Checks if the text contains any of the blocked words.
+    fn is_blocked(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.blocklist
+            .iter()
+            .any(|word| text.contains(&word.to_lowercase()))
+    }
+
+    /// Generates a random pet name.
+    fn generate_pet_name(&self) -> String {
+        let separator = self.separator.as_deref().unwrap_or("-");
+        let mut words = petname::petname(self.words.map(|v| v.get()).unwrap_or(2), "-")
+            .split('-')
+            .map(|word| {
+                if self.capitalize.unwrap_or(false) {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<String>>();
+        if let Some(digits) = self.append_number.filter(|v| *v > 0) {
+            let mut rng = rand::thread_rng();
+            words.push(
+                (0..digits)
+                    .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+                    .collect(),
+            );
+        }
+        words.join(separator)
+    }
+
+    /// Checks if the same data always results in the same URL.
+    pub fn is_content_addressed(&self) -> bool {
+        self.enabled.unwrap_or(true) && matches!(self.type_, RandomURLType::Hash)
+    }
+
+    /// Checks if the file is named after the digest of its content.
+    ///
+    /// The name (without the extension) must be a digest of the configured length.
+    pub fn is_content_address(&self, file_name: &str) -> bool {
+        let name = file_name.split('.').next().unwrap_or_default();
+        self.is_content_addressed()
+            && name.len() == self.length.unwrap_or(DEFAULT_HASH_LENGTH)
+            && name
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, 'a'..='f'))
+    }
+
+    /// Returns the number of retries for generating an unused random URL.
+    pub fn get_collision_retries(&self) -> usize {
+        self.collision_retries.unwrap_or(DEFAULT_COLLISION_RETRIES)
+    }
+
+    /// Checks if the length of the random string depends on the number of the stored files.
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive_length.unwrap_or(false)
+            && self.length.is_none()
+            && matches!(self.type_, RandomURLType::Alphanumeric)
+    }
+
+    /// Returns the length of the random string to generate.
+    fn get_length(&self) -> usize {
+        match (self.length, self.min_length, self.max_length) {
+            (Some(length), _, _) => length,
+            (None, min_length, max_length) if self.is_adaptive() && self.file_count.is_some() => {
+                let charset_length = self
+                    .get_charset()
+                    .map(|v| v.len())
+                    .unwrap_or(ALPHANUMERIC_LENGTH);
+                get_adaptive_length(charset_length, self.file_count.unwrap_or_default())
+                    .max(min_length.unwrap_or(1))
+                    .min(max_length.unwrap_or(usize::MAX))
+            }
+            (None, Some(min_length), Some(max_length)) if min_length <= max_length => {
+                rand::thread_rng().gen_range(min_length..=max_length)
+            }
+            _ => DEFAULT_LENGTH,
+        }
+    }
+
+    /// Limits the requested length of the random string to `max_length`.
+    ///
+    /// Returns `None` if the requested length is less than `min_length`.
+    pub fn clamp_length(&self, length: usize) -> Option<usize> {
+        if length < self.min_length.unwrap_or(1) {
+            return None;
+        }
+        Some(
+            self.max_length
+                .map(|max_length| length.min(max_length))
+                .unwrap_or(length),
+        )
+    }
+
+    /// Checks if the settings are coherent for the configured type.
+    ///
+    /// Returns the found problems.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.blocklist.iter().any(|v| v.is_empty()) {
+            problems.push(String::from(
+                "[paste].random_url.blocklist must not contain empty words",
+            ));
+        }
+        if self.adaptive_length.unwrap_or(false) {
+            if !matches!(self.type_, RandomURLType::Alphanumeric) {
+                problems.push(String::from(
+                    "[paste].random_url.adaptive_length is only supported for the alphanumeric type",
+                ));
+            } else if self.length.is_some() {
+                problems.push(String::from(
+                    "[paste].random_url.length must not be set with adaptive_length",
+                ));
+            }
+        }
+        match self.type_ {
+            RandomURLType::PetName => match self.words {
+                Some(WordCount::Fixed(0)) | Some(WordCount::Range(0, _)) => {
+                    problems.push(String::from(
+                        "[paste].random_url.words must be greater than 0",
+                    ));
+                }
+                Some(WordCount::Range(min, max)) if min > max => {
+                    problems.push(String::from(
+                        "[paste].random_url.words must not have a minimum greater than the maximum",
+                    ));
+                }
+                _ => {}
+            },
+            RandomURLType::Alphanumeric => {
+                if self.length == Some(0) || self.max_length == Some(0) {
+                    problems.push(String::from(
+                        "[paste].random_url.length must be greater than 0",
+                    ));
+                }
+                if let (Some(min_length), Some(max_length)) = (self.min_length, self.max_length) {
+                    if min_length > max_length {
+                        problems.push(String::from(
+                            "[paste].random_url.min_length must not be greater than max_length",
+                        ));
+                    }
+                }
+                if let Some(length) = self.length {
+                    if self.min_length.map(|v| length < v).unwrap_or(false)
+                        || self.max_length.map(|v| length > v).unwrap_or(false)
+                    {
+                        problems.push(String::from(
+                            "[paste].random_url.length must be between min_length and max_length",
+                        ));
+                    }
+                }
+                if self.get_charset().map(|v| v.is_empty()).unwrap_or(false) {
+                    problems.push(String::from(
+                        "[paste].random_url does not result in any characters to choose from",
+                    ));
+                }
+            }
+            RandomURLType::Uuid => {}
+            RandomURLType::Hash => {
+                if self.length.is_some_and(|v| v == 0 || v > MAX_HASH_LENGTH) {
+                    problems.push(format!(
+                        "[paste].random_url.length must be between 1 and {MAX_HASH_LENGTH} for the hash type"
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Returns the characters to generate the random string from.
+    ///
+    /// Returns `None` if the default alphanumeric characters should be used.
+    fn get_charset(&self) -> Option<Vec<char>> {
+        let exclude_ambiguous = self.exclude_ambiguous.unwrap_or(false);
+        let mut charset: Vec<char> = match self.charset.as_deref().filter(|v| !v.is_empty()) {
+            Some(charset) => charset.chars().collect(),
+            None if self.uppercase.unwrap_or(true)
+                && self.digits.unwrap_or(true)
+                && !exclude_ambiguous =>
+            {
+                return None;
+            }
+            None => {
+                let mut charset: Vec<char> = ('a'..='z').collect();
+                if self.uppercase.unwrap_or(true) {
+                    charset.extend('A'..='Z');
+                }
+                if self.digits.unwrap_or(true) {
+                    charset.extend('0'..='9');
+                }
+                charset
+            }
+        };
+        if exclude_ambiguous {
+            charset.retain(|c| !AMBIGUOUS_CHARACTERS.contains(*c));
+        }
+        charset.sort_unstable();
+        charset.dedup();
+        Some(charset)
+    }
+}
+
+/// Returns the shortest length that keeps the probability of colliding with
+/// one of the stored files below `1 / ADAPTIVE_COLLISION_RATIO`.
+fn get_adaptive_length(charset_length: usize, file_count: usize) -> usize {
+    let charset_length = u128::try_from(charset_length).unwrap_or(u128::MAX);
+    if charset_length < 2 {
+        return DEFAULT_LENGTH;
+    }
+    let required = ADAPTIVE_COLLISION_RATIO.saturating_mul(
+        u128::try_from(file_count)
+            .unwrap_or(u128::MAX)
+            .saturating_add(1),
+    );
+    let mut length = 1;
+    let mut combinations = charset_length;
+    while combinations < required {
+        combinations = combinations.saturating_mul(charset_length);
+        length += 1;
+    }
+    length
+}
+
+/// Returns the current date in UTC (e.g. `20240101`).
+fn get_date() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_secs())
+        .unwrap_or_default();
+    let date = uts2ts::uts2ts(i64::try_from(seconds).unwrap_or(i64::MAX));
+    format!("{:04}{:02}{:02}", date.year, date.month, date.day)
+}
+
+/// Number of the words in a pet name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum WordCount {
+    /// Fixed number of words (e.g. `2`).
+    Fixed(u8),
+    /// Range of the number of words (e.g. `[2, 4]`).
+    ///
+    /// The number is chosen randomly between the minimum and the maximum (inclusive).
+    Range(u8, u8),
+}
+
+impl WordCount {
+    /// Returns the number of words to generate.
+    pub fn get(&self) -> u8 {
+        match *self {
+            Self::Fixed(count) => count,
+            Self::Range(min, max) if min < max => rand::thread_rng().gen_range(min..=max),
+            Self::Range(min, _) => min,
+        }
+    }
+}
+
+/// Position of the date in the random URL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampPosition {
+    /// Do not include the date.
+    #[default]
+    None,
+    /// Prepend the date (e.g. `20240101-petname`).
+    Prefix,
+    /// Append the date (e.g. `petname-20240101`).
+    Suffix,
 }
 
 /// Type of the random URL.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RandomURLType {
     /// Generate a random pet name.
+    #[default]
     PetName,
     /// Generate a random alphanumeric string.
     Alphanumeric,
-}
-
-impl Default for RandomURLType {
-    fn default() -> Self {
-        Self::PetName
-    }
+    /// Generate a random UUID (version 4).
+    Uuid,
+    /// Use the SHA256 digest of the content (e.g. first 16 hexadecimal characters).
+    ///
+    /// The identical files get the same URL.
+    Hash,
 }
 
 #[cfg(test)]
@@ -65,7 +447,7 @@ mod tests {
     fn test_generate_url() {
         let random_config = RandomURLConfig {
             enabled: Some(true),
-            words: Some(3),
+            words: Some(WordCount::Fixed(3)),
             separator: Some(String::from("~")),
             type_: RandomURLType::PetName,
             ..RandomURLConfig::default()
@@ -75,6 +457,22 @@ mod tests {
             .expect("cannot generate random URL");
         assert_eq!(3, random_url.split('~').count());
 
+        let random_config = RandomURLConfig {
+            words: Some(WordCount::Fixed(3)),
+            separator: Some(String::new()),
+            capitalize: Some(true),
+            append_number: Some(4),
+            type_: RandomURLType::PetName,
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        let (words, number) = random_url.split_at(random_url.len() - 4);
+        assert_eq!(3, words.chars().filter(|c| c.is_ascii_uppercase()).count());
+        assert!(words.starts_with(|c: char| c.is_ascii_uppercase()));
+        assert!(number.chars().all(|c| c.is_ascii_digit()));
+
         let random_config = RandomURLConfig {
             length: Some(21),
             type_: RandomURLType::Alphanumeric,
@@ -85,10 +483,239 @@ mod tests {
             .expect("cannot generate random URL");
         assert_eq!(21, random_url.len());
 
+        let random_config = RandomURLConfig {
+            length: Some(100),
+            type_: RandomURLType::Alphanumeric,
+            uppercase: Some(false),
+            exclude_ambiguous: Some(true),
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        assert_eq!(100, random_url.len());
+        assert!(random_url
+            .chars()
+            .all(|c| (c.is_ascii_lowercase() || c.is_ascii_digit())
+                && !AMBIGUOUS_CHARACTERS.contains(c)));
+
+        let random_config = RandomURLConfig {
+            length: Some(10),
+            type_: RandomURLType::Alphanumeric,
+            charset: Some(String::from("ab")),
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        assert_eq!(10, random_url.len());
+        assert!(random_url.chars().all(|c| c == 'a' || c == 'b'));
+
+        let random_config = RandomURLConfig {
+            type_: RandomURLType::Uuid,
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        let uuid = uuid::Uuid::parse_str(&random_url).expect("invalid UUID");
+        assert_eq!(Some(uuid::Version::Random), uuid.get_version());
+
         let random_config = RandomURLConfig {
             enabled: Some(false),
             ..RandomURLConfig::default()
         };
         assert!(random_config.generate().is_none());
     }
+
+    #[test]
+    fn test_generate_url_timestamp() {
+        let random_config = RandomURLConfig {
+            words: Some(WordCount::Fixed(2)),
+            timestamp: TimestampPosition::Prefix,
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        let (date, pet_name) = random_url.split_once('-').expect("date is not found");
+        assert_eq!(8, date.len());
+        assert!(date.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(2, pet_name.split('-').count());
+
+        let random_config = RandomURLConfig {
+            length: Some(4),
+            type_: RandomURLType::Alphanumeric,
+            timestamp: TimestampPosition::Suffix,
+            ..RandomURLConfig::default()
+        };
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        let (random_text, date) = random_url.split_once('-').expect("date is not found");
+        assert_eq!(4, random_text.len());
+        assert_eq!(get_date(), date);
+    }
+
+    #[test]
+    fn test_adaptive_length() {
+        assert_eq!(2, get_adaptive_length(62, 0));
+        assert_eq!(2, get_adaptive_length(62, 37));
+        assert_eq!(3, get_adaptive_length(62, 38));
+        assert_eq!(5, get_adaptive_length(62, 1_000_000));
+        assert_eq!(7, get_adaptive_length(10, 10_000));
+
+        let mut random_config = RandomURLConfig {
+            adaptive_length: Some(true),
+            type_: RandomURLType::Alphanumeric,
+            ..RandomURLConfig::default()
+        };
+        assert_eq!(DEFAULT_LENGTH, random_config.get_length());
+        random_config.file_count = Some(1_000_000);
+        let random_url = random_config
+            .generate()
+            .expect("cannot generate random URL");
+        assert_eq!(5, random_url.len());
+
+        random_config.min_length = Some(6);
+        assert_eq!(6, random_config.get_length());
+        random_config.min_length = None;
+        random_config.max_length = Some(4);
+        assert_eq!(4, random_config.get_length());
+        random_config.length = Some(10);
+        assert!(!random_config.is_adaptive());
+        assert_eq!(10, random_config.get_length());
+        assert!(!random_config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_word_count() -> Result<(), config::ConfigError> {
+        for (words, expected) in [
+            ("3", WordCount::Fixed(3)),
+            ("[2, 4]", WordCount::Range(2, 4)),
+        ] {
+            let random_config = config::Config::builder()
+                .add_source(config::File::from_str(
+                    &format!("type = \"petname\"\nwords = {words}"),
+                    config::FileFormat::Toml,
+                ))
+                .build()?
+                .try_deserialize::<RandomURLConfig>()?;
+            assert_eq!(Some(expected), random_config.words);
+        }
+
+        let random_config = RandomURLConfig {
+            words: Some(WordCount::Range(2, 4)),
+            type_: RandomURLType::PetName,
+            ..RandomURLConfig::default()
+        };
+        for _ in 0..10 {
+            let random_url = random_config
+                .generate()
+                .expect("cannot generate random URL");
+            assert!((2..=4).contains(&random_url.split('-').count()));
+        }
+        assert!(random_config.validate().is_empty());
+
+        let random_config = RandomURLConfig {
+            words: Some(WordCount::Range(4, 2)),
+            ..RandomURLConfig::default()
+        };
+        assert!(!random_config.validate().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_url_blocklist() {
+        let random_config = RandomURLConfig {
+            length: Some(1),
+            charset: Some(String::from("abcdefghijklmnopqrst")),
+            type_: RandomURLType::Alphanumeric,
+            blocklist: vec![String::from("A")],
+            ..RandomURLConfig::default()
+        };
+        for _ in 0..100 {
+            let random_url = random_config
+                .generate()
+                .expect("cannot generate random URL");
+            assert_ne!("a", random_url);
+        }
+        assert!(random_config.is_blocked("BAR"));
+        assert!(!random_config.is_blocked("foo"));
+
+        let random_config = RandomURLConfig {
+            charset: Some(String::from("a")),
+            type_: RandomURLType::Alphanumeric,
+            blocklist: vec![String::from("a")],
+            ..RandomURLConfig::default()
+        };
+        assert_eq!(Some(String::from("aaaaaaaa")), random_config.generate());
+
+        let random_config = RandomURLConfig {
+            blocklist: vec![String::new()],
+            ..RandomURLConfig::default()
+        };
+        assert_eq!(
+            vec![String::from(
+                "[paste].random_url.blocklist must not contain empty words"
+            )],
+            random_config.validate()
+        );
+    }
+
+    #[test]
+    fn test_generate_url_hash() {
+        let random_config = RandomURLConfig {
+            type_: RandomURLType::Hash,
+            ..RandomURLConfig::default()
+        };
+        assert!(random_config.is_content_addressed());
+        assert_eq!(
+            Some(String::from("2cf24dba5fb0a30e")),
+            random_config.generate_for(b"hello")
+        );
+        assert_eq!(
+            random_config.generate_for(b"hello"),
+            random_config.generate_for(b"hello")
+        );
+        assert_ne!(random_config.generate(), random_config.generate());
+        assert!(random_config.validate().is_empty());
+
+        let random_config = RandomURLConfig {
+            type_: RandomURLType::Hash,
+            length: Some(65),
+            ..RandomURLConfig::default()
+        };
+        assert_eq!(1, random_config.validate().len());
+    }
+
+    #[test]
+    fn test_generate_url_length_range() {
+        let random_config = RandomURLConfig {
+            min_length: Some(4),
+            max_length: Some(6),
+            type_: RandomURLType::Alphanumeric,
+            ..RandomURLConfig::default()
+        };
+        for _ in 0..100 {
+            let random_url = random_config
+                .generate()
+                .expect("cannot generate random URL");
+            assert!((4..=6).contains(&random_url.len()));
+        }
+        assert!(random_config.validate().is_empty());
+
+        assert_eq!(None, random_config.clamp_length(3));
+        assert_eq!(Some(5), random_config.clamp_length(5));
+        assert_eq!(Some(6), random_config.clamp_length(100));
+
+        let random_config = RandomURLConfig {
+            length: Some(10),
+            min_length: Some(12),
+            max_length: Some(11),
+            type_: RandomURLType::Alphanumeric,
+            ..RandomURLConfig::default()
+        };
+        assert_eq!(2, random_config.validate().len());
+    }
 }
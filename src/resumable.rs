@@ -0,0 +1,242 @@
+use crate::util;
+use actix_web::{error, Error};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Directory of the incomplete uploads in the upload path.
+pub const UPLOADS_DIR: &str = ".uploads";
+
+/// Default value of [`resumable_upload_timeout`].
+///
+/// [`resumable_upload_timeout`]: crate::config::PasteConfig::resumable_upload_timeout
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Incomplete upload that can be resumed.
+///
+/// The state is stored as a JSON file next to the received data in the
+/// [`uploads directory`](UPLOADS_DIR).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumableUpload {
+    /// Identifier of the upload.
+    pub id: String,
+    /// Total size of the upload in bytes.
+    pub length: u64,
+    /// File name sent by the client.
+    pub file_name: Option<String>,
+}
+
+/// Progress of a resumable upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadStatus {
+    /// Number of the received bytes.
+    pub received_bytes: u64,
+    /// Total size of the upload in bytes.
+    pub total_bytes: u64,
+    /// Percentage of the received bytes.
+    pub percentage: f64,
+}
+
+impl ResumableUpload {
+    /// Creates a new upload with the given total size.
+    pub fn create(upload_path: &Path, length: u64, file_name: Option<String>) -> IoResult<Self> {
+        let upload = Self {
+            id: Uuid::new_v4().simple().to_string(),
+            length,
+            file_name,
+        };
+        let (state_path, data_path) = get_paths(upload_path, &upload.id)?;
+        fs::create_dir_all(upload_path.join(UPLOADS_DIR))?;
+        fs::write(&state_path, serde_json::to_vec(&upload)?)?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(data_path)?;
+        Ok(upload)
+    }
+
+    /// Reads the upload with the given identifier.
+    ///
+    /// Returns `None` if the upload does not exist or it has timed out.
+    pub fn read(upload_path: &Path, id: &str, timeout: Duration) -> IoResult<Option<Self>> {
+        let Ok((state_path, data_path)) = get_paths(upload_path, id) else {
+            return Ok(None);
+        };
+        if !state_path.is_file() || !data_path.is_file() {
+            return Ok(None);
+        }
+        if is_stale(&data_path, timeout) {
+            delete(upload_path, id)?;
+            return Ok(None);
+        }
+        serde_json::from_slice(&fs::read(state_path)?)
+            .map(Some)
+            .map_err(|e| IoError::new(IoErrorKind::InvalidData, e))
+    }
+
+    /// Returns the number of the received bytes.
+    pub fn get_offset(&self, upload_path: &Path) -> IoResult<u64> {
+        let (_, data_path) = get_paths(upload_path, &self.id)?;
+        Ok(fs::metadata(data_path)?.len())
+    }
+
+    /// Returns the progress of the upload.
+    pub fn get_status(&self, upload_path: &Path) -> IoResult<UploadStatus> {
+        let received_bytes = self.get_offset(upload_path)?;
+        Ok(UploadStatus {
+            received_bytes,
+            total_bytes: self.length,
+            percentage: received_bytes as f64 * 100.0 / self.length.max(1) as f64,
+        })
+    }
+
+    /// Appends the chunk to the received data at the given offset.
+    ///
+    /// The data file is locked while appending, so that the concurrent requests cannot
+    /// write at the same offset.
+    ///
+    /// Returns an error if the chunk exceeds the total size of the upload or the number of the
+    /// received bytes no longer matches the offset.
+    pub fn append(&self, upload_path: &Path, offset: u64, chunk: &[u8]) -> Result<u64, Error> {
+        let new_offset = offset + chunk.len() as u64;
+        if new_offset > self.length {
+            return Err(error::ErrorPayloadTooLarge(
+                "upload exceeds the declared length\n",
+            ));
+        }
+        let (_, data_path) = get_paths(upload_path, &self.id)?;
+        let mut file = OpenOptions::new().append(true).open(data_path)?;
+        file.lock()?;
+        if file.metadata()?.len() != offset {
+            return Err(error::ErrorConflict("upload offset does not match\n"));
+        }
+        file.write_all(chunk)?;
+        Ok(new_offset)
+    }
+
+    /// Returns the received data and removes the upload.
+    pub fn finish(&self, upload_path: &Path) -> IoResult<Vec<u8>> {
+        let (_, data_path) = get_paths(upload_path, &self.id)?;
+        let data = fs::read(data_path)?;
+        delete(upload_path, &self.id)?;
+        Ok(data)
+    }
+}
+
+/// Returns the paths of the state and data files of the upload with the given identifier.
+fn get_paths(upload_path: &Path, id: &str) -> IoResult<(PathBuf, PathBuf)> {
+    if Uuid::try_parse(id).is_err() {
+        return Err(IoError::new(
+            IoErrorKind::InvalidInput,
+            "invalid upload identifier",
+        ));
+    }
+    let dir = upload_path.join(UPLOADS_DIR);
+    Ok((
+        util::safe_path_join(&dir, format!("{id}.json"))?,
+        util::safe_path_join(&dir, id)?,
+    ))
+}
+
+/// Checks if the data file has not been modified within the timeout.
+fn is_stale(data_path: &Path, timeout: Duration) -> bool {
+    fs::metadata(data_path)
+        .and_then(|v| v.modified())
+        .ok()
+        .and_then(|v| SystemTime::now().duration_since(v).ok())
+        .is_some_and(|v| v > timeout)
+}
+
+/// Removes the upload with the given identifier.
+pub fn delete(upload_path: &Path, id: &str) -> IoResult<()> {
+    let (state_path, data_path) = get_paths(upload_path, id)?;
+    for path in [state_path, data_path] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the incomplete uploads that have not received any data within the timeout.
+///
+/// Returns the number of the removed uploads. Fail-safe, omits errors.
+pub fn remove_stale(upload_path: &Path, timeout: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(upload_path.join(UPLOADS_DIR)) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_none() && is_stale(path, timeout))
+        .filter_map(|path| {
+            let id = path.file_name()?.to_str()?;
+            delete(upload_path, id).ok()?;
+            info!("Removed incomplete upload: {}", id);
+            Some(())
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use std::env;
+    use std::thread;
+
+    #[test]
+    fn test_resumable_upload() -> Result<(), Error> {
+        let upload_path = env::current_dir()?.join("test_resumable_upload");
+        let upload = ResumableUpload::create(&upload_path, 6, Some(String::from("file.txt")))?;
+        assert_eq!(
+            Some(&upload),
+            ResumableUpload::read(&upload_path, &upload.id, DEFAULT_TIMEOUT)?.as_ref()
+        );
+        assert_eq!(
+            None,
+            ResumableUpload::read(&upload_path, "../x", DEFAULT_TIMEOUT)?
+        );
+        assert_eq!(0, upload.get_offset(&upload_path)?);
+        assert_eq!(3, upload.append(&upload_path, 0, b"abc")?);
+        assert_eq!(
+            StatusCode::CONFLICT,
+            upload
+                .append(&upload_path, 0, b"abc")
+                .map_err(|e| e.as_response_error().status_code())
+                .err()
+                .unwrap_or(StatusCode::OK)
+        );
+        assert!(upload.append(&upload_path, 3, b"defg").is_err());
+        assert_eq!(6, upload.append(&upload_path, 3, b"def")?);
+        assert_eq!(6, upload.get_offset(&upload_path)?);
+        assert_eq!(
+            UploadStatus {
+                received_bytes: 6,
+                total_bytes: 6,
+                percentage: 100.0,
+            },
+            upload.get_status(&upload_path)?
+        );
+        assert_eq!(b"abcdef".to_vec(), upload.finish(&upload_path)?);
+        assert_eq!(
+            None,
+            ResumableUpload::read(&upload_path, &upload.id, DEFAULT_TIMEOUT)?
+        );
+
+        let upload = ResumableUpload::create(&upload_path, 6, None)?;
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(0, remove_stale(&upload_path, DEFAULT_TIMEOUT));
+        assert_eq!(1, remove_stale(&upload_path, Duration::from_millis(1)));
+        assert_eq!(
+            None,
+            ResumableUpload::read(&upload_path, &upload.id, DEFAULT_TIMEOUT)?
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
@@ -1,46 +1,84 @@
-use crate::auth::{extract_tokens, handle_unauthorized_error, unauthorized_error};
-use crate::config::{Config, LandingPageConfig, TokenType};
+use crate::alias;
+use crate::auth::{
+    extract_tokens, generate_delete_token, get_auth_token, get_token_hash,
+    handle_unauthorized_error, has_configured_token, hash_password, unauthorized_error,
+    verify_delete_token, verify_password, TokenError, UNAUTHORIZED,
+};
+use crate::cleanup::{CleanupReport, DeletionReport};
+use crate::config::{CompressionType, Config, LandingPageConfig, OversizePolicy, TokenType};
 use crate::file::Directory;
 use crate::header::{self, ContentDisposition};
+use crate::highlight;
+use crate::markdown;
+use crate::metadata::Metadata;
+use crate::metrics::METRICS;
+use crate::middleware::get_client_host;
 use crate::mime as mime_util;
 use crate::paste::{Paste, PasteType};
-use crate::util::{self, safe_path_join};
-use actix_files::NamedFile;
+use crate::resumable::{self, ResumableUpload};
+use crate::stats::Stats;
+use crate::storage::{self, StorageBackend};
+use crate::thumbnail;
+use crate::util;
+use crate::webhook::{self, EventType};
+use actix_files::{HttpRange, NamedFile};
 use actix_multipart::Multipart;
+use actix_web::body::SizedStream;
+use actix_web::http::header::{
+    ContentDisposition as ActixContentDisposition, DispositionParam, DispositionType, ETag,
+    EntityTag, HeaderName, HeaderValue, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+    ACCEPT, ACCEPT_ENCODING, ACCEPT_RANGES, ALLOW, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_RANGE,
+    LOCATION, RANGE, RETRY_AFTER, VARY,
+};
 use actix_web::http::StatusCode;
 use actix_web::middleware::ErrorHandlers;
-use actix_web::{delete, error, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Bytes;
+use actix_web::{
+    delete, error, get, head, options, patch, post, put, web, Error, HttpMessage, HttpRequest,
+    HttpResponse, HttpResponseBuilder,
+};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
 use actix_web_grants::GrantsMiddleware;
 use awc::Client;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use byte_unit::{Byte, UnitType};
-use futures_util::stream::StreamExt;
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
+use image::{ImageFormat, Luma};
 use mime::TEXT_PLAIN_UTF_8;
+use qrcode::QrCode;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::RwLock;
-use std::time::Duration;
+use std::io::{Cursor, ErrorKind as IoErrorKind, Read, Result as IoResult};
+use std::path::{Path, PathBuf};
+use std::str;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uts2ts;
 
 /// Shows the landing page.
+///
+/// If there is no landing page, a minimal page is shown.
 #[get("/")]
 #[allow(deprecated)]
-async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+async fn index(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
     let mut config = config
         .read()
         .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
         .clone();
-    let redirect = HttpResponse::Found()
-        .append_header(("Location", env!("CARGO_PKG_HOMEPAGE")))
-        .finish();
+    let minimal_page = HttpResponse::Ok()
+        .content_type(TEXT_PLAIN_UTF_8)
+        .body(concat!(env!("CARGO_PKG_NAME"), "\n"));
     if config.server.landing_page.is_some() {
         if config.landing_page.is_none() {
             config.landing_page = Some(LandingPageConfig::default());
         }
         if let Some(ref mut landing_page) = config.landing_page {
-            landing_page.text = config.server.landing_page;
+            landing_page.text = config.server.landing_page.take();
         }
     }
     if config.server.landing_page_content_type.is_some() {
@@ -48,12 +86,17 @@ async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error>
             config.landing_page = Some(LandingPageConfig::default());
         }
         if let Some(ref mut landing_page) = config.landing_page {
-            landing_page.content_type = config.server.landing_page_content_type;
+            landing_page.content_type = config.server.landing_page_content_type.take();
         }
     }
-    if let Some(mut landing_page) = config.landing_page {
+    if let Some(mut landing_page) = config.landing_page.take() {
         if let Some(file) = landing_page.file {
             landing_page.text = fs::read_to_string(file).ok();
+        } else if landing_page.text.is_none() && landing_page.builtin.unwrap_or(false) {
+            landing_page.text = Some(get_builtin_landing_page(
+                &get_server_url(&request, &config),
+                &config,
+            ));
         }
         match landing_page.text {
             Some(page) => Ok(HttpResponse::Ok()
@@ -63,22 +106,154 @@ async fn index(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error>
                         .unwrap_or(TEXT_PLAIN_UTF_8.to_string()),
                 )
                 .body(page)),
-            None => Ok(redirect),
+            None => Ok(minimal_page),
         }
     } else {
-        Ok(redirect)
+        Ok(minimal_page)
+    }
+}
+
+/// Returns the built-in landing page with the upload instructions and limits.
+fn get_builtin_landing_page(server_url: &str, config: &Config) -> String {
+    let mut page = format!(
+        "{}\n\n\
+        Submit files via HTTP POST here:\n    \
+        curl -F 'file=@example.txt' {server_url}\n\
+        This will return the URL of the uploaded file.\n\n\
+        Maximum file size: {}\n",
+        env!("CARGO_PKG_NAME"),
+        config
+            .server
+            .max_content_length
+            .get_appropriate_unit(UnitType::Decimal),
+    );
+    match config.paste.default_expiry {
+        Some(default_expiry) => page.push_str(&format!(
+            "Files expire after: {}\n",
+            humantime::format_duration(default_expiry)
+        )),
+        None => page.push_str("Files do not expire by default.\n"),
+    }
+    if !config.paste.allowed_extensions.is_empty() {
+        page.push_str(&format!(
+            "Allowed extensions: {}\n",
+            config.paste.allowed_extensions.join(", ")
+        ));
     }
+    page
 }
 
+/// Default value of [`max_highlight_size`](crate::config::PasteConfig::max_highlight_size).
+const DEFAULT_MAX_HIGHLIGHT_SIZE: u64 = 1_000_000;
+
+/// Default value of [`max_render_size`](crate::config::PasteConfig::max_render_size).
+const DEFAULT_MAX_RENDER_SIZE: u64 = 1_000_000;
+
+/// Number of bytes to read for inferring the media type of the files with unknown extensions.
+const MIME_INFER_LENGTH: u64 = 8192;
+
 /// File serving options (i.e. query parameters).
 #[derive(Debug, Deserialize)]
 struct ServeOptions {
-    /// If set to `true`, change the MIME type to `application/octet-stream` and force downloading
-    /// the file.
+    /// If set to `true` (or given without a value), change the MIME type to
+    /// `application/octet-stream` and force downloading the file as an attachment.
+    #[serde(default, deserialize_with = "deserialize_flag")]
     download: bool,
+    /// If set to `true` (or given without a value), render the text file as syntax
+    /// highlighted HTML.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    highlight: bool,
+    /// If set to `true` (or given without a value), render the Markdown file as HTML.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    render: bool,
+    /// Password of the password protected paste.
+    password: Option<String>,
+    /// If set to `true` (or given without a value), return the shortened URL as text
+    /// instead of redirecting to it.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw: bool,
+}
+
+/// Thumbnail options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct ThumbnailOptions {
+    /// Width of the thumbnail in pixels.
+    w: Option<u32>,
+    /// Password of the password protected paste.
+    password: Option<String>,
+}
+
+/// QR code options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct QrCodeOptions {
+    /// Password of the password protected paste.
+    password: Option<String>,
+}
+
+/// Deserializes a query flag that can be given without a value (e.g. `?download`).
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "" | "true" => Ok(true),
+        "false" => Ok(false),
+        v => Err(serde::de::Error::custom(format!("invalid flag: {v}"))),
+    }
+}
+
+/// Reads the beginning of the file for inferring its media type.
+fn read_file_head(storage: &dyn StorageBackend, path: &Path) -> IoResult<Vec<u8>> {
+    if !storage.is_local() || storage.is_encrypted() || storage.is_compressed() {
+        return storage.fetch(path);
+    }
+    let mut data = Vec::new();
+    fs::File::open(path)?
+        .take(MIME_INFER_LENGTH)
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Checks the password of the paste if it is password protected.
+///
+/// The password is taken from the query parameter or the [`PASSWORD`](header::PASSWORD) header.
+fn check_password(
+    request: &HttpRequest,
+    password: Option<&str>,
+    metadata: Option<&Metadata>,
+) -> Result<(), Error> {
+    if let Some(password_hash) = metadata.and_then(|v| v.password_hash.as_ref()) {
+        let password = password.or_else(|| {
+            request
+                .headers()
+                .get(header::PASSWORD)
+                .and_then(|v| v.to_str().ok())
+        });
+        match password {
+            Some(password) if verify_password(password_hash, password) => {}
+            Some(_) => return Err(error::ErrorUnauthorized("invalid password\n")),
+            None => return Err(error::ErrorUnauthorized("password is required\n")),
+        }
+    }
+    Ok(())
+}
+
+/// Checks the auth token of the request if the paste is private.
+fn check_private(
+    request: &HttpRequest,
+    config: &Config,
+    metadata: Option<&Metadata>,
+) -> Result<(), Error> {
+    if metadata.is_some_and(|v| v.private) && !has_configured_token(config, request.headers()) {
+        return Err(error::ErrorUnauthorized(UNAUTHORIZED));
+    }
+    Ok(())
 }
 
 /// Serves a file from the upload directory.
+///
+/// The Markdown files are rendered as HTML with the `render` option or the `.html` suffix
+/// (e.g. `/README.md.html`).
 #[get("/{file}")]
 async fn serve(
     request: HttpRequest,
@@ -88,474 +263,5852 @@ async fn serve(
 ) -> Result<HttpResponse, Error> {
     let config = config
         .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    let mut path = util::glob_match_file(safe_path_join(&config.server.upload_path, &*file)?)?;
-    let mut paste_type = PasteType::File;
-    if !path.exists() || path.is_dir() {
-        for type_ in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
-            let alt_path = safe_path_join(type_.get_path(&config.server.upload_path)?, &*file)?;
-            let alt_path = util::glob_match_file(alt_path)?;
-            if alt_path.exists()
-                || path.file_name().and_then(|v| v.to_str()) == Some(&type_.get_dir())
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    let storage = storage::get_backend(&config)?;
+    let render = options.as_ref().map(|v| v.render).unwrap_or(false);
+    let lookup = util::run_blocking(&config, &storage, move |config, storage| {
+        let file = file.into_inner();
+        let (path, paste_type) = match find_file(config, storage, &file) {
+            Ok(v) => v,
+            Err(e) => match file
+                .strip_suffix(".html")
+                .filter(|v| markdown::is_markdown(v))
+                .and_then(|v| find_file(config, storage, v).ok().map(|paste| (v, paste)))
             {
-                path = alt_path;
-                paste_type = *type_;
-                break;
-            }
-        }
-    }
-    if !path.is_file() || !path.exists() {
-        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
-    }
+                Some((markdown_file, (path, paste_type))) => {
+                    let metadata = read_metadata(config, storage, &path)?;
+                    return Ok((markdown_file.to_string(), true, path, paste_type, metadata));
+                }
+                None => return Err(e),
+            },
+        };
+        let metadata = read_metadata(config, storage, &path)?;
+        Ok((file, render, path, paste_type, metadata))
+    })
+    .await;
+    let (file, render, mut path, paste_type, metadata) = match lookup {
+        Ok(v) => v,
+        Err(e) => return get_not_found_page(&config, e),
+    };
+    check_private(&request, &config, metadata.as_ref())?;
+    check_password(
+        &request,
+        options.as_ref().and_then(|v| v.password.as_deref()),
+        metadata.as_ref(),
+    )?;
+    METRICS.served_files.inc();
     match paste_type {
         PasteType::File | PasteType::RemoteFile | PasteType::Oneshot => {
-            let mime_type = if options.map(|v| v.download).unwrap_or(false) {
+            let highlight = options.as_ref().map(|v| v.highlight).unwrap_or(false);
+            let max_highlight_size = config
+                .paste
+                .max_highlight_size
+                .unwrap_or_else(|| Byte::from_u64(DEFAULT_MAX_HIGHLIGHT_SIZE))
+                .as_u64();
+            if highlight
+                && fs::metadata(&path)
+                    .map(|v| v.len() > max_highlight_size)
+                    .unwrap_or(false)
+            {
+                return Err(error::ErrorPayloadTooLarge(
+                    "file is too large to be highlighted\n",
+                ));
+            }
+            let max_render_size = config
+                .paste
+                .max_render_size
+                .unwrap_or_else(|| Byte::from_u64(DEFAULT_MAX_RENDER_SIZE))
+                .as_u64();
+            if render {
+                if !markdown::is_markdown(&file) {
+                    return Err(error::ErrorBadRequest(
+                        "only Markdown files can be rendered\n",
+                    ));
+                }
+                if fs::metadata(&path)
+                    .map(|v| v.len() > max_render_size)
+                    .unwrap_or(false)
+                {
+                    return Err(error::ErrorPayloadTooLarge(
+                        "file is too large to be rendered\n",
+                    ));
+                }
+            }
+            let download = options.as_ref().map(|v| v.download).unwrap_or(false);
+            let mime_type = if download {
                 mime::APPLICATION_OCTET_STREAM
-            } else {
-                mime_util::get_mime_type(&config.paste.mime_override, file.to_string())
+            } else if let Some(content_type) =
+                metadata.as_ref().and_then(|v| v.content_type.as_ref())
+            {
+                content_type
+                    .parse()
                     .map_err(error::ErrorInternalServerError)?
+            } else {
+                let mime_type =
+                    mime_util::get_mime_type(&config.paste.mime_override, file.to_string())
+                        .map_err(error::ErrorInternalServerError)?;
+                if mime_type == mime::APPLICATION_OCTET_STREAM {
+                    let head_path = path.clone();
+                    let head = util::run_blocking(&config, &storage, move |_, storage| {
+                        Ok(read_file_head(storage, &head_path)?)
+                    })
+                    .await?;
+                    mime_util::infer_mime_type(&head).unwrap_or(mime_type)
+                } else {
+                    mime_type
+                }
+            };
+            let cache_control = config
+                .server
+                .cache_control
+                .as_ref()
+                .and_then(|v| {
+                    v.get(
+                        mime_type.as_ref(),
+                        paste_type.is_oneshot(),
+                        config
+                            .paste
+                            .random_url
+                            .as_ref()
+                            .is_some_and(|v| v.is_content_address(&file)),
+                    )
+                })
+                .and_then(|v| HeaderValue::from_str(v).ok());
+            // Oneshot pastes are consumed, so they cannot be cached.
+            let last_modified = (!paste_type.is_oneshot())
+                .then(|| get_last_modified(&*storage, &path, metadata.as_ref()))
+                .flatten();
+            let original_name = metadata
+                .as_ref()
+                .and_then(|v| v.original_name.clone())
+                .filter(|_| config.paste.preserve_original_name.unwrap_or(false));
+            let original_name_header = original_name
+                .as_ref()
+                .and_then(|v| HeaderValue::from_bytes(v.as_bytes()).ok());
+            let content_disposition =
+                (download || original_name.is_some()).then(|| ActixContentDisposition {
+                    disposition: if download {
+                        DispositionType::Attachment
+                    } else {
+                        DispositionType::Inline
+                    },
+                    parameters: vec![DispositionParam::Filename(
+                        metadata
+                            .and_then(|v| v.original_name)
+                            .unwrap_or_else(|| file.to_string()),
+                    )],
+                });
+            if paste_type.is_oneshot() {
+                path = match consume_oneshot(&path, &file) {
+                    Ok(v) => v,
+                    Err(e) => return get_not_found_page(&config, e),
+                };
+            }
+            if render {
+                let data = fetch(&config, &storage, &path).await?;
+                if u64::try_from(data.len()).unwrap_or(u64::MAX) > max_render_size {
+                    return Err(error::ErrorPayloadTooLarge(
+                        "file is too large to be rendered\n",
+                    ));
+                }
+                let text = str::from_utf8(&data).map_err(|_| {
+                    error::ErrorUnsupportedMediaType("binary files cannot be rendered\n")
+                })?;
+                return Ok(HttpResponse::Ok()
+                    .content_type(mime::TEXT_HTML_UTF_8)
+                    .body(markdown::render(&file, text)));
+            }
+            if highlight {
+                let data = fetch(&config, &storage, &path).await?;
+                if u64::try_from(data.len()).unwrap_or(u64::MAX) > max_highlight_size {
+                    return Err(error::ErrorPayloadTooLarge(
+                        "file is too large to be highlighted\n",
+                    ));
+                }
+                if !highlight::is_text(&data) {
+                    return Err(error::ErrorUnsupportedMediaType(
+                        "binary files cannot be highlighted\n",
+                    ));
+                }
+                let text = str::from_utf8(&data).map_err(error::ErrorInternalServerError)?;
+                return Ok(HttpResponse::Ok().content_type(mime::TEXT_HTML_UTF_8).body(
+                    highlight::highlight(&file, text).map_err(error::ErrorInternalServerError)?,
+                ));
+            }
+            // Oneshot pastes are served in full, regardless of the conditional and range headers.
+            if paste_type.is_oneshot()
+                || storage.is_compressed()
+                || !storage.is_local()
+                || storage.is_encrypted()
+            {
+                let range = request
+                    .headers()
+                    .get(RANGE)
+                    .filter(|_| !paste_type.is_oneshot());
+                let mut response = HttpResponse::Ok();
+                response.content_type(mime_type);
+                if let Some(cache_control) = cache_control {
+                    response.insert_header((CACHE_CONTROL, cache_control));
+                }
+                let data = if storage.is_compressed() {
+                    response.insert_header((VARY, "Accept-Encoding"));
+                    let encoded_path = path.clone();
+                    let (data, compression) =
+                        util::run_blocking(&config, &storage, move |_, storage| {
+                            Ok(storage.fetch_encoded(&encoded_path)?)
+                        })
+                        .await?;
+                    if compression == CompressionType::None {
+                        data
+                    } else if range.is_none() && accepts_encoding(&request, compression.as_str()) {
+                        response.insert_header((CONTENT_ENCODING, compression.as_str()));
+                        data
+                    } else {
+                        storage::decompress(compression, &data)?
+                    }
+                } else {
+                    fetch(&config, &storage, &path).await?
+                };
+                if let Some(content_disposition) = content_disposition {
+                    response.insert_header(content_disposition);
+                }
+                if let Some(original_name) = original_name_header {
+                    response.insert_header((header::ORIGINAL_FILENAME, original_name));
+                }
+                if !paste_type.is_oneshot() {
+                    response.insert_header((ACCEPT_RANGES, "bytes"));
+                    let validators = CacheValidators::new(&data, last_modified)?;
+                    validators.apply(&mut response);
+                    if validators.is_fresh(&request) {
+                        return Ok(response.status(StatusCode::NOT_MODIFIED).finish());
+                    }
+                }
+                if let Some(range) = range {
+                    return Ok(get_range_response(response, range, data));
+                }
+                return Ok(response.body(data));
+            }
+            let file = NamedFile::open(&path)?;
+            let file = match content_disposition {
+                Some(content_disposition) => file.set_content_disposition(content_disposition),
+                None => file.disable_content_disposition(),
             };
-            let response = NamedFile::open(&path)?
-                .disable_content_disposition()
+            let mut response = file
                 .set_content_type(mime_type)
                 .prefer_utf8(true)
                 .into_response(&request);
-            if paste_type.is_oneshot() {
-                fs::rename(
-                    &path,
-                    path.with_file_name(format!(
-                        "{}.{}",
-                        file,
-                        util::get_system_time()?.as_millis()
-                    )),
-                )?;
+            if let Some(cache_control) = cache_control {
+                response.headers_mut().insert(CACHE_CONTROL, cache_control);
+            }
+            if let Some(original_name) = original_name_header {
+                response.headers_mut().insert(
+                    HeaderName::from_static(header::ORIGINAL_FILENAME),
+                    original_name,
+                );
             }
             Ok(response)
         }
-        PasteType::Url => Ok(HttpResponse::Found()
-            .append_header(("Location", fs::read_to_string(&path)?))
-            .finish()),
-        PasteType::OneshotUrl => {
-            let resp = HttpResponse::Found()
-                .append_header(("Location", fs::read_to_string(&path)?))
-                .finish();
-            fs::rename(
-                &path,
-                path.with_file_name(format!("{}.{}", file, util::get_system_time()?.as_millis())),
-            )?;
-            Ok(resp)
+        PasteType::Url | PasteType::OneshotUrl => {
+            if paste_type == PasteType::OneshotUrl {
+                path = match consume_oneshot(&path, &file) {
+                    Ok(v) => v,
+                    Err(e) => return get_not_found_page(&config, e),
+                };
+            }
+            let url = String::from_utf8(fetch(&config, &storage, &path).await?)
+                .map_err(error::ErrorInternalServerError)?;
+            if options.map(|v| v.raw).unwrap_or(false) {
+                return Ok(HttpResponse::Ok()
+                    .content_type(TEXT_PLAIN_UTF_8)
+                    .body(url + "\n"));
+            }
+            Ok(HttpResponse::Found()
+                .append_header(("Location", url))
+                .finish())
         }
     }
 }
 
-/// Remove a file from the upload directory.
-#[delete("/{file}")]
-#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
-async fn delete(
+/// Checks if a file exists without serving it.
+///
+/// Oneshot pastes are not consumed.
+#[head("/{file}")]
+async fn exists(
+    request: HttpRequest,
     file: web::Path<String>,
+    options: Option<web::Query<ServeOptions>>,
     config: web::Data<RwLock<Config>>,
 ) -> Result<HttpResponse, Error> {
     let config = config
         .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    let path = util::glob_match_file(safe_path_join(&config.server.upload_path, &*file)?)?;
-    if !path.is_file() || !path.exists() {
-        return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    let storage = storage::get_backend(&config)?;
+    let (path, paste_type, metadata) =
+        util::run_blocking(&config, &storage, move |config, storage| {
+            find_file_with_metadata(config, storage, &file)
+        })
+        .await?;
+    check_private(&request, &config, metadata.as_ref())?;
+    check_password(
+        &request,
+        options.as_ref().and_then(|v| v.password.as_deref()),
+        metadata.as_ref(),
+    )?;
+    let file_metadata = storage
+        .is_local()
+        .then(|| fs::metadata(&path))
+        .transpose()?;
+    let size = match (&metadata, &file_metadata) {
+        (Some(metadata), _) => metadata.size as u64,
+        (None, Some(file_metadata)) if !storage.is_encrypted() && !storage.is_compressed() => {
+            file_metadata.len()
+        }
+        _ => fetch(&config, &storage, &path).await?.len() as u64,
+    };
+    let mut response = HttpResponse::Ok();
+    if let Some(modified) = get_last_modified(&*storage, &path, metadata.as_ref()) {
+        response.insert_header(LastModified(modified.into()));
+    }
+    if matches!(paste_type, PasteType::File | PasteType::RemoteFile) {
+        response.insert_header((ACCEPT_RANGES, "bytes"));
+    }
+    // The body is not sent for HEAD requests but its size is used as the content length.
+    Ok(response.body(SizedStream::new(
+        size,
+        stream::empty::<Result<Bytes, Error>>(),
+    )))
+}
+
+/// Builds the `206 Partial Content` response for the requested byte range of the data.
+///
+/// Like [`NamedFile`], only the first range is served and the invalid ranges are
+/// rejected with `416 Range Not Satisfiable`.
+fn get_range_response(
+    mut response: HttpResponseBuilder,
+    range: &HeaderValue,
+    data: Vec<u8>,
+) -> HttpResponse {
+    let size = data.len() as u64;
+    let range = range
+        .to_str()
+        .ok()
+        .and_then(|v| HttpRange::parse(v, size).ok())
+        .and_then(|v| v.first().copied())
+        .filter(|v| v.length > 0);
+    let body = range.and_then(|range| {
+        let start = usize::try_from(range.start).ok()?;
+        let end = start.checked_add(usize::try_from(range.length).ok()?)?;
+        data.get(start..end).map(|v| (range, v.to_vec()))
+    });
+    match body {
+        Some((range, body)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .insert_header((
+                CONTENT_RANGE,
+                format!(
+                    "bytes {}-{}/{}",
+                    range.start,
+                    range.start + range.length - 1,
+                    size
+                ),
+            ))
+            .body(body),
+        None => response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .insert_header((CONTENT_RANGE, format!("bytes */{size}")))
+            .finish(),
+    }
+}
+
+/// Returns the last modification time of the stored file.
+///
+/// Falls back to the creation time in the metadata if the file is not stored locally.
+fn get_last_modified(
+    storage: &dyn StorageBackend,
+    path: &Path,
+    metadata: Option<&Metadata>,
+) -> Option<SystemTime> {
+    storage
+        .is_local()
+        .then(|| fs::metadata(path).and_then(|v| v.modified()).ok())
+        .flatten()
+        .or_else(|| {
+            metadata
+                .and_then(|v| u64::try_from(v.created_at).ok())
+                .map(|v| UNIX_EPOCH + Duration::from_millis(v))
+        })
+}
+
+/// Validators for the conditional requests of a served file.
+///
+/// Used for the files that are not served via [`NamedFile`], which has its own validators.
+struct CacheValidators {
+    /// Tag derived from the hash of the response body.
+    etag: EntityTag,
+    /// Last modification time of the file.
+    last_modified: Option<HttpDate>,
+}
+
+impl CacheValidators {
+    /// Creates the validators for the given response body.
+    fn new(data: &[u8], last_modified: Option<SystemTime>) -> Result<Self, Error> {
+        let mut digest = util::sha256_digest(data)?;
+        digest.truncate(ETAG_LENGTH);
+        Ok(Self {
+            etag: EntityTag::new_strong(digest),
+            last_modified: last_modified.map(HttpDate::from),
+        })
+    }
+
+    /// Inserts the `ETag` and `Last-Modified` headers to the response.
+    fn apply(&self, response: &mut HttpResponseBuilder) {
+        response.insert_header(ETag(self.etag.clone()));
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header(LastModified(last_modified));
+        }
+    }
+
+    /// Checks if the client already has the same version of the file.
+    ///
+    /// `If-Modified-Since` is only checked if there is no `If-None-Match` header.
+    fn is_fresh(&self, request: &HttpRequest) -> bool {
+        match request.get_header::<IfNoneMatch>() {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&self.etag)),
+            None => match (request.get_header::<IfModifiedSince>(), self.last_modified) {
+                (Some(IfModifiedSince(since)), Some(last_modified)) => {
+                    // HTTP dates have a precision of seconds.
+                    let seconds = |date: HttpDate| {
+                        SystemTime::from(date)
+                            .duration_since(UNIX_EPOCH)
+                            .map(|v| v.as_secs())
+                            .ok()
+                    };
+                    seconds(last_modified) <= seconds(since)
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Reads the data at the given path (see [`util::run_blocking`]).
+async fn fetch(
+    config: &Config,
+    storage: &Arc<dyn StorageBackend>,
+    path: &Path,
+) -> Result<Vec<u8>, Error> {
+    let path = path.to_path_buf();
+    util::run_blocking(config, storage, move |_, storage| Ok(storage.fetch(&path)?)).await
+}
+
+/// Returns the path, the type and the metadata of the paste with the given file name.
+///
+/// See [`find_file`].
+fn find_file_with_metadata(
+    config: &Config,
+    storage: &dyn StorageBackend,
+    file: &str,
+) -> Result<(PathBuf, PasteType, Option<Metadata>), Error> {
+    let (path, paste_type) = find_file(config, storage, file)?;
+    let metadata = read_metadata(config, storage, &path)?;
+    Ok((path, paste_type, metadata))
+}
+
+/// Reads the metadata of the file at the given path.
+fn read_metadata(
+    config: &Config,
+    storage: &dyn StorageBackend,
+    path: &Path,
+) -> Result<Option<Metadata>, Error> {
+    Metadata::read(storage, get_upload_root(config, path), path).map_err(|e| {
+        error!("cannot read metadata: {}", e);
+        error::ErrorInternalServerError("cannot read metadata")
+    })
+}
+
+/// Returns the path and the type of the paste with the given file name.
+///
+/// The [aliases](crate::alias) are resolved if there is no such file.
+/// Returns an error if the file is not found or expired.
+fn find_file(
+    config: &Config,
+    storage: &dyn StorageBackend,
+    file: &str,
+) -> Result<(PathBuf, PasteType), Error> {
+    if let Some(paste) = find_paste(config, storage, file)? {
+        return Ok(paste);
+    }
+    if let Ok(Some(file_name)) = alias::read(storage, &config.server.upload_path, file) {
+        if let Some(paste) = find_paste(config, storage, &file_name)? {
+            return Ok(paste);
+        }
+    }
+    Err(error::ErrorNotFound("file is not found or expired :(\n"))
+}
+
+/// Returns the path and the type of the paste with the given file name (if it exists).
+fn find_paste(
+    config: &Config,
+    storage: &dyn StorageBackend,
+    file: &str,
+) -> Result<Option<(PathBuf, PasteType)>, Error> {
+    let shard_length = config.paste.shard_length;
+    for upload_path in config.server.get_upload_paths() {
+        let mut path = util::find_file_path(storage, upload_path, file, shard_length)?;
+        let mut paste_type = PasteType::File;
+        if !storage.exists(&path)? {
+            for type_ in &[PasteType::Url, PasteType::Oneshot, PasteType::OneshotUrl] {
+                let alt_path = util::find_file_path(
+                    storage,
+                    &type_.get_path(upload_path)?,
+                    file,
+                    shard_length,
+                )?;
+                if storage.exists(&alt_path)?
+                    || path.file_name().and_then(|v| v.to_str()) == Some(&type_.get_dir())
+                {
+                    path = alt_path;
+                    paste_type = *type_;
+                    break;
+                }
+            }
+        }
+        if storage.exists(&path)? {
+            return Ok(Some((path, paste_type)));
+        }
     }
-    match fs::remove_file(path) {
-        Ok(_) => info!("deleted file: {:?}", file.to_string()),
+    Ok(None)
+}
+
+/// Returns the upload path (or the [overflow path]) that contains the file at the given path.
+///
+/// [overflow path]: crate::config::ServerConfig::overflow_paths
+fn get_upload_root<'a>(config: &'a Config, path: &Path) -> &'a Path {
+    config
+        .server
+        .get_upload_paths()
+        .find(|v| path.starts_with(v))
+        .unwrap_or(&config.server.upload_path)
+}
+
+/// Returns the [`not_found_page`] for the files that are not found or expired.
+///
+/// Other errors (or the errors while reading the page) are returned as is.
+///
+/// [`not_found_page`]: crate::config::ServerConfig::not_found_page
+fn get_not_found_page(config: &Config, error: Error) -> Result<HttpResponse, Error> {
+    let Some(page) = config
+        .server
+        .not_found_page
+        .as_ref()
+        .filter(|_| error.as_response_error().status_code() == StatusCode::NOT_FOUND)
+    else {
+        return Err(error);
+    };
+    match fs::read(page) {
+        Ok(body) => Ok(HttpResponse::NotFound()
+            .content_type(
+                mime_util::get_mime_type(&[], page.to_string_lossy().to_string())
+                    .map_err(error::ErrorInternalServerError)?,
+            )
+            .body(body)),
         Err(e) => {
-            error!("cannot delete file: {}", e);
-            return Err(error::ErrorInternalServerError("cannot delete file"));
+            error!("cannot read the not found page: {}", e);
+            Err(error)
         }
     }
-    Ok(HttpResponse::Ok().body(String::from("file deleted\n")))
 }
 
-/// Expose version endpoint
-#[get("/version")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn version(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+/// Returns the URL that can be used to access the server externally.
+///
+/// Falls back to the scheme and the host of the request if [`url`] is not set.
+///
+/// [`url`]: crate::config::ServerConfig::url
+fn get_server_url(request: &HttpRequest, config: &Config) -> String {
+    match &config.server.url {
+        Some(v) => v.trim_end_matches('/').to_string(),
+        None => {
+            let connection = request.connection_info();
+            format!("{}://{}", connection.scheme(), connection.host())
+        }
+    }
+}
+
+/// Returns a QR code (PNG) of the URL of the file.
+///
+/// The private and password protected pastes are checked like they are served.
+#[get("/{file}/qr")]
+async fn qr_code(
+    request: HttpRequest,
+    file: web::Path<String>,
+    options: Option<web::Query<QrCodeOptions>>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
     let config = config
         .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-    if !config.server.expose_version.unwrap_or(false) {
-        warn!("server is not configured to expose version endpoint");
-        Err(error::ErrorNotFound(""))?;
-    }
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    let storage = storage::get_backend(&config)?;
+    let file = file.into_inner();
+    let lookup_file = file.clone();
+    let (_, _, metadata) = util::run_blocking(&config, &storage, move |config, storage| {
+        find_file_with_metadata(config, storage, &lookup_file)
+    })
+    .await?;
+    check_private(&request, &config, metadata.as_ref())?;
+    check_password(
+        &request,
+        options.as_ref().and_then(|v| v.password.as_deref()),
+        metadata.as_ref(),
+    )?;
+    let url = format!("{}/{}", get_server_url(&request, &config), file);
+    let image = QrCode::new(url.as_bytes())
+        .map_err(error::ErrorInternalServerError)?
+        .render::<Luma<u8>>()
+        .build();
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().content_type(mime::IMAGE_PNG).body(bytes))
+}
 
-    let version = env!("CARGO_PKG_VERSION");
-    Ok(HttpResponse::Ok().body(version.to_owned() + "\n"))
+/// Marks a oneshot paste as consumed by renaming it to an expired path.
+///
+/// Since renaming is atomic, only the first request can claim the paste.
+/// Returns the new path of the file to read the contents from.
+fn consume_oneshot(path: &Path, file: &str) -> Result<PathBuf, Error> {
+    let consumed_path =
+        path.with_file_name(format!("{}.{}", file, util::get_system_time()?.as_millis()));
+    match fs::rename(path, &consumed_path) {
+        Ok(()) => Ok(consumed_path),
+        Err(e) if e.kind() == IoErrorKind::NotFound => {
+            Err(error::ErrorNotFound("file is not found or expired :(\n"))
+        }
+        Err(e) => Err(e.into()),
+    }
 }
 
-/// Handles file upload by processing `multipart/form-data`.
-#[post("/")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn upload(
+/// Remove a file from the upload directory.
+///
+/// Requires either one of the delete tokens or the delete token of the file.
+#[delete("/{file}")]
+async fn delete(
     request: HttpRequest,
-    mut payload: Multipart,
+    file: web::Path<String>,
+    auth_details: AuthDetails<TokenType>,
     client: web::Data<Client>,
     config: web::Data<RwLock<Config>>,
 ) -> Result<HttpResponse, Error> {
-    let connection = request.connection_info().clone();
-    let host = connection.realip_remote_addr().unwrap_or("unknown host");
-    let server_url = match config
+    let config = config
         .read()
         .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-        .server
-        .url
-        .clone()
+        .clone();
+    if !auth_details.has_authority(&TokenType::Delete)
+        && !verify_delete_token(&config, request.headers(), &file)
     {
-        Some(v) => v,
-        None => {
-            format!("{}://{}", connection.scheme(), connection.host(),)
-        }
-    };
-    let time = util::get_system_time()?;
-    let mut expiry_date = header::parse_expiry_date(request.headers(), time)?;
-    if expiry_date.is_none() {
-        expiry_date = config
-            .read()
-            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-            .paste
-            .default_expiry
-            .and_then(|v| time.checked_add(v).map(|t| t.as_millis()));
+        return Ok(unauthorized_error());
     }
-    let mut urls: Vec<String> = Vec::new();
-    while let Some(item) = payload.next().await {
-        let header_filename = header::parse_header_filename(request.headers())?;
-        let mut field = item?;
-        let content = ContentDisposition::from(field.content_disposition().clone());
-        if let Ok(paste_type) = PasteType::try_from(&content) {
-            let mut bytes = Vec::<u8>::new();
-            while let Some(chunk) = field.next().await {
-                bytes.append(&mut chunk?.to_vec());
-            }
-            if bytes.is_empty() {
-                warn!("{} sent zero bytes", host);
-                return Err(error::ErrorBadRequest("invalid file size"));
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    let storage = storage::get_backend(&config)?;
+    let file = file.into_inner();
+    let delete_file = file.clone();
+    let file_size = util::run_blocking(&config, &storage, move |config, storage| {
+        let mut found_path = None;
+        for upload_path in config.server.get_upload_paths() {
+            let path = util::find_file_path(
+                storage,
+                upload_path,
+                &delete_file,
+                config.paste.shard_length,
+            )?;
+            if storage.exists(&path)? {
+                found_path = Some(path);
+                break;
             }
-            if paste_type != PasteType::Oneshot
-                && paste_type != PasteType::RemoteFile
-                && paste_type != PasteType::OneshotUrl
-                && expiry_date.is_none()
-                && !config
-                    .read()
-                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-                    .paste
-                    .duplicate_files
-                    .unwrap_or(true)
-            {
-                let bytes_checksum = util::sha256_digest(&*bytes)?;
-                let config = config
-                    .read()
-                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                if let Some(file) = Directory::try_from(config.server.upload_path.as_path())?
-                    .get_file(bytes_checksum)
-                {
-                    urls.push(format!(
-                        "{}/{}\n",
-                        server_url,
-                        file.path
-                            .file_name()
-                            .map(|v| v.to_string_lossy())
-                            .unwrap_or_default()
-                    ));
-                    continue;
-                }
+        }
+        let Some(path) = found_path else {
+            return Err(error::ErrorNotFound("file is not found or expired :(\n"));
+        };
+        let upload_path = get_upload_root(config, &path);
+        let file_size = match Metadata::read(storage, upload_path, &path) {
+            Ok(Some(metadata)) => Some(metadata.size as u64),
+            _ if storage.is_local() && !storage.is_encrypted() && !storage.is_compressed() => {
+                fs::metadata(&path).ok().map(|v| v.len())
             }
-            let mut paste = Paste {
-                data: bytes.to_vec(),
-                type_: paste_type,
-            };
-            let mut file_name = match paste.type_ {
-                PasteType::File | PasteType::Oneshot => {
-                    let config = config
-                        .read()
-                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                    paste.store_file(
-                        content.get_file_name()?,
-                        expiry_date,
-                        header_filename,
-                        &config,
-                    )?
-                }
-                PasteType::RemoteFile => {
-                    paste
-                        .store_remote_file(expiry_date, &client, &config)
-                        .await?
-                }
-                PasteType::Url | PasteType::OneshotUrl => {
-                    let config = config
-                        .read()
-                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-                    paste.store_url(expiry_date, &config)?
-                }
-            };
-            info!(
-                "{} ({}) is uploaded from {}",
-                file_name,
-                Byte::from_u128(paste.data.len() as u128)
-                    .unwrap_or_default()
-                    .get_appropriate_unit(UnitType::Decimal),
-                host
-            );
-            let config = config
-                .read()
-                .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
-            if let Some(handle_spaces_config) = config.server.handle_spaces {
-                file_name = handle_spaces_config.process_filename(&file_name);
+            _ => None,
+        };
+        if let Err(e) = storage.delete(&path) {
+            error!("cannot delete file: {}", e);
+            return Err(error::ErrorInternalServerError("cannot delete file"));
+        }
+        if let Err(e) = Metadata::delete(storage, upload_path, &path) {
+            error!("cannot delete metadata: {}", e);
+        }
+        if storage.is_local() {
+            if let Err(e) = thumbnail::delete(upload_path, &path) {
+                error!("cannot delete thumbnails: {}", e);
             }
-            urls.push(format!("{}/{}\n", server_url, file_name));
-        } else {
-            warn!("{} sent an invalid form field", host);
-            return Err(error::ErrorBadRequest("invalid form field"));
         }
+        Ok(file_size)
+    })
+    .await?;
+    info!("deleted file: {:?}", file);
+    if let Some(webhook_config) = &config.webhooks {
+        webhook::send(
+            &client,
+            webhook_config,
+            &webhook::Event::new(EventType::Delete, &file, file_size, PasteType::File)?,
+        )?;
     }
-    Ok(HttpResponse::Ok().body(urls.join("")))
-}
-
-/// File entry item for list endpoint.
-#[derive(Serialize, Deserialize)]
-pub struct ListItem {
-    /// Uploaded file name.
-    pub file_name: PathBuf,
-    /// Size of the file in bytes.
-    pub file_size: u64,
-    /// ISO8601 formatted date-time string of the expiration timestamp if one exists for this file.
-    pub expires_at_utc: Option<String>,
+    Ok(HttpResponse::Ok().body(String::from("file deleted\n")))
 }
 
-/// Returns the list of files.
-#[get("/list")]
-#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
-async fn list(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+/// Removes the expired and orphaned files and returns the counts of the removed files.
+#[post("/admin/cleanup")]
+#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
+async fn cleanup(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
     let config = config
         .read()
-        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
-        .clone();
-    if !config.server.expose_list.unwrap_or(false) {
-        warn!("server is not configured to expose list endpoint");
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    if !storage::get_backend(&config)?.is_local() {
+        return Err(error::ErrorBadRequest(
+            "cleanup is not supported by the storage\n",
+        ));
+    }
+    let mut report = CleanupReport::default();
+    for upload_path in config.server.get_upload_paths() {
+        report += crate::cleanup::run(upload_path);
+    }
+    info!(
+        "cleanup removed {} expired and {} orphaned files ({} bytes)",
+        report.expired_files, report.orphaned_files, report.freed_bytes
+    );
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Removes the pastes that are uploaded with the auth token of the given SHA256 digest.
+///
+/// The token is only known for the pastes with [metadata](crate::metadata).
+#[delete("/admin/token/{token_hash}")]
+#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
+async fn delete_token_files(
+    token_hash: web::Path<String>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    if !storage::get_backend(&config)?.is_local() {
+        return Err(error::ErrorBadRequest(
+            "deleting the files of a token is not supported by the storage\n",
+        ));
+    }
+    let mut report = DeletionReport::default();
+    for upload_path in config.server.get_upload_paths() {
+        report += crate::cleanup::remove_token_files(upload_path, &token_hash.to_lowercase());
+    }
+    info!(
+        "deleted {} files of the token {} ({} bytes)",
+        report.deleted_files, token_hash, report.freed_bytes
+    );
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Returns the loaded configuration with the sensitive values redacted.
+#[get("/admin/config")]
+#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
+async fn admin_config(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .redacted();
+    Ok(HttpResponse::Ok().json(config))
+}
+
+/// Returns the statistics of the stored files.
+///
+/// The statistics are cached for a short time (see [`CACHE_DURATION`](crate::stats::CACHE_DURATION)).
+#[get("/admin/stats")]
+#[actix_web_grants::protect("TokenType::Delete", ty = TokenType, error = unauthorized_error)]
+async fn admin_stats(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if !storage::get_backend(&config)?.is_local() {
+        return Err(error::ErrorBadRequest(
+            "statistics are not supported by the storage\n",
+        ));
+    }
+    Ok(HttpResponse::Ok().json(Stats::get_all(config.server.get_upload_paths())?))
+}
+
+/// Creates an alias for an existing file.
+///
+/// The name of the alias is read from the request body and sanitized like the file names.
+#[post("/{file}/alias")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn create_alias(
+    request: HttpRequest,
+    file: web::Path<String>,
+    body: Bytes,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    let storage = storage::get_backend(&config)?;
+    let alias = util::sanitize_filename(
+        str::from_utf8(&body)
+            .map_err(error::ErrorBadRequest)?
+            .trim(),
+    )?;
+    if alias.is_empty() || alias.starts_with('.') {
+        return Err(error::ErrorBadRequest("invalid alias\n"));
+    }
+    if config.paste.is_reserved(&alias) {
+        return Err(error::ErrorConflict("file name is reserved\n"));
+    }
+    let alias_name = alias.clone();
+    let file_name = util::run_blocking(&config, &storage, move |config, storage| {
+        // Point to the original file instead of creating a chain of aliases.
+        let file_name = match alias::read(storage, &config.server.upload_path, &file)
+            .ok()
+            .flatten()
+        {
+            Some(file_name) if find_paste(config, storage, &file)?.is_none() => file_name,
+            _ => file.to_string(),
+        };
+        let (_, paste_type) = find_paste(config, storage, &file_name)?
+            .ok_or_else(|| error::ErrorNotFound("file is not found or expired :(\n"))?;
+        if matches!(paste_type, PasteType::Oneshot | PasteType::OneshotUrl) {
+            return Err(error::ErrorBadRequest(
+                "oneshot files cannot have an alias\n",
+            ));
+        }
+        if find_paste(config, storage, &alias_name)?.is_some() {
+            return Err(error::ErrorConflict("file already exists\n"));
+        }
+        match alias::write(storage, &config.server.upload_path, &alias_name, &file_name) {
+            Ok(()) => Ok(file_name),
+            Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+                Err(error::ErrorConflict("alias already exists\n"))
+            }
+            Err(e) => Err(e.into()),
+        }
+    })
+    .await?;
+    info!("created alias {} for {}", alias, file_name);
+    Ok(HttpResponse::Ok().body(format!("{}/{}\n", get_server_url(&request, &config), alias)))
+}
+
+/// Serves a thumbnail of an image file.
+///
+/// The thumbnails of the [`cached widths`](thumbnail::CACHED_WIDTHS) are cached in the
+/// [`thumbnail directory`](thumbnail::THUMBNAIL_DIR) if the files are stored locally without
+/// encryption.
+#[get("/{file}/thumbnail")]
+async fn serve_thumbnail(
+    request: HttpRequest,
+    file: web::Path<String>,
+    options: Option<web::Query<ThumbnailOptions>>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    let storage = storage::get_backend(&config)?;
+    let (path, paste_type, metadata) =
+        util::run_blocking(&config, &storage, move |config, storage| {
+            find_file_with_metadata(config, storage, &file)
+        })
+        .await?;
+    check_private(&request, &config, metadata.as_ref())?;
+    check_password(
+        &request,
+        options.as_ref().and_then(|v| v.password.as_deref()),
+        metadata.as_ref(),
+    )?;
+    match paste_type {
+        PasteType::File | PasteType::RemoteFile => {}
+        PasteType::Oneshot => {
+            return Err(error::ErrorBadRequest(
+                "thumbnails are not supported for oneshot files\n",
+            ));
+        }
+        PasteType::Url | PasteType::OneshotUrl => {
+            return Err(error::ErrorUnsupportedMediaType(
+                "thumbnails are only supported for images\n",
+            ));
+        }
+    }
+    let width = options
+        .as_ref()
+        .and_then(|v| v.w)
+        .unwrap_or(thumbnail::DEFAULT_WIDTH);
+    if width == 0 || width > thumbnail::MAX_WIDTH {
+        return Err(error::ErrorBadRequest(format!(
+            "thumbnail width must be between 1 and {}\n",
+            thumbnail::MAX_WIDTH
+        )));
+    }
+    let thumbnail_path = (storage.is_local()
+        && !storage.is_encrypted()
+        && thumbnail::CACHED_WIDTHS.contains(&width))
+    .then(|| thumbnail::get_path(get_upload_root(&config, &path), &path, width))
+    .transpose()?;
+    if let Some(data) = thumbnail_path
+        .as_ref()
+        .and_then(|v| thumbnail::read_cached(v, &path))
+    {
+        return Ok(HttpResponse::Ok().content_type(mime::IMAGE_PNG).body(data));
+    }
+    let data = fetch(&config, &storage, &path).await?;
+    let data = util::block(move || thumbnail::generate(&data, width)).await?;
+    if let Some(thumbnail_path) = thumbnail_path {
+        if let Err(e) = thumbnail::write_cached(&thumbnail_path, &data) {
+            warn!("cannot cache the thumbnail: {}", e);
+        }
+    }
+    Ok(HttpResponse::Ok().content_type(mime::IMAGE_PNG).body(data))
+}
+
+/// Expose version endpoint
+#[get("/version")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn version(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if !config.server.expose_version.unwrap_or(false) {
+        warn!("server is not configured to expose version endpoint");
         Err(error::ErrorNotFound(""))?;
     }
-    let entries: Vec<ListItem> = fs::read_dir(config.server.upload_path)?
-        .filter_map(|entry| {
-            entry.ok().and_then(|e| {
-                let metadata = match e.metadata() {
-                    Ok(metadata) => {
-                        if metadata.is_dir() {
-                            return None;
-                        }
-                        metadata
-                    }
-                    Err(e) => {
-                        error!("failed to read metadata: {e}");
-                        return None;
-                    }
-                };
-                let mut file_name = PathBuf::from(e.file_name());
-                let expires_at_utc = if let Some(expiration) = file_name
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .and_then(|v| v.parse::<i64>().ok())
-                {
-                    file_name.set_extension("");
-                    if util::get_system_time().ok()?
-                        > Duration::from_millis(expiration.try_into().ok()?)
-                    {
-                        return None;
-                    }
-                    Some(uts2ts::uts2ts(expiration / 1000).as_string())
-                } else {
-                    None
-                };
-                Some(ListItem {
-                    file_name,
-                    file_size: metadata.len(),
-                    expires_at_utc,
+
+    let version = env!("CARGO_PKG_VERSION");
+    Ok(HttpResponse::Ok().body(version.to_owned() + "\n"))
+}
+
+/// Health status for the health check endpoint.
+#[derive(Serialize, Deserialize)]
+pub struct HealthStatus {
+    /// Status of the service.
+    pub status: String,
+    /// Whether if the upload directory is writable.
+    pub writable: bool,
+}
+
+/// Health check endpoint.
+///
+/// Checks if the upload directory is writable by creating and removing a temporary file.
+#[get("/health")]
+async fn health(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let upload_path = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .server
+        .upload_path
+        .clone();
+    let writable = util::check_writable(&upload_path);
+    if let Err(e) = &writable {
+        error!("upload directory is not writable: {}", e);
+    }
+    Ok(HttpResponse::Ok().json(HealthStatus {
+        status: String::from("ok"),
+        writable: writable.is_ok(),
+    }))
+}
+
+/// Expose Prometheus metrics endpoint
+#[get("/metrics")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn metrics(config: web::Data<RwLock<Config>>) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if !config.server.expose_metrics.unwrap_or(false) {
+        warn!("server is not configured to expose metrics endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(METRICS.render(&config.server.upload_path)?))
+}
+
+/// Handles file upload by processing `multipart/form-data`.
+///
+/// A raw `text/plain` body is stored as a single file.
+#[post("/")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn upload(
+    request: HttpRequest,
+    payload: web::Payload,
+    auth_details: AuthDetails<TokenType>,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    if config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .server
+        .read_only
+        .unwrap_or(false)
+    {
+        return Ok(read_only_error());
+    }
+    let parts = if is_raw_body(&request) {
+        stream::once(async { Ok(get_raw_part("-", payload)) }).boxed_local()
+    } else if is_json_body(&request) {
+        let max_content_length = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .max_content_length;
+        stream::iter(
+            get_json_parts(payload, max_content_length)
+                .await?
+                .into_iter()
+                .map(Ok),
+        )
+        .boxed_local()
+    } else {
+        Multipart::new(request.headers(), payload)
+            .map(|item| {
+                item.map(|field| UploadPart {
+                    content: ContentDisposition::from(field.content_disposition().clone()),
+                    data: field.map(|v| v.map_err(Error::from)).boxed_local(),
                 })
+                .map_err(Error::from)
             })
+            .boxed_local()
+    };
+    let response = process_upload(request, parts, auth_details, client, config).await;
+    if response.is_err() {
+        METRICS.upload_failures.inc();
+    }
+    response
+}
+
+/// Handles file upload with the raw body as the content and the path as the file name.
+///
+/// The file name is used as if it is given via the [`filename`](header::FILENAME) header.
+#[put("/{file}")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn put_upload(
+    request: HttpRequest,
+    file: web::Path<String>,
+    payload: web::Payload,
+    auth_details: AuthDetails<TokenType>,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    if config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .server
+        .read_only
+        .unwrap_or(false)
+    {
+        return Ok(read_only_error());
+    }
+    let file_name = file.into_inner();
+    let filename_part = UploadPart {
+        content: ContentDisposition::from(ActixContentDisposition {
+            disposition: DispositionType::FormData,
+            parameters: vec![DispositionParam::Name(String::from(header::FILENAME))],
+        }),
+        data: stream::iter([Ok(Bytes::from(file_name.clone()))]).boxed_local(),
+    };
+    let parts =
+        stream::iter([Ok(filename_part), Ok(get_raw_part(&file_name, payload))]).boxed_local();
+    let response = process_upload(request, parts, auth_details, client, config).await;
+    if response.is_err() {
+        METRICS.upload_failures.inc();
+    }
+    response
+}
+
+/// Default value of [`max_batch_size`](crate::config::PasteConfig::max_batch_size).
+const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Result of a URL in the batch URL shortening request.
+#[derive(Serialize, Deserialize)]
+pub struct ShortenedUrl {
+    /// The URL to shorten.
+    pub url: String,
+    /// Shortened URL if the URL is stored.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub short: Option<String>,
+    /// Reason of the failure if the URL is not stored.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+}
+
+/// Shortens the URLs that are given as a JSON array.
+///
+/// Each URL is stored with [`Paste::store_url`] and the failures are reported per URL.
+#[post("/urls")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn shorten_urls(
+    request: HttpRequest,
+    payload: web::Payload,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let mut config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    let token_policy = config
+        .get_token_policy(get_auth_token(request.headers()).unwrap_or_default())
+        .cloned();
+    if let Some(token_policy) = &token_policy {
+        token_policy.apply(&mut config);
+    }
+    let host = &get_client_host(&request, &config.server.trusted_proxies);
+    let time = util::get_system_time()?;
+    let expiry_date = match header::parse_expiry_date(request.headers(), time)? {
+        Some(_)
+            if !token_policy
+                .as_ref()
+                .and_then(|v| v.allow_expiry)
+                .unwrap_or(true) =>
+        {
+            warn!(
+                "{} sent an expiring paste that is not allowed for the token",
+                host
+            );
+            return Err(error::ErrorForbidden("expiring files are not allowed\n"));
+        }
+        Some(expiry_date) => Some(expiry_date),
+        None => config
+            .paste
+            .get_default_expiry(PasteType::Url)
+            .and_then(|v| time.checked_add(v).map(|t| t.as_millis())),
+    };
+    if expiry_date.is_some() && !storage::get_backend(&config)?.is_local() {
+        return Err(error::ErrorBadRequest(
+            "expiring and oneshot files are not supported by the storage\n",
+        ));
+    }
+    let limit = usize::try_from(config.server.max_content_length.as_u64()).unwrap_or(usize::MAX);
+    let mut data = payload.map(|v| v.map_err(Error::from)).boxed_local();
+    let (body, _) = read_part(&mut data, limit, false).await?;
+    let urls = serde_json::from_slice::<Vec<String>>(&body)
+        .map_err(|e| error::ErrorBadRequest(format!("invalid JSON body: {e}\n")))?;
+    let max_batch_size = config
+        .paste
+        .max_batch_size
+        .unwrap_or(DEFAULT_MAX_BATCH_SIZE);
+    if urls.is_empty() {
+        return Err(error::ErrorBadRequest("no URLs are given\n"));
+    }
+    if urls.len() > max_batch_size {
+        return Err(error::ErrorPayloadTooLarge(format!(
+            "too many URLs, the maximum is {max_batch_size}\n"
+        )));
+    }
+    let server_url = get_server_url(&request, &config);
+    let token_hash = get_token_hash(&config, request.headers());
+    let storage = storage::get_backend(&config)?;
+    let mut results = Vec::new();
+    for url in urls {
+        let paste = Paste {
+            data: url.as_bytes().to_vec(),
+            type_: PasteType::Url,
+            dry_run: false,
+            create_new: false,
+            metadata: None,
+        };
+        let token_hash = token_hash.clone();
+        let result = util::run_blocking(&config, &storage, move |config, storage| {
+            let file_name = paste.store_url(expiry_date, config)?;
+            if config.paste.store_metadata.unwrap_or(false) {
+                Metadata {
+                    original_name: None,
+                    content_type: None,
+                    size: paste.data.len(),
+                    created_at: time.as_millis(),
+                    expires_at: expiry_date,
+                    oneshot: false,
+                    password_hash: None,
+                    token_hash,
+                    private: false,
+                }
+                .write(
+                    storage,
+                    &config.server.upload_path,
+                    &util::get_shard_path(
+                        &paste.type_.get_path(&config.server.upload_path)?,
+                        &file_name,
+                        config.paste.shard_length,
+                    )?,
+                )?;
+            }
+            Ok((paste, file_name))
         })
-        .collect();
-    Ok(HttpResponse::Ok().json(entries))
+        .await
+        .and_then(|(paste, file_name)| {
+            info!("{} is shortened as {} from {}", url, file_name, host);
+            METRICS.uploads.inc();
+            if let Some(webhook_config) = &config.webhooks {
+                webhook::send(
+                    &client,
+                    webhook_config,
+                    &webhook::Event::new(
+                        EventType::Upload,
+                        &file_name,
+                        Some(paste.data.len() as u64),
+                        paste.type_,
+                    )?,
+                )?;
+            }
+            Ok(format!("{}/{}", server_url, file_name))
+        });
+        results.push(match result {
+            Ok(short) => ShortenedUrl {
+                url,
+                short: Some(short),
+                error: None,
+            },
+            Err(e) => {
+                METRICS.upload_failures.inc();
+                ShortenedUrl {
+                    url,
+                    short: None,
+                    error: Some(e.to_string().trim_end().to_string()),
+                }
+            }
+        });
+    }
+    Ok(HttpResponse::Ok().json(results))
 }
 
-/// Configures the server routes.
-pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("")
-            .service(index)
-            .service(version)
-            .service(list)
-            .service(serve)
-            .service(upload)
-            .service(delete)
-            .route("", web::head().to(HttpResponse::MethodNotAllowed))
-            .wrap(GrantsMiddleware::with_extractor(extract_tokens))
-            .wrap(
-                ErrorHandlers::new().handler(StatusCode::UNAUTHORIZED, handle_unauthorized_error),
-            ),
+/// Creates a resumable upload with the total size given in the
+/// [`Upload-Length`](header::UPLOAD_LENGTH) header.
+///
+/// The data is sent in chunks via [`patch_resumable_upload`].
+#[post("/uploads")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn create_resumable_upload(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    if config.server.read_only.unwrap_or(false) {
+        return Ok(read_only_error());
+    }
+    if !storage::get_backend(&config)?.is_local() {
+        return Err(error::ErrorBadRequest(
+            "resumable uploads are not supported by the storage\n",
+        ));
+    }
+    let length = header::parse_upload_size(request.headers(), header::UPLOAD_LENGTH)?
+        .filter(|v| *v > 0)
+        .ok_or_else(|| error::ErrorBadRequest("upload length is not specified\n"))?;
+    let max_content_length = config
+        .get_token_policy(get_auth_token(request.headers()).unwrap_or_default())
+        .and_then(|v| v.max_content_length)
+        .unwrap_or(config.server.max_content_length);
+    if length > max_content_length.as_u64() {
+        return Err(error::ErrorPayloadTooLarge("upload limit exceeded\n"));
+    }
+    let file_name = header::parse_header_filename(request.headers())?
+        .map(|v| util::sanitize_filename(&v))
+        .transpose()?
+        .filter(|v| !v.is_empty());
+    let timeout = config
+        .paste
+        .resumable_upload_timeout
+        .unwrap_or(resumable::DEFAULT_TIMEOUT);
+    resumable::remove_stale(&config.server.upload_path, timeout);
+    let resumable_upload = ResumableUpload::create(&config.server.upload_path, length, file_name)?;
+    info!(
+        "created resumable upload {} ({} bytes)",
+        resumable_upload.id, length
     );
+    Ok(HttpResponse::Created()
+        .insert_header((
+            LOCATION,
+            format!(
+                "{}/uploads/{}",
+                get_server_url(&request, &config),
+                resumable_upload.id
+            ),
+        ))
+        .insert_header((header::UPLOAD_OFFSET, "0"))
+        .finish())
+}
+
+/// Returns the number of the received bytes of a resumable upload.
+#[head("/uploads/{id}")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn get_resumable_upload(
+    id: web::Path<String>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    let resumable_upload = read_resumable_upload(&config, &id)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            header::UPLOAD_OFFSET,
+            resumable_upload
+                .get_offset(&config.server.upload_path)?
+                .to_string(),
+        ))
+        .insert_header((header::UPLOAD_LENGTH, resumable_upload.length.to_string()))
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .finish())
+}
+
+/// Returns the progress of a resumable upload as JSON.
+#[get("/uploads/{id}/status")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn get_resumable_upload_status(
+    id: web::Path<String>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+    let resumable_upload = read_resumable_upload(&config, &id)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .json(resumable_upload.get_status(&config.server.upload_path)?))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::LandingPageConfig;
-    use crate::middleware::ContentLengthLimiter;
-    use crate::random::{RandomURLConfig, RandomURLType};
-    use actix_web::body::MessageBody;
-    use actix_web::body::{BodySize, BoxBody};
-    use actix_web::error::Error;
-    use actix_web::http::header::AUTHORIZATION;
-    use actix_web::http::{header, StatusCode};
-    use actix_web::test::{self, TestRequest};
-    use actix_web::web::Data;
-    use actix_web::App;
-    use awc::ClientBuilder;
-    use glob::glob;
-    use std::fs::File;
-    use std::io::Write;
-    use std::path::PathBuf;
-    use std::str;
-    use std::thread;
-    use std::time::Duration;
+/// Appends a chunk to a resumable upload at the offset given in the
+/// [`Upload-Offset`](header::UPLOAD_OFFSET) header.
+///
+/// The upload is stored as a regular paste after receiving the last chunk.
+#[patch("/uploads/{id}")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn patch_resumable_upload(
+    request: HttpRequest,
+    id: web::Path<String>,
+    payload: web::Payload,
+    auth_details: AuthDetails<TokenType>,
+    client: web::Data<Client>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let (resumable_upload, upload_path) = {
+        let config = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+        if config.server.read_only.unwrap_or(false) {
+            return Ok(read_only_error());
+        }
+        (
+            read_resumable_upload(&config, &id)?,
+            config.server.upload_path.clone(),
+        )
+    };
+    let offset = resumable_upload.get_offset(&upload_path)?;
+    if header::parse_upload_size(request.headers(), header::UPLOAD_OFFSET)? != Some(offset) {
+        return Ok(offset_conflict(offset));
+    }
+    let limit = usize::try_from(resumable_upload.length - offset).unwrap_or(usize::MAX);
+    let mut data = payload.map(|v| v.map_err(Error::from)).boxed_local();
+    let (chunk, _) = read_part(&mut data, limit, false).await?;
+    // Another request may have appended to the upload while receiving the chunk.
+    let offset = match resumable_upload.append(&upload_path, offset, &chunk) {
+        Err(e) if e.as_response_error().status_code() == StatusCode::CONFLICT => {
+            return Ok(offset_conflict(resumable_upload.get_offset(&upload_path)?));
+        }
+        result => result?,
+    };
+    if offset < resumable_upload.length {
+        return Ok(HttpResponse::NoContent()
+            .insert_header((header::UPLOAD_OFFSET, offset.to_string()))
+            .finish());
+    }
+    let data = resumable_upload.finish(&upload_path)?;
+    debug!("finished resumable upload {}", resumable_upload.id);
+    // Fall back to the default file name if the file name is not specified.
+    let file_name = resumable_upload
+        .file_name
+        .unwrap_or_else(|| String::from("."));
+    let parts = stream::once(async move {
+        Ok(UploadPart {
+            content: ContentDisposition::from(ActixContentDisposition {
+                disposition: DispositionType::FormData,
+                parameters: vec![
+                    DispositionParam::Name(String::from("file")),
+                    DispositionParam::Filename(file_name),
+                ],
+            }),
+            data: stream::once(async { Ok(Bytes::from(data)) }).boxed_local(),
+        })
+    })
+    .boxed_local();
+    let response = process_upload(request, parts, auth_details, client, config).await;
+    if response.is_err() {
+        METRICS.upload_failures.inc();
+    }
+    response
+}
+
+/// Returns the response for the chunks that do not match the current offset of an upload.
+fn offset_conflict(offset: u64) -> HttpResponse {
+    HttpResponse::Conflict()
+        .insert_header((header::UPLOAD_OFFSET, offset.to_string()))
+        .body("upload offset does not match\n")
+}
+
+/// Returns the resumable upload with the given identifier or a "not found" error.
+fn read_resumable_upload(config: &Config, id: &str) -> Result<ResumableUpload, Error> {
+    ResumableUpload::read(
+        &config.server.upload_path,
+        id,
+        config
+            .paste
+            .resumable_upload_timeout
+            .unwrap_or(resumable::DEFAULT_TIMEOUT),
+    )?
+    .ok_or_else(|| error::ErrorNotFound("upload is not found or expired :(\n"))
+}
+
+/// Number of the hexadecimal characters of the content hash in the `ETag` header.
+const ETAG_LENGTH: usize = 16;
+
+/// Number of seconds for the `Retry-After` header in the read-only mode.
+const READ_ONLY_RETRY_AFTER: u64 = 300;
+
+/// Returns the response for the uploads and deletions in the read-only mode.
+///
+/// See [`read_only`](crate::config::ServerConfig::read_only).
+fn read_only_error() -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .insert_header((RETRY_AFTER, READ_ONLY_RETRY_AFTER.to_string()))
+        .body("server is in read-only mode\n")
+}
+
+/// Checks if the request has a raw `text/plain` body instead of `multipart/form-data`.
+fn is_raw_body(request: &HttpRequest) -> bool {
+    request
+        .mime_type()
+        .ok()
+        .flatten()
+        .is_some_and(|v| v.essence_str() == mime::TEXT_PLAIN.essence_str())
+}
+
+/// Checks if the request body is a [`JsonUpload`].
+fn is_json_body(request: &HttpRequest) -> bool {
+    request
+        .mime_type()
+        .ok()
+        .flatten()
+        .is_some_and(|v| v.essence_str() == mime::APPLICATION_JSON.essence_str())
+}
+
+/// JSON body of an upload.
+#[derive(Debug, Deserialize)]
+struct JsonUpload {
+    /// File name of the paste.
+    filename: Option<String>,
+    /// Base64 encoded content of the paste.
+    content_base64: String,
+    /// Expiry time of the paste (e.g. `10min`).
+    expire: Option<String>,
+}
+
+/// Returns the upload parts of a [`JsonUpload`] body.
+///
+/// The content is decoded, so that the upload limit applies to the decoded data.
+async fn get_json_parts(
+    mut payload: web::Payload,
+    max_content_length: Byte,
+) -> Result<Vec<UploadPart>, Error> {
+    let max_length = usize::try_from(util::get_json_body_limit(max_content_length.as_u64()))
+        .unwrap_or(usize::MAX);
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > max_length {
+            return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let json_upload = serde_json::from_slice::<JsonUpload>(&body)
+        .map_err(|e| error::ErrorBadRequest(format!("invalid JSON body: {e}\n")))?;
+    let data = BASE64_STANDARD
+        .decode(json_upload.content_base64.trim())
+        .map_err(|e| error::ErrorBadRequest(format!("invalid base64 content: {e}\n")))?;
+    let mut parts = Vec::new();
+    if let Some(expire) = json_upload.expire {
+        parts.push(UploadPart {
+            content: ContentDisposition::from(ActixContentDisposition {
+                disposition: DispositionType::FormData,
+                parameters: vec![DispositionParam::Name(String::from(header::EXPIRE))],
+            }),
+            data: stream::iter([Ok(Bytes::from(expire))]).boxed_local(),
+        });
+    }
+    parts.push(UploadPart {
+        content: ContentDisposition::from(ActixContentDisposition {
+            disposition: DispositionType::FormData,
+            parameters: vec![
+                DispositionParam::Name(String::from("file")),
+                DispositionParam::Filename(
+                    json_upload.filename.unwrap_or_else(|| String::from("-")),
+                ),
+            ],
+        }),
+        data: stream::iter([Ok(Bytes::from(data))]).boxed_local(),
+    });
+    Ok(parts)
+}
+
+/// Returns the upload part of a raw request body with the given file name.
+fn get_raw_part(file_name: &str, payload: web::Payload) -> UploadPart {
+    UploadPart {
+        content: ContentDisposition::from(ActixContentDisposition {
+            disposition: DispositionType::FormData,
+            parameters: vec![
+                DispositionParam::Name(String::from("file")),
+                DispositionParam::Filename(file_name.to_string()),
+            ],
+        }),
+        data: payload.map(|v| v.map_err(Error::from)).boxed_local(),
+    }
+}
+
+/// Part of an upload request.
+struct UploadPart {
+    /// Content disposition of the part.
+    content: ContentDisposition,
+    /// Data of the part.
+    data: LocalBoxStream<'static, Result<Bytes, Error>>,
+}
+
+/// Returns the upload limits in the response headers.
+///
+/// The limits of the [token policy](crate::config::TokenConfig) are returned
+/// if the request has a token with a policy.
+#[options("/")]
+async fn upload_limits(
+    request: HttpRequest,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let mut config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    if let Some(token_policy) = config
+        .get_token_policy(get_auth_token(request.headers()).unwrap_or_default())
+        .cloned()
+    {
+        token_policy.apply(&mut config);
+    }
+    let mut response = HttpResponse::NoContent();
+    response
+        .insert_header((ALLOW, "GET, POST, OPTIONS"))
+        .insert_header((
+            header::MAX_CONTENT_LENGTH,
+            config.server.max_content_length.as_u64().to_string(),
+        ));
+    if !config.paste.allowed_extensions.is_empty() {
+        response.insert_header((
+            header::ALLOWED_EXTENSIONS,
+            config.paste.allowed_extensions.join(","),
+        ));
+    }
+    if !config.paste.mime_whitelist.is_empty() {
+        response.insert_header((
+            header::ALLOWED_MIME_TYPES,
+            config.paste.mime_whitelist.join(","),
+        ));
+    }
+    Ok(response.finish())
+}
+
+/// Form fields that are accepted by the upload endpoint.
+const FORM_FIELDS: &[&str] = &[
+    "file",
+    "oneshot",
+    "url",
+    "oneshot_url",
+    "remote",
+    "append",
+    header::EXPIRE,
+    header::FILENAME,
+];
+
+/// Verifies the uploaded data against the [`SHA256 checksum`](header::CONTENT_SHA256) (if given).
+fn verify_checksum(data: &[u8], checksum: Option<&str>, host: &str) -> Result<(), Error> {
+    match checksum {
+        Some(checksum) if util::sha256_digest(data)? != checksum => {
+            warn!(
+                "{} sent a file that does not match the SHA256 checksum",
+                host
+            );
+            Err(error::ErrorUnprocessableEntity(
+                "SHA256 checksum mismatch\n",
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Stores the files of the `multipart/form-data` upload and returns their URLs.
+async fn process_upload(
+    request: HttpRequest,
+    mut payload: LocalBoxStream<'static, Result<UploadPart, Error>>,
+    auth_details: AuthDetails<TokenType>,
+    client: web::Data<Client>,
+    mut config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let token_policy = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .get_token_policy(get_auth_token(request.headers()).unwrap_or_default())
+        .cloned();
+    let token_hash = get_token_hash(
+        &*config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?,
+        request.headers(),
+    );
+    if let Some(token_policy) = &token_policy {
+        let mut request_config = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .clone();
+        token_policy.apply(&mut request_config);
+        config = web::Data::new(RwLock::new(request_config));
+    }
+    let allow_expiry = token_policy
+        .as_ref()
+        .and_then(|v| v.allow_expiry)
+        .unwrap_or(true);
+    let allow_oneshot = token_policy
+        .as_ref()
+        .and_then(|v| v.allow_oneshot)
+        .unwrap_or(true);
+    if let Some(url_length) = header::parse_url_length(request.headers())? {
+        let mut request_config = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .clone();
+        if let Some(random_url) = request_config
+            .paste
+            .random_url
+            .as_mut()
+            .filter(|v| v.max_length.is_some())
+        {
+            random_url.length = Some(
+                random_url
+                    .clamp_length(url_length)
+                    .ok_or_else(|| error::ErrorBadRequest("URL length is too short\n"))?,
+            );
+            config = web::Data::new(RwLock::new(request_config));
+        }
+    }
+    let host = &get_client_host(
+        &request,
+        &config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .trusted_proxies,
+    );
+    let server_url = get_server_url(
+        &request,
+        &*config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?,
+    );
+    let time = util::get_system_time()?;
+    let mut expiry_date = header::parse_expiry_date(request.headers(), time)?;
+    if expiry_date.is_some() && !allow_expiry {
+        warn!(
+            "{} sent an expiring paste that is not allowed for the token",
+            host
+        );
+        return Err(error::ErrorForbidden("expiring files are not allowed\n"));
+    }
+    let mut uploads: Vec<UploadItem> = Vec::new();
+    let mut header_filename = header::parse_header_filename(request.headers())?;
+    let dry_run = header::parse_dry_run(request.headers())?;
+    let create_new = header::parse_create_new(request.headers())?;
+    let private = header::parse_private(request.headers())?;
+    let content_sha256 = header::parse_content_sha256(request.headers())?;
+    if private
+        && config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .get_tokens(TokenType::Auth)
+            .is_none()
+    {
+        warn!(
+            "{} sent a private paste without auth tokens configured",
+            host
+        );
+        return Err(error::ErrorBadRequest(
+            "private pastes require auth tokens to be configured\n",
+        ));
+    }
+    let password_hash = request
+        .headers()
+        .get(header::PASSWORD)
+        .map(|v| match v.to_str() {
+            Ok(password) if !password.is_empty() => hash_password(password),
+            _ => Err(error::ErrorBadRequest("invalid password\n")),
+        })
+        .transpose()?;
+    let (max_content_length, truncate_oversize, allow_empty) = {
+        let config = config
+            .read()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+        (
+            usize::try_from(config.server.max_content_length.as_u64()).unwrap_or(usize::MAX),
+            config.server.on_oversize == OversizePolicy::Truncate,
+            config.paste.allow_empty.unwrap_or(false),
+        )
+    };
+    let default_type = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .paste
+        .default_type;
+    // Fall back to the default type for the unrecognized form fields.
+    let get_paste_type = |content: &ContentDisposition| {
+        PasteType::try_from(content).ok().or_else(|| {
+            default_type.filter(|_| {
+                ![header::EXPIRE, header::FILENAME, "append"]
+                    .iter()
+                    .any(|v| content.has_form_field(v))
+            })
+        })
+    };
+    let mut file_count = 0;
+    let mut failed_part = None;
+    // The truncated files share the upload limit so that the parts cannot exceed it in total.
+    let mut remaining_length = max_content_length;
+    while let Some(item) = payload.next().await {
+        let UploadPart {
+            content,
+            data: mut field,
+        } = match item {
+            Ok(part) => part,
+            Err(_) if failed_part.is_some() => break,
+            Err(e) => return Err(e),
+        };
+        // Skip the rest of the parts after a failure while counting the files.
+        if failed_part.is_some() {
+            if get_paste_type(&content).is_some() || content.has_form_field("append") {
+                file_count += 1;
+            }
+            continue;
+        }
+        if content.has_form_field(header::EXPIRE) {
+            let (bytes, _) = read_part(&mut field, max_content_length, false).await?;
+            let expire_time = str::from_utf8(&bytes).map_err(error::ErrorBadRequest)?;
+            expiry_date = header::parse_expiry_time(expire_time.trim(), time)?;
+            if !allow_expiry {
+                warn!(
+                    "{} sent an expiring paste that is not allowed for the token",
+                    host
+                );
+                return Err(error::ErrorForbidden("expiring files are not allowed\n"));
+            }
+            continue;
+        }
+        if content.has_form_field(header::FILENAME) {
+            let (bytes, _) = read_part(&mut field, max_content_length, false).await?;
+            let file_name = str::from_utf8(&bytes).map_err(error::ErrorBadRequest)?;
+            header_filename = Some(file_name.trim().to_string());
+            continue;
+        }
+        if content.has_form_field("append") {
+            file_count += 1;
+            let part_name = content.get_file_name().ok().map(String::from);
+            let result = async {
+                let file_name = content.get_file_name()?;
+                if !auth_details.has_authority(&TokenType::Delete)
+                    && !verify_delete_token(
+                        &*config.read().map_err(|_| {
+                            error::ErrorInternalServerError("cannot acquire config")
+                        })?,
+                        request.headers(),
+                        file_name,
+                    )
+                {
+                    warn!("{} sent an append request without a delete token", host);
+                    return Err(TokenError.into());
+                }
+                let (bytes, _) = read_part(&mut field, max_content_length, false).await?;
+                verify_checksum(&bytes, content_sha256.as_deref(), host)?;
+                let config = config
+                    .read()
+                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                    .clone();
+                let file_size = bytes.len();
+                let paste = Paste {
+                    data: bytes,
+                    type_: PasteType::File,
+                    dry_run,
+                    create_new: false,
+                    metadata: None,
+                };
+                let file_name = paste.append_file(file_name, &config).await?;
+                if dry_run {
+                    info!(
+                        "{} bytes can be appended to {} from {} (dry run)",
+                        file_size, file_name, host
+                    );
+                } else {
+                    info!(
+                        "{} bytes are appended to {} from {}",
+                        file_size, file_name, host
+                    );
+                }
+                Ok(UploadItem {
+                    url: format!("{}/{}", server_url, file_name),
+                    file_name,
+                    file_size,
+                    expires_at_utc: None,
+                    delete_token: None,
+                    truncated: false,
+                })
+            }
+            .await;
+            match result {
+                Ok(item) => uploads.push(item),
+                Err(e) => failed_part = Some((file_count, part_name, e)),
+            }
+            continue;
+        }
+        if let Some(paste_type) = get_paste_type(&content) {
+            file_count += 1;
+            let part_name = content.get_file_name().ok().map(String::from);
+            let result = async {
+                // Use the default expiry time of the paste type if it is not specified.
+                let expiry_date = match expiry_date {
+                    Some(expiry_date) => Some(expiry_date),
+                    None => config
+                        .read()
+                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                        .paste
+                        .get_default_expiry(paste_type)
+                        .and_then(|v| time.checked_add(v).map(|t| t.as_millis())),
+                };
+                if matches!(paste_type, PasteType::Oneshot | PasteType::OneshotUrl)
+                    && !allow_oneshot
+                {
+                    warn!(
+                        "{} sent a oneshot paste that is not allowed for the token",
+                        host
+                    );
+                    return Err(error::ErrorForbidden("oneshot files are not allowed\n"));
+                }
+                if (expiry_date.is_some()
+                    || paste_type == PasteType::Oneshot
+                    || paste_type == PasteType::OneshotUrl)
+                    && !storage::get_backend(
+                        &*config.read().map_err(|_| {
+                            error::ErrorInternalServerError("cannot acquire config")
+                        })?,
+                    )?
+                    .is_local()
+                {
+                    warn!("{} sent a paste that is not supported by the storage", host);
+                    return Err(error::ErrorBadRequest(
+                        "expiring and oneshot files are not supported by the storage\n",
+                    ));
+                }
+                let truncate =
+                    truncate_oversize && matches!(paste_type, PasteType::File | PasteType::Oneshot);
+                let limit = if truncate {
+                    remaining_length
+                } else {
+                    max_content_length
+                };
+                let (bytes, truncated) = read_part(&mut field, limit, truncate).await?;
+                if truncate {
+                    remaining_length -= bytes.len();
+                }
+                if truncated {
+                    warn!(
+                        "{} sent a file that exceeds the upload limit, truncated to {} bytes",
+                        host, limit
+                    );
+                }
+                if bytes.is_empty()
+                    && !(allow_empty && matches!(paste_type, PasteType::File | PasteType::Oneshot))
+                {
+                    warn!("{} sent zero bytes", host);
+                    return Err(error::ErrorBadRequest("invalid file size"));
+                }
+                verify_checksum(&bytes, content_sha256.as_deref(), host)?;
+                if token_policy
+                    .as_ref()
+                    .and_then(|v| v.max_content_length)
+                    .is_some_and(|v| bytes.len() as u64 > v.as_u64())
+                {
+                    warn!(
+                        "{} sent a file that exceeds the upload limit of the token",
+                        host
+                    );
+                    return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+                }
+                if paste_type != PasteType::Oneshot
+                    && paste_type != PasteType::RemoteFile
+                    && paste_type != PasteType::OneshotUrl
+                    && expiry_date.is_none()
+                    && password_hash.is_none()
+                    && !private
+                    && !config
+                        .read()
+                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                        .paste
+                        .duplicate_files
+                        .unwrap_or(true)
+                {
+                    let bytes_checksum = util::sha256_digest(&*bytes)?;
+                    let config = config
+                        .read()
+                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+                    if let Some(file) =
+                        Directory::find_file(config.server.get_upload_paths(), bytes_checksum)?
+                    {
+                        let file_name = file
+                            .path
+                            .file_name()
+                            .map(|v| v.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        return Ok(UploadItem {
+                            url: format!("{}/{}", server_url, file_name),
+                            file_name,
+                            file_size: bytes.len(),
+                            expires_at_utc: None,
+                            delete_token: None,
+                            truncated,
+                        });
+                    }
+                }
+                // Store the file in the next upload path if the upload path is full.
+                let overflow_config = config
+                    .read()
+                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                    .get_overflow_config(bytes.len() as u64);
+                let config = match overflow_config {
+                    Some(overflow_config) => web::Data::new(RwLock::new(overflow_config)),
+                    None => web::Data::clone(&config),
+                };
+                let original_name = match paste_type {
+                    PasteType::File | PasteType::Oneshot => {
+                        content.get_file_name().ok().map(String::from)
+                    }
+                    PasteType::RemoteFile => str::from_utf8(&bytes).ok().map(String::from),
+                    PasteType::Url | PasteType::OneshotUrl => None,
+                };
+                let store_metadata = {
+                    let config = config
+                        .read()
+                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?;
+                    config.paste.store_metadata.unwrap_or(false)
+                        || config.paste.preserve_original_name.unwrap_or(false)
+                };
+                // The size and the media type are set while storing the file.
+                let metadata = (store_metadata || password_hash.is_some() || private).then(|| {
+                    Metadata {
+                        original_name,
+                        content_type: None,
+                        size: 0,
+                        created_at: time.as_millis(),
+                        expires_at: expiry_date,
+                        oneshot: matches!(paste_type, PasteType::Oneshot | PasteType::OneshotUrl),
+                        password_hash: password_hash.clone(),
+                        token_hash: token_hash.clone(),
+                        private,
+                    }
+                });
+                let mut paste = Paste {
+                    data: bytes.to_vec(),
+                    type_: paste_type,
+                    dry_run,
+                    create_new,
+                    metadata,
+                };
+                if matches!(paste.type_, PasteType::File | PasteType::Oneshot) {
+                    let security = config
+                        .read()
+                        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                        .security
+                        .clone();
+                    paste
+                        .scan(content.get_file_name()?, security.as_ref())
+                        .await?;
+                }
+                let mut file_name = match paste.type_ {
+                    PasteType::File | PasteType::Oneshot => {
+                        let config = config
+                            .read()
+                            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                            .clone();
+                        let upload_name = content.get_file_name()?.to_string();
+                        let header_filename = header_filename.clone();
+                        let (stored_paste, file_name) = util::run_blocking(
+                            &config,
+                            &storage::get_backend(&config)?,
+                            move |config, _| {
+                                let file_name = paste.store_file(
+                                    &upload_name,
+                                    expiry_date,
+                                    header_filename,
+                                    config,
+                                )?;
+                                Ok((paste, file_name))
+                            },
+                        )
+                        .await?;
+                        paste = stored_paste;
+                        file_name
+                    }
+                    PasteType::RemoteFile => {
+                        paste
+                            .store_remote_file(expiry_date, &client, &config)
+                            .await?
+                    }
+                    PasteType::Url | PasteType::OneshotUrl => {
+                        let config = config
+                            .read()
+                            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                            .clone();
+                        let (stored_paste, file_name) = util::run_blocking(
+                            &config,
+                            &storage::get_backend(&config)?,
+                            move |config, _| {
+                                let file_name = paste.store_url(expiry_date, config)?;
+                                Ok((paste, file_name))
+                            },
+                        )
+                        .await?;
+                        paste = stored_paste;
+                        file_name
+                    }
+                };
+                let expires_at_utc = expiry_date
+                    .and_then(|v| i64::try_from(v / 1000).ok())
+                    .map(|v| uts2ts::uts2ts(v).as_string());
+                if dry_run {
+                    info!(
+                        "{} ({} bytes) can be uploaded from {} (dry run)",
+                        file_name,
+                        paste.data.len(),
+                        host
+                    );
+                    return Ok(UploadItem {
+                        url: format!("{}/{}", server_url, file_name),
+                        file_name,
+                        file_size: paste.data.len(),
+                        expires_at_utc,
+                        delete_token: None,
+                        truncated,
+                    });
+                }
+                info!(
+                    "{} ({}) is uploaded from {}",
+                    file_name,
+                    Byte::from_u128(paste.data.len() as u128)
+                        .unwrap_or_default()
+                        .get_appropriate_unit(UnitType::Decimal),
+                    host
+                );
+                METRICS.uploads.inc();
+                METRICS
+                    .uploaded_bytes
+                    .inc_by(u64::try_from(paste.data.len()).unwrap_or(u64::MAX));
+                let config = config
+                    .read()
+                    .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+                    .clone();
+                if let Some(handle_spaces_config) = config.server.handle_spaces {
+                    file_name = handle_spaces_config.process_filename(&file_name);
+                }
+                let delete_token = config
+                    .get_delete_token_secret()
+                    .map(|secret| generate_delete_token(secret, &file_name))
+                    .transpose()?;
+                if let Some(webhook_config) = &config.webhooks {
+                    webhook::send(
+                        &client,
+                        webhook_config,
+                        &webhook::Event::new(
+                            EventType::Upload,
+                            &file_name,
+                            Some(paste.data.len() as u64),
+                            paste.type_,
+                        )?,
+                    )?;
+                }
+                Ok(UploadItem {
+                    url: format!("{}/{}", server_url, file_name),
+                    file_name,
+                    file_size: paste.data.len(),
+                    expires_at_utc,
+                    delete_token,
+                    truncated,
+                })
+            }
+            .await;
+            match result {
+                Ok(item) => uploads.push(item),
+                Err(e) => failed_part = Some((file_count, part_name, e)),
+            }
+        } else {
+            warn!("{} sent an invalid form field", host);
+            return Err(error::ErrorBadRequest(format!(
+                "invalid form field \"{}\", expected one of: {}\n",
+                content.get_field_name().unwrap_or_default(),
+                FORM_FIELDS.join(", ")
+            )));
+        }
+    }
+    if let Some((part_index, part_name, e)) = failed_part {
+        if file_count == 1 {
+            return Err(e);
+        }
+        return Err(get_part_error(e, part_index, part_name.as_deref()));
+    }
+    let mut response = HttpResponse::Ok();
+    for delete_token in uploads.iter().filter_map(|v| v.delete_token.as_ref()) {
+        response.append_header((header::DELETE_TOKEN, delete_token.as_str()));
+    }
+    for item in uploads.iter().filter(|v| v.truncated) {
+        response.append_header((header::TRUNCATED, item.file_name.as_str()));
+    }
+    if accepts_json(&request) || is_json_body(&request) {
+        return Ok(response.json(uploads));
+    }
+    let response_template = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .server
+        .response_template
+        .clone();
+    Ok(response.body(
+        uploads
+            .iter()
+            .map(|v| match &response_template {
+                Some(template) => v.render(template) + "\n",
+                None => v.url.clone() + "\n",
+            })
+            .collect::<String>(),
+    ))
+}
+
+/// Reads the data of an upload part.
+///
+/// If the data exceeds the given limit, it is truncated to the limit (byte-exact)
+/// when `truncate` is `true`. Otherwise, an error is returned.
+///
+/// Returns the data and whether if it is truncated.
+async fn read_part(
+    data: &mut LocalBoxStream<'static, Result<Bytes, Error>>,
+    limit: usize,
+    truncate: bool,
+) -> Result<(Vec<u8>, bool), Error> {
+    let mut bytes = Vec::<u8>::new();
+    let mut truncated = false;
+    while let Some(chunk) = data.next().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit {
+            if !truncate {
+                return Err(error::ErrorPayloadTooLarge("upload limit exceeded"));
+            }
+            // Keep reading the rest of the part without storing it.
+            truncated = true;
+            bytes.extend_from_slice(&chunk[..limit - bytes.len()]);
+            continue;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok((bytes, truncated))
+}
+
+/// Returns the error of a failed part in a multi-file upload.
+///
+/// The part is identified by its position among the file parts and its file name.
+fn get_part_error(error: Error, part_index: usize, file_name: Option<&str>) -> Error {
+    let file_name = file_name
+        .filter(|v| !v.is_empty())
+        .map(|v| format!(" ({v})"))
+        .unwrap_or_default();
+    error::InternalError::new(
+        format!(
+            "file #{part_index}{file_name}: {}\n",
+            error.to_string().trim_end()
+        ),
+        error.as_response_error().status_code(),
+    )
+    .into()
+}
+
+/// Uploaded file item for the JSON response of the upload endpoint.
+#[derive(Serialize, Deserialize)]
+pub struct UploadItem {
+    /// Uploaded file name.
+    pub file_name: String,
+    /// URL of the uploaded file.
+    pub url: String,
+    /// Size of the file in bytes.
+    pub file_size: usize,
+    /// ISO8601 formatted date-time string of the expiration timestamp if one exists for this file.
+    pub expires_at_utc: Option<String>,
+    /// Delete token of the file if [`delete_token_secret`] is set.
+    ///
+    /// [`delete_token_secret`]: crate::config::ServerConfig::delete_token_secret
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub delete_token: Option<String>,
+    /// Whether if the file is truncated due to exceeding the upload limit.
+    ///
+    /// See [`on_oversize`](crate::config::ServerConfig::on_oversize).
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub truncated: bool,
+}
+
+impl UploadItem {
+    /// Renders the plain-text response of the file with the given template.
+    ///
+    /// See [`response_template`](crate::config::ServerConfig::response_template).
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("{url}", &self.url)
+            .replace("{filename}", &self.file_name)
+            .replace("{size}", &self.file_size.to_string())
+            .replace(
+                "{expires}",
+                self.expires_at_utc.as_deref().unwrap_or_default(),
+            )
+    }
+}
+
+/// Checks if the client prefers a JSON response via the `Accept` header.
+fn accepts_json(request: &HttpRequest) -> bool {
+    request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',').any(|v| {
+                v.split(';')
+                    .next()
+                    .map(|v| {
+                        v.trim()
+                            .eq_ignore_ascii_case(mime::APPLICATION_JSON.essence_str())
+                    })
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Checks if the client accepts the given content coding via the `Accept-Encoding` header.
+fn accepts_encoding(request: &HttpRequest, encoding: &str) -> bool {
+    request
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',').any(|v| {
+                let mut parts = v.split(';');
+                parts
+                    .next()
+                    .map(|v| v.trim().eq_ignore_ascii_case(encoding))
+                    .unwrap_or(false)
+                    && !parts.any(|v| {
+                        v.trim()
+                            .strip_prefix("q=")
+                            .and_then(|v| v.parse::<f32>().ok())
+                            .map(|v| v == 0.0)
+                            .unwrap_or(false)
+                    })
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// File entry item for list endpoint.
+#[derive(Serialize, Deserialize)]
+pub struct ListItem {
+    /// Uploaded file name.
+    pub file_name: PathBuf,
+    /// Size of the file in bytes.
+    pub file_size: u64,
+    /// ISO8601 formatted date-time string of the modification timestamp.
+    #[serde(default)]
+    pub modified_at_utc: Option<String>,
+    /// ISO8601 formatted date-time string of the expiration timestamp if one exists for this file.
+    pub expires_at_utc: Option<String>,
+    /// Whether if the entry is a shortened URL.
+    #[serde(default)]
+    pub is_url: bool,
+}
+
+/// Listing options (i.e. query parameters).
+#[derive(Debug, Deserialize)]
+struct ListOptions {
+    /// If set to `true` (or given without a value), include the shortened URLs in the list.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    urls: bool,
+}
+
+/// Returns the list of files.
+#[get("/list")]
+#[actix_web_grants::protect("TokenType::Auth", ty = TokenType, error = unauthorized_error)]
+async fn list(
+    options: Option<web::Query<ListOptions>>,
+    config: web::Data<RwLock<Config>>,
+) -> Result<HttpResponse, Error> {
+    let config = config
+        .read()
+        .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+        .clone();
+    if !config.server.expose_list.unwrap_or(false) {
+        warn!("server is not configured to expose list endpoint");
+        Err(error::ErrorNotFound(""))?;
+    }
+    let mut entries = list_files(&config.server.upload_path, false)?;
+    for shard_dir in util::get_shard_dirs(&config.server.upload_path) {
+        entries.append(&mut list_files(&shard_dir, false)?);
+    }
+    if options.map(|v| v.urls).unwrap_or(false) {
+        let url_path = PasteType::Url.get_path(&config.server.upload_path)?;
+        if url_path.is_dir() {
+            entries.append(&mut list_files(&url_path, true)?);
+            for shard_dir in util::get_shard_dirs(&url_path) {
+                entries.append(&mut list_files(&shard_dir, true)?);
+            }
+        }
+    }
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Returns the unexpired files in the given directory.
+///
+/// Subdirectories (e.g. oneshot files) are not included.
+fn list_files(path: &Path, is_url: bool) -> Result<Vec<ListItem>, Error> {
+    Ok(fs::read_dir(path)?
+        .filter_map(|entry| {
+            entry.ok().and_then(|e| {
+                let metadata = match e.metadata() {
+                    Ok(metadata) => {
+                        if metadata.is_dir() {
+                            return None;
+                        }
+                        metadata
+                    }
+                    Err(e) => {
+                        error!("failed to read metadata: {e}");
+                        return None;
+                    }
+                };
+                let mut file_name = PathBuf::from(e.file_name());
+                let expires_at_utc = if let Some(expiration) = file_name
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(|v| v.parse::<i64>().ok())
+                {
+                    file_name.set_extension("");
+                    if util::get_system_time().ok()?
+                        > Duration::from_millis(expiration.try_into().ok()?)
+                    {
+                        return None;
+                    }
+                    Some(uts2ts::uts2ts(expiration / 1000).as_string())
+                } else {
+                    None
+                };
+                let modified_at_utc = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+                    .and_then(|v| i64::try_from(v.as_secs()).ok())
+                    .map(|v| uts2ts::uts2ts(v).as_string());
+                Some(ListItem {
+                    file_name,
+                    file_size: metadata.len(),
+                    modified_at_utc,
+                    expires_at_utc,
+                    is_url,
+                })
+            })
+        })
+        .collect())
+}
+
+/// Configures the server routes.
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("")
+            .service(index)
+            .service(version)
+            .service(health)
+            .service(list)
+            .service(metrics)
+            .service(admin_config)
+            .service(admin_stats)
+            .service(qr_code)
+            .service(create_alias)
+            .service(serve_thumbnail)
+            .service(serve)
+            .service(exists)
+            .service(upload)
+            .service(put_upload)
+            .service(upload_limits)
+            .service(shorten_urls)
+            .service(create_resumable_upload)
+            .service(get_resumable_upload)
+            .service(get_resumable_upload_status)
+            .service(patch_resumable_upload)
+            .service(delete)
+            .service(cleanup)
+            .service(delete_token_files)
+            .route("", web::head().to(HttpResponse::MethodNotAllowed))
+            .wrap(GrantsMiddleware::with_extractor(extract_tokens))
+            .wrap(
+                ErrorHandlers::new().handler(StatusCode::UNAUTHORIZED, handle_unauthorized_error),
+            ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LandingPageConfig;
+    use crate::config::{
+        CacheControlConfig, ConflictHandlingConfig, CorsConfig, RateLimitConfig,
+        SecurityHeadersConfig, StorageConfig,
+    };
+    use crate::middleware::{
+        AccessLogger, ContentLengthLimiter, IpFilter, RateLimiter, SecurityHeaders,
+    };
+    use crate::random::{RandomURLConfig, RandomURLType};
+    use actix_web::body::MessageBody;
+    use actix_web::body::{BodySize, BoxBody};
+    use actix_web::error::Error;
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::{self, TestRequest};
+    use actix_web::web::Data;
+    use actix_web::App;
+    use awc::ClientBuilder;
+    use glob::glob;
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::str;
+    use std::thread;
+    use std::time::Duration;
+
+    fn get_multipart_request(data: &str, name: &str, filename: &str) -> TestRequest {
+        let multipart_data = format!(
+            "\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n\
+             {}\r\n\
+             --multipart_bound--\r\n",
+            name,
+            filename,
+            data.len(),
+            data,
+        );
+        TestRequest::post()
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .insert_header((
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_str(&data.len().to_string())
+                    .expect("cannot create header value"),
+            ))
+            .set_payload(multipart_data)
+    }
+
+    async fn assert_body(body: BoxBody, expected: &str) -> Result<(), Error> {
+        if let BodySize::Sized(size) = body.size() {
+            assert_eq!(size, expected.len() as u64);
+            let body_bytes = actix_web::body::to_bytes(body).await?;
+            let body_text = str::from_utf8(&body_bytes)?;
+            assert_eq!(expected, body_text);
+            Ok(())
+        } else {
+            Err(error::ErrorInternalServerError("unexpected body type"))
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_index() -> Result<(), Error> {
+        let config = Config::default();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "rustypaste\n").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page() -> Result<(), Error> {
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                text: Some(String::from("landing page")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "landing page").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page_file() -> Result<(), Error> {
+        let filename = "landing_page.txt";
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                file: Some(filename.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut file = File::create(filename)?;
+        file.write_all("landing page from file".as_bytes())?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "landing page from file").await?;
+        fs::remove_file(filename)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_landing_page_file_not_found() -> Result<(), Error> {
+        let filename = "landing_page.txt";
+        let config = Config {
+            landing_page: Some(LandingPageConfig {
+                text: Some(String::from("landing page")),
+                file: Some(filename.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "rustypaste\n").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_with_builtin_landing_page() -> Result<(), Error> {
+        let mut config = Config {
+            landing_page: Some(LandingPageConfig {
+                builtin: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        config.server.max_content_length = Byte::from_u64(10_000_000);
+        config.paste.default_expiry = Some(Duration::from_secs(3600));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .service(index),
+        )
+        .await;
+        let request = TestRequest::default().to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = actix_web::body::to_bytes(response.into_body()).await?;
+        let body = str::from_utf8(&body).map_err(error::ErrorInternalServerError)?;
+        assert!(body.contains("curl -F 'file=@example.txt' http://localhost:8080\n"));
+        assert!(body.contains("Maximum file size: 10 MB\n"));
+        assert!(body.contains("Files expire after: 1h\n"));
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth_failure_status() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_auth_failure_status");
+        config.server.auth_tokens = Some(["auth".to_string()].into());
+        config.server.delete_tokens = Some(["delete".to_string()].into());
+        config.server.auth_failure_status = Some(404);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+        let config = Data::new(RwLock::new(config));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::clone(&config))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for request in [
+            get_multipart_request("data", "file", "data.txt").to_request(),
+            TestRequest::delete().uri("/data.txt").to_request(),
+            TestRequest::get().uri("/admin/stats").to_request(),
+        ] {
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::NOT_FOUND, response.status());
+            assert_body(response.into_body(), "").await?;
+        }
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .auth_failure_body = Some(String::from("nothing to see here\n"));
+        let response = test::call_service(
+            &app,
+            get_multipart_request("data", "file", "data.txt")
+                .insert_header((header::AUTHORIZATION, "wrong"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_body(response.into_body(), "nothing to see here\n").await?;
+
+        // The private pastes are not affected.
+        let response = test::call_service(
+            &app,
+            get_multipart_request("data", "file", "private.txt")
+                .insert_header((header::AUTHORIZATION, "auth"))
+                .insert_header((crate::header::PRIVATE, "true"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/private.txt").to_request()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "unauthorized\n").await?;
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .server
+            .auth_failure_status = None;
+        let response =
+            test::call_service(&app, TestRequest::get().uri("/admin/stats").to_request()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "nothing to see here\n").await?;
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version_without_auth() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "unauthorized\n").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version_without_config() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_body(response.into_body(), "").await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_version() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_version = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/version")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &(env!("CARGO_PKG_VERSION").to_owned() + "\n"),
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_health() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/health").to_request();
+        let result: HealthStatus = test::call_and_read_body_json(&app, request).await;
+        assert_eq!("ok", result.status);
+        assert!(result.writable);
+
+        config.server.upload_path = PathBuf::from("test_health_does_not_exist");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/health").to_request();
+        let result: HealthStatus = test::call_and_read_body_json(&app, request).await;
+        assert_eq!("ok", result.status);
+        assert!(!result.writable);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_metrics() -> Result<(), Error> {
+        let mut config = Config::default();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/metrics").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        config.server.expose_metrics = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/metrics").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = response.into_body().try_into_bytes().unwrap_or_default();
+        let body = str::from_utf8(&body).map_err(error::ErrorInternalServerError)?;
+        assert!(body.contains("rustypaste_uploads_total"));
+        assert!(body.contains("rustypaste_files"));
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let filename = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", filename).to_request(),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/list")
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result.first().expect("json object").file_name,
+            PathBuf::from(filename)
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_urls() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_upload_list_urls";
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        fs::create_dir_all(PasteType::Url.get_path(&config.server.upload_path)?)?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        test::call_service(
+            &app,
+            get_multipart_request("test data", "file", "test_file.txt").to_request(),
+        )
+        .await;
+        test::call_service(
+            &app,
+            get_multipart_request("https://example.com", "url", "").to_request(),
+        )
+        .await;
+
+        let request = TestRequest::default().uri("/list").to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(result.len(), 1);
+        let item = result.first().expect("json object");
+        assert!(!item.is_url);
+        assert!(item.modified_at_utc.is_some());
+
+        for uri in ["/list?urls=true", "/list?urls"] {
+            let request = TestRequest::default().uri(uri).to_request();
+            let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+            assert_eq!(result.len(), 2);
+            assert!(result
+                .iter()
+                .any(|v| v.is_url && v.file_name == Path::new("url")));
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_list_expired() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.expose_list = Some(true);
+
+        let test_upload_dir = "test_upload";
+        fs::create_dir(test_upload_dir)?;
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let filename = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", filename)
+                .insert_header((
+                    header::HeaderName::from_static("expire"),
+                    header::HeaderValue::from_static("50ms"),
+                ))
+                .to_request(),
+        )
+        .await;
+
+        thread::sleep(Duration::from_millis(500));
+
+        let request = TestRequest::default()
+            .insert_header(("content-type", "text/plain"))
+            .uri("/list")
+            .to_request();
+        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
+
+        assert!(result.is_empty());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_auth() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response =
+            test::call_service(&app, get_multipart_request("", "", "").to_request()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_body(response.into_body(), "unauthorized\n").await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .wrap(ContentLengthLimiter::new(
+                    Byte::from_u64(1),
+                    OversizePolicy::Reject,
+                ))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "test").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_body(response.into_body().boxed(), "upload limit exceeded").await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit() -> Result<(), Error> {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .wrap(RateLimiter::new(Some(RateLimitConfig {
+                    requests: 2,
+                    window: Duration::from_secs(60),
+                    exempt_reads: Some(true),
+                    ..Default::default()
+                })))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let request = TestRequest::delete().uri("/test_rate_limit").to_request();
+            let response = test::call_service(&app, request).await;
+            assert_ne!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        }
+        let request = TestRequest::delete().uri("/test_rate_limit").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(
+            Some("60"),
+            response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let request = TestRequest::get().uri("/health").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let request = TestRequest::get().uri("/test_rate_limit").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_ne!(StatusCode::TOO_MANY_REQUESTS, response.status());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_ip_filter() -> Result<(), Error> {
+        let parse_peer_addr = |v: &str| {
+            v.parse::<std::net::SocketAddr>()
+                .map_err(error::ErrorInternalServerError)
+        };
+        let mut config = Config::default();
+        config.server.allowed_ips = vec!["10.0.0.0/8"
+            .parse()
+            .map_err(error::ErrorInternalServerError)?];
+        config.server.blocked_ips = vec!["10.0.0.1"
+            .parse()
+            .map_err(error::ErrorInternalServerError)?];
+        config.server.upload_path = env::current_dir()?.join("test_ip_filter");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .wrap(IpFilter::new(&config.server))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for peer_addr in ["10.0.0.1:8000", "192.168.0.1:8000"] {
+            let request = TestRequest::delete()
+                .uri("/test_ip_filter")
+                .peer_addr(parse_peer_addr(peer_addr)?)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::FORBIDDEN, response.status());
+        }
+        let request = TestRequest::delete()
+            .uri("/test_ip_filter")
+            .peer_addr(parse_peer_addr("10.0.0.2:8000")?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::get()
+            .uri("/test_ip_filter")
+            .peer_addr(parse_peer_addr("192.168.0.1:8000")?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        config.server.filter_reads = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .wrap(IpFilter::new(&config.server))
+                .configure(configure_routes),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri("/test_ip_filter")
+            .peer_addr(parse_peer_addr("192.168.0.1:8000")?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        // The forwarded address is ignored without trusted proxies.
+        let request = TestRequest::get()
+            .uri("/test_ip_filter")
+            .peer_addr(parse_peer_addr("10.0.0.2:8000")?)
+            .insert_header(("X-Forwarded-For", "192.168.0.1"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let request = TestRequest::get()
+            .uri("/health")
+            .peer_addr(parse_peer_addr("192.168.0.1:8000")?)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_trusted_proxies() -> Result<(), Error> {
+        let parse_peer_addr = |v: &str| {
+            v.parse::<std::net::SocketAddr>()
+                .map_err(error::ErrorInternalServerError)
+        };
+        let mut config = Config::default();
+        config.server.allowed_ips = vec!["192.168.0.0/16"
+            .parse()
+            .map_err(error::ErrorInternalServerError)?];
+        config.server.trusted_proxies = vec!["10.0.0.0/24"
+            .parse()
+            .map_err(error::ErrorInternalServerError)?];
+        config.server.upload_path = env::current_dir()?.join("test_trusted_proxies");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .wrap(IpFilter::new(&config.server))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (peer_addr, forwarded_for, status) in [
+            ("10.0.0.1:8000", "192.168.0.1", StatusCode::NOT_FOUND),
+            (
+                "10.0.0.1:8000",
+                "192.168.0.1, 10.0.0.2",
+                StatusCode::NOT_FOUND,
+            ),
+            ("10.0.1.1:8000", "192.168.0.1", StatusCode::FORBIDDEN),
+            (
+                "10.0.0.1:8000",
+                "192.168.0.1, 172.16.0.1",
+                StatusCode::FORBIDDEN,
+            ),
+        ] {
+            let request = TestRequest::delete()
+                .uri("/test_trusted_proxies")
+                .peer_addr(parse_peer_addr(peer_addr)?)
+                .insert_header(("X-Forwarded-For", forwarded_for))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_access_log() -> Result<(), Error> {
+        let log_path = env::current_dir()?.join("test_access.log");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .wrap(AccessLogger::new(Some(&log_path))?)
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get()
+            .uri("/version?password=secret_password")
+            .insert_header((
+                AUTHORIZATION,
+                header::HeaderValue::from_static("secret_token"),
+            ))
+            .peer_addr(
+                "127.0.0.1:8080"
+                    .parse()
+                    .map_err(error::ErrorInternalServerError)?,
+            )
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::get()
+            .uri("/health")
+            .insert_header(("X-Forwarded-For", "192.168.0.1"))
+            .peer_addr(
+                "127.0.0.2:8080"
+                    .parse()
+                    .map_err(error::ErrorInternalServerError)?,
+            )
+            .to_request();
+        test::call_service(&app, request).await;
+
+        let access_log = fs::read_to_string(&log_path)?;
+        assert!(access_log.contains("127.0.0.1 \"GET /version\" 404 "));
+        assert!(access_log.contains("127.0.0.2 \"GET /health\" "));
+        assert!(!access_log.contains("192.168.0.1"));
+        assert!(!access_log.contains("secret"));
+
+        fs::remove_file(log_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_security_headers() -> Result<(), Error> {
+        let test_upload_dir = "test_security_headers_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("page.html"),
+            "<script>alert(1)</script>",
+        )?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("image.svg"),
+            "<svg></svg>",
+        )?;
+        fs::write(PathBuf::from(test_upload_dir).join("file.txt"), "text")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.landing_page = Some(LandingPageConfig {
+            text: Some(String::from("<h1>landing page</h1>")),
+            content_type: Some(String::from("text/html")),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .wrap(SecurityHeaders::new(None))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in ["page.html", "image.svg"] {
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some(SecurityHeadersConfig::DEFAULT_CONTENT_SECURITY_POLICY),
+                response
+                    .headers()
+                    .get(header::CONTENT_SECURITY_POLICY)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert_eq!(
+                Some("nosniff"),
+                response
+                    .headers()
+                    .get(header::X_CONTENT_TYPE_OPTIONS)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert_eq!(
+                Some("DENY"),
+                response
+                    .headers()
+                    .get(header::X_FRAME_OPTIONS)
+                    .and_then(|v| v.to_str().ok())
+            );
+        }
+        for uri in ["/file.txt", "/"] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert!(response
+                .headers()
+                .get(header::CONTENT_SECURITY_POLICY)
+                .is_none());
+            assert!(response.headers().contains_key(header::X_FRAME_OPTIONS));
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .wrap(SecurityHeaders::new(Some(&SecurityHeadersConfig {
+                    content_security_policy: Some(String::from("sandbox")),
+                    frame_options: Some(String::new()),
+                    force_download: Some(true),
+                    ..SecurityHeadersConfig::default()
+                })))
+                .configure(configure_routes),
+        )
+        .await;
+        let request = TestRequest::get().uri("/page.html").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("sandbox"),
+            response
+                .headers()
+                .get(header::CONTENT_SECURITY_POLICY)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!(
+            Some("attachment"),
+            response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert!(!response.headers().contains_key(header::X_FRAME_OPTIONS));
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_cache_control() -> Result<(), Error> {
+        let test_upload_dir = "test_cache_control_upload";
+        let upload_path = PathBuf::from(test_upload_dir);
+        fs::create_dir_all(PasteType::Oneshot.get_path(&upload_path)?)?;
+        fs::write(upload_path.join("file.txt"), "text")?;
+        fs::write(upload_path.join("image.png"), "png")?;
+        fs::write(upload_path.join("0123456789abcdef.txt"), "hash")?;
+        fs::write(upload_path.join("oneshot").join("once.txt"), "once")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = upload_path.clone();
+        config.server.cache_control = Some(CacheControlConfig {
+            default: Some(String::from("public, max-age=60")),
+            content_types: [(String::from("image/*"), String::from("max-age=86400"))].into(),
+            ..CacheControlConfig::default()
+        });
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Hash,
+            ..RandomURLConfig::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (file_name, cache_control) in [
+            ("file.txt", "public, max-age=60"),
+            ("image.png", "max-age=86400"),
+            (
+                "0123456789abcdef.txt",
+                CacheControlConfig::DEFAULT_CONTENT_ADDRESSED,
+            ),
+            ("once.txt", CacheControlConfig::DEFAULT_ONESHOT),
+        ] {
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some(cache_control),
+                response
+                    .headers()
+                    .get(header::CACHE_CONTROL)
+                    .and_then(|v| v.to_str().ok())
+            );
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_cors() -> Result<(), Error> {
+        let cors_config = CorsConfig {
+            allowed_origins: vec![String::from("https://example.com")],
+            allowed_headers: vec![String::from("authorization"), String::from("expire")],
+            ..CorsConfig::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(Client::default()))
+                .wrap(crate::middleware::cors(Some(&cors_config)))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "expire"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("https://example.com"),
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("POST")));
+
+        let request = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "PUT"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let request = TestRequest::get()
+            .uri("/version")
+            .insert_header((header::ORIGIN, "https://other.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+
+        let request = TestRequest::get()
+            .uri("/version")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(
+            Some("https://example.com"),
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit_without_content_length() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .wrap(ContentLengthLimiter::new(
+                    Byte::from_u64(100),
+                    OversizePolicy::Reject,
+                ))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_payload_limit_stream.txt";
+        let mut request = get_multipart_request(&"x".repeat(1000), "file", file_name).to_request();
+        request.headers_mut().remove(header::CONTENT_LENGTH);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_body(response.into_body().boxed(), "upload limit exceeded").await?;
+        assert!(!env::current_dir()?.join(file_name).exists());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit_truncate() -> Result<(), Error> {
+        let test_upload_dir = "test_truncate_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(10);
+        config.server.on_oversize = OversizePolicy::Truncate;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .wrap(ContentLengthLimiter::new(
+                    Byte::from_u64(10),
+                    OversizePolicy::Truncate,
+                ))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "truncated.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&"x".repeat(1000), "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some(file_name),
+            response
+                .headers()
+                .get(crate::header::TRUNCATED)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!(
+            "x".repeat(10),
+            fs::read_to_string(PathBuf::from(test_upload_dir).join(file_name))?
+        );
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("small", "file", "small.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(crate::header::TRUNCATED).is_none());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("https://example.com/long", "url", "").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        let multipart_data = ["first.txt", "second.txt"]
+            .iter()
+            .map(|file_name| {
+                format!(
+                    "--multipart_bound\r\n\
+                     Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+                     Content-Type: text/plain\r\n\r\n\
+                     {}\r\n",
+                    "x".repeat(8)
+                )
+            })
+            .collect::<String>()
+            + "--multipart_bound--\r\n";
+        let response = test::call_service(
+            &app,
+            TestRequest::post()
+                .insert_header((
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static(
+                        "multipart/mixed; boundary=\"multipart_bound\"",
+                    ),
+                ))
+                .set_payload(multipart_data)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("second.txt"),
+            response
+                .headers()
+                .get(crate::header::TRUNCATED)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!(
+            "x".repeat(8),
+            fs::read_to_string(PathBuf::from(test_upload_dir).join("first.txt"))?
+        );
+        assert_eq!(
+            "x".repeat(2),
+            fs::read_to_string(PathBuf::from(test_upload_dir).join("second.txt"))?
+        );
+
+        let response = test::call_service(
+            &app,
+            TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, "text/plain"))
+                .set_payload("x".repeat(1000))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_overflow_paths() -> Result<(), Error> {
+        let upload_path = env::current_dir()?.join("test_overflow_upload");
+        let overflow_path = env::current_dir()?.join("test_overflow_upload_2");
+        fs::create_dir_all(&upload_path)?;
+        fs::create_dir_all(&overflow_path)?;
+        let mut config = Config::default();
+        config.server.upload_path = upload_path.clone();
+        config.server.overflow_paths = vec![overflow_path.clone()];
+        config.server.overflow_threshold = Some(Byte::from_u64(10));
+        config.server.delete_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for file_name in ["first.txt", "second.txt"] {
+            let request = get_multipart_request("test data", "file", file_name).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+        assert!(upload_path.join("first.txt").is_file());
+        assert!(overflow_path.join("second.txt").is_file());
+
+        let request = get_multipart_request("new data", "file", "first.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        assert!(!overflow_path.join("first.txt").exists());
+
+        for (file_name, data) in [("first.txt", "test data"), ("second.txt", "test data")] {
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), data).await?;
+        }
+
+        let request = TestRequest::get()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/admin/stats")
+            .to_request();
+        let response: Stats = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(2, response.file_count);
+        assert_eq!(18, response.total_bytes);
+
+        fs::write(overflow_path.join("expired.txt.1"), "expired")?;
+        let request = TestRequest::post()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/admin/cleanup")
+            .to_request();
+        let report: CleanupReport = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, report.expired_files);
+        assert!(!overflow_path.join("expired.txt.1").exists());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/second.txt")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!overflow_path.join("second.txt").exists());
+
+        fs::remove_dir_all(upload_path)?;
+        fs::remove_dir_all(overflow_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "file deleted\n").await?;
+
+        let path = PathBuf::from(file_name);
+        assert!(!path.exists());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_file_sharded() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?.join("test_sharded_upload");
+        config.paste.shard_length = Some(2);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "sharded_file.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("sharded", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(upload_path.join("sh").join(file_name).is_file());
+
+        fs::write(upload_path.join("flat_file.txt"), "flat")?;
+        for (file_name, data) in [(file_name, "sharded"), ("flat_file.txt", "flat")] {
+            let serve_request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), data).await?;
+        }
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!upload_path.join("sh").join(file_name).exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file_without_token_in_config() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_body(response.into_body(), "").await?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file_with_delete_token() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_token_secret = Some("secret".to_string());
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file_delete_token.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let delete_token = response
+            .headers()
+            .get(crate::header::DELETE_TOKEN)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .expect("delete token is not returned");
+
+        let request = TestRequest::delete()
+            .insert_header((crate::header::DELETE_TOKEN, "invalid_token"))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert!(PathBuf::from(file_name).exists());
+
+        let request = TestRequest::delete()
+            .insert_header((crate::header::DELETE_TOKEN, delete_token.as_str()))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "file deleted\n").await?;
+        assert!(!PathBuf::from(file_name).exists());
+
+        let request = TestRequest::delete()
+            .insert_header((crate::header::DELETE_TOKEN, delete_token.as_str()))
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&timestamp, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        fs::remove_file(file_name)?;
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_cleanup() -> Result<(), Error> {
+        let test_upload_dir = "test_cleanup_endpoint_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(PathBuf::from(test_upload_dir).join("expired.txt.1"), "data")?;
+        fs::write(PathBuf::from(test_upload_dir).join("file.txt"), "data")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.delete_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::post().uri("/admin/cleanup").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert!(PathBuf::from(test_upload_dir)
+            .join("expired.txt.1")
+            .exists());
+
+        let request = TestRequest::post()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/admin/cleanup")
+            .to_request();
+        let report: crate::cleanup::CleanupReport =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, report.expired_files);
+        assert_eq!(4, report.freed_bytes);
+        assert!(!PathBuf::from(test_upload_dir)
+            .join("expired.txt.1")
+            .exists());
+        assert!(PathBuf::from(test_upload_dir).join("file.txt").exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_delete_token_files() -> Result<(), Error> {
+        let test_upload_dir = "test_delete_token_files_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.auth_tokens = Some(["first".to_string(), "second".to_string()].into());
+        config.server.delete_tokens = Some(["admin".to_string()].into());
+        config.paste.store_metadata = Some(true);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (token, file_name) in [("first", "first.txt"), ("second", "second.txt")] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("data", "file", file_name)
+                    .insert_header((AUTHORIZATION, token))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        let uri = format!("/admin/token/{}", util::sha256_digest("first".as_bytes())?);
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, "first"))
+            .uri(&uri)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, "admin"))
+            .uri(&uri)
+            .to_request();
+        let report: crate::cleanup::DeletionReport =
+            test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, report.deleted_files);
+        assert_eq!(4, report.freed_bytes);
+        assert!(!PathBuf::from(test_upload_dir).join("first.txt").exists());
+        assert!(PathBuf::from(test_upload_dir).join("second.txt").exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_admin_stats() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_admin_stats");
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        fs::create_dir_all(&config.server.upload_path)?;
+        fs::write(config.server.upload_path.join("file.txt"), "test data")?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get().uri("/admin/stats").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/admin/stats")
+            .to_request();
+        let response: Stats = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, response.file_count);
+        assert_eq!(9, response.total_bytes);
+        assert_eq!(1, response.files);
+        assert_eq!(
+            Some("file.txt"),
+            response.newest.as_ref().map(|v| v.file_name.as_str())
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_admin_config() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.auth_tokens = Some(["auth".to_string()].into());
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.delete_token_secret = Some(String::from("secret"));
+        config.storage = Some(StorageConfig {
+            encryption_key: Some(String::from("key")),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("auth")))
+            .uri("/admin/config")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/admin/config")
+            .to_request();
+        let response: Config = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(
+            Some(HashSet::from([String::from("[redacted]")])),
+            response.server.auth_tokens
+        );
+        assert_eq!(
+            Some(HashSet::from([String::from("[redacted]")])),
+            response.server.delete_tokens
+        );
+        assert_eq!(
+            Some(String::from("[redacted]")),
+            response.server.delete_token_secret
+        );
+        assert_eq!(
+            Some(String::from("[redacted]")),
+            response.storage.and_then(|v| v.encryption_key)
+        );
+        assert_eq!(
+            Config::default().server.upload_path,
+            response.server.upload_path
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_read_only() -> Result<(), Error> {
+        let test_upload_dir = "test_read_only_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(PathBuf::from(test_upload_dir).join("file.txt"), "data")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.read_only = Some(true);
+        config.server.delete_tokens = Some(["test".to_string()].into());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("data", "file", "new.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!(
+            Some("300"),
+            response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "server is in read-only mode\n").await?;
+        assert!(!PathBuf::from(test_upload_dir).join("new.txt").exists());
+
+        let request = TestRequest::delete()
+            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+            .uri("/file.txt")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert!(PathBuf::from(test_upload_dir).join("file.txt").exists());
+
+        let request = TestRequest::get().uri("/file.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "data").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_raw_body() -> Result<(), Error> {
+        let test_upload_dir = "test_raw_body_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let request = TestRequest::post()
+            .insert_header(("content-type", "text/plain; charset=utf-8"))
+            .set_payload(timestamp.clone())
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/stdin.txt\n").await?;
+
+        let serve_request = TestRequest::get().uri("/stdin.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        let request = TestRequest::post()
+            .insert_header(("content-type", "text/plain"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_put_upload() -> Result<(), Error> {
+        let test_upload_dir = "test_put_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.max_content_length = Byte::from_u64(20);
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::put()
+            .uri("/screenshot.png")
+            .set_payload("test data")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::put()
+            .uri("/screenshot.png")
+            .insert_header((AUTHORIZATION, "test"))
+            .set_payload("test data")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "http://localhost:8080/screenshot.png\n",
+        )
+        .await?;
+
+        let serve_request = TestRequest::get().uri("/screenshot.png").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "test data").await?;
+
+        let request = TestRequest::put()
+            .uri("/large.txt")
+            .insert_header((AUTHORIZATION, "test"))
+            .set_payload("too much test data for the limit")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        let request = TestRequest::put()
+            .uri("/..%2Fescape.txt")
+            .insert_header((AUTHORIZATION, "test"))
+            .set_payload("test data")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert!(!Path::new("escape.txt").exists());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_json_upload() -> Result<(), Error> {
+        let test_upload_dir = "test_json_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(10);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        // "test data" is 9 bytes, which is below the limit after decoding.
+        let request = TestRequest::post()
+            .uri("/")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(
+                r#"{"filename": "json.txt", "content_base64": "dGVzdCBkYXRh", "expire": "10min"}"#,
+            )
+            .to_request();
+        let response: Vec<UploadItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, response.len());
+        assert_eq!("json.txt", response[0].file_name);
+        assert_eq!(9, response[0].file_size);
+        assert!(response[0].expires_at_utc.is_some());
+
+        let serve_request = TestRequest::get().uri("/json.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "test data").await?;
+
+        for (body, status) in [
+            (
+                r#"{"content_base64": "not base64!"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (r#"{"filename": "x.txt"}"#, StatusCode::BAD_REQUEST),
+            (
+                r#"{"filename": "large.txt", "content_base64": "dG9vIG11Y2ggZGF0YQ=="}"#,
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ] {
+            let request = TestRequest::post()
+                .uri("/")
+                .insert_header((header::CONTENT_TYPE, "application/json"))
+                .set_payload(body)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_resumable_upload() -> Result<(), Error> {
+        let test_upload_dir = "test_resumable_server_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.max_content_length = Byte::from_u64(10);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::post()
+            .uri("/uploads")
+            .insert_header((crate::header::UPLOAD_LENGTH, "11"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        let request = TestRequest::post()
+            .uri("/uploads")
+            .insert_header((crate::header::UPLOAD_LENGTH, "6"))
+            .insert_header((crate::header::FILENAME, "chunks.txt"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::CREATED, response.status());
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .ok_or_else(|| error::ErrorInternalServerError("location is not found"))?;
+        let uri = location.trim_start_matches("http://localhost:8080");
+        assert!(uri.starts_with("/uploads/"));
+
+        let request = TestRequest::patch()
+            .uri(uri)
+            .insert_header((crate::header::UPLOAD_OFFSET, "0"))
+            .set_payload("abc")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NO_CONTENT, response.status());
+        assert_eq!(
+            Some("3"),
+            response
+                .headers()
+                .get(crate::header::UPLOAD_OFFSET)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let request = TestRequest::patch()
+            .uri(uri)
+            .insert_header((crate::header::UPLOAD_OFFSET, "0"))
+            .set_payload("def")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+
+        let request = TestRequest::default()
+            .method(Method::HEAD)
+            .uri(uri)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("3"),
+            response
+                .headers()
+                .get(crate::header::UPLOAD_OFFSET)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        let request = TestRequest::get()
+            .uri(&format!("{uri}/status"))
+            .to_request();
+        let status: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(
+            serde_json::json!({"received_bytes": 3, "total_bytes": 6, "percentage": 50.0}),
+            status
+        );
+
+        let request = TestRequest::patch()
+            .uri(uri)
+            .insert_header((crate::header::UPLOAD_OFFSET, "3"))
+            .set_payload("defg")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        let request = TestRequest::patch()
+            .uri(uri)
+            .insert_header((crate::header::UPLOAD_OFFSET, "3"))
+            .set_payload("def")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/chunks.txt\n").await?;
+
+        let serve_request = TestRequest::get().uri("/chunks.txt").to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "abcdef").await?;
+
+        let request = TestRequest::default()
+            .method(Method::HEAD)
+            .uri(uri)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let request = TestRequest::get()
+            .uri(&format!("{uri}/status"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_not_found_page() -> Result<(), Error> {
+        let test_upload_dir = "test_not_found_page_upload";
+        fs::create_dir(test_upload_dir)?;
+        let page = PathBuf::from(test_upload_dir).join("404.html");
+        fs::write(&page, "<h1>not found</h1>")?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.not_found_page = Some(page);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::get().uri("/missing.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_eq!(
+            Some("text/html"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "<h1>not found</h1>").await?;
+
+        let request = TestRequest::get().uri("/404.html").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_content_type() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_content_type_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "notes.unknown";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("some notes", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/plain; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+
+        for query in ["download", "download=true"] {
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}?{query}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some("application/octet-stream"),
+                response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert_eq!(
+                Some(format!("attachment; filename=\"{file_name}\"").as_str()),
+                response
+                    .headers()
+                    .get(header::CONTENT_DISPOSITION)
+                    .and_then(|v| v.to_str().ok())
+            );
+        }
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_head_file() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_head_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("head data", "file", "head.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = test::call_service(
+            &app,
+            get_multipart_request("oneshot data", "oneshot", "head_oneshot.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        for (file_name, size) in [("head.txt", 9), ("head_oneshot.txt", 12)] {
+            let request = TestRequest::default()
+                .method(Method::HEAD)
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(BodySize::Sized(size), response.response().body().size());
+            assert!(response.headers().contains_key(header::LAST_MODIFIED));
+            let body_bytes = actix_web::body::to_bytes(response.into_body()).await?;
+            assert!(body_bytes.is_empty());
+        }
+
+        let request = TestRequest::get().uri("/head_oneshot.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "oneshot data").await?;
+
+        for file_name in ["head_oneshot.txt", "missing.txt"] {
+            let request = TestRequest::default()
+                .method(Method::HEAD)
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::NOT_FOUND, response.status());
+        }
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_limits() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.max_content_length = Byte::from_u64(100);
+        config.paste.mime_whitelist = vec![String::from("text/*"), String::from("image/*")];
+        config.tokens = vec![crate::config::TokenConfig {
+            token: String::from("limited"),
+            max_content_length: Some(Byte::from_u64(5)),
+            allowed_extensions: Some(vec![String::from("txt"), String::from("md")]),
+            ..Default::default()
+        }];
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let get_header = |response: &actix_web::dev::ServiceResponse, name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let request = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NO_CONTENT, response.status());
+        assert_eq!(
+            Some(String::from("100")),
+            get_header(&response, crate::header::MAX_CONTENT_LENGTH)
+        );
+        assert_eq!(
+            None,
+            get_header(&response, crate::header::ALLOWED_EXTENSIONS)
+        );
+        assert_eq!(
+            Some(String::from("text/*,image/*")),
+            get_header(&response, crate::header::ALLOWED_MIME_TYPES)
+        );
+
+        let request = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .insert_header((AUTHORIZATION, "limited"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(
+            Some(String::from("5")),
+            get_header(&response, crate::header::MAX_CONTENT_LENGTH)
+        );
+        assert_eq!(
+            Some(String::from("txt,md")),
+            get_header(&response, crate::header::ALLOWED_EXTENSIONS)
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_token_policy() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_token_policy_upload");
+        config.server.max_content_length = Byte::from_u64(100);
+        config.tokens = vec![
+            crate::config::TokenConfig {
+                token: String::from("limited"),
+                max_content_length: Some(Byte::from_u64(5)),
+                allowed_extensions: Some(vec![String::from("txt")]),
+                allow_oneshot: Some(false),
+                allow_expiry: Some(false),
+                skip_virus_scan: None,
+            },
+            crate::config::TokenConfig {
+                token: String::from("default"),
+                ..Default::default()
+            },
+        ];
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let get_request = |token: &str, data: &str, name: &str, file_name: &str| {
+            get_multipart_request(data, name, file_name)
+                .insert_header((AUTHORIZATION, format!("Bearer {token}")))
+        };
+
+        let response = test::call_service(
+            &app,
+            get_request("unknown", "test", "file", "unknown.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_request("limited", "test", "file", "limited.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_request("limited", "too large", "file", "large.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_request("limited", "test", "file", "limited.png").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_request("limited", "test", "oneshot", "oneshot.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert_body(response.into_body(), "oneshot files are not allowed\n").await?;
+
+        let response = test::call_service(
+            &app,
+            get_request("limited", "test", "file", "expiring.txt")
+                .insert_header((crate::header::EXPIRE, "10min"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert_body(response.into_body(), "expiring files are not allowed\n").await?;
+
+        let response = test::call_service(
+            &app,
+            get_request("default", "large data", "oneshot", "default.png")
+                .insert_header((crate::header::EXPIRE, "10min"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_if_none_match() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_if_none_match");
+        config.paste.handle_conflicts = Some(ConflictHandlingConfig::Overwrite);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (data, status) in [
+            ("first", StatusCode::OK),
+            ("second", StatusCode::PRECONDITION_FAILED),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(data, "file", "file.txt")
+                    .insert_header((header::IF_NONE_MATCH, "*"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, response.status());
+        }
+        assert_eq!("first", fs::read_to_string(upload_path.join("file.txt"))?);
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("third", "file", "file.txt")
+                .insert_header((header::IF_NONE_MATCH, "\"etag\""))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("fourth", "file", "file.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("fourth", fs::read_to_string(upload_path.join("file.txt"))?);
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_same_name_metadata() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_same_name_metadata");
+        config.paste.handle_conflicts = Some(ConflictHandlingConfig::Overwrite);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        for (file_name, expire) in [("overwritten.txt", "1h"), ("expired.txt", "10ms")] {
+            let request = get_multipart_request("first", "file", file_name)
+                .insert_header((crate::header::PASSWORD, "hunter2"))
+                .insert_header((crate::header::EXPIRE, expire))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+        thread::sleep(Duration::from_millis(20));
+
+        for file_name in ["overwritten.txt", "expired.txt"] {
+            let request = get_multipart_request("second", "file", file_name).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "second").await?;
+            assert_eq!(
+                None,
+                Metadata::read(
+                    &storage::LocalStorage,
+                    &upload_path,
+                    &upload_path.join(file_name)
+                )?
+            );
+        }
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_default_type() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_default_type");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+        let config = Data::new(RwLock::new(config));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::clone(&config))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("data", "data", "data.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(
+            response.into_body(),
+            "invalid form field \"data\", expected one of: \
+             file, oneshot, url, oneshot_url, remote, append, expire, filename\n",
+        )
+        .await?;
+
+        config
+            .write()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire config"))?
+            .paste
+            .default_type = Some(PasteType::Oneshot);
+        let response = test::call_service(
+            &app,
+            get_multipart_request("data", "data", "data.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/data.txt\n").await?;
+        assert_eq!(
+            "data",
+            fs::read_to_string(upload_path.join("oneshot").join("data.txt"))?
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_shorten_urls() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_shorten_urls");
+        config.paste.max_batch_size = Some(3);
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(8),
+            ..RandomURLConfig::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let request = TestRequest::post()
+            .uri("/urls")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(r#"["https://orhun.dev", "ftp://example.com", "https://example.com"]"#)
+            .to_request();
+        let results: Vec<ShortenedUrl> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(3, results.len());
+        assert_eq!("https://orhun.dev", results[0].url);
+        assert!(results[0].error.is_none());
+        let short = results[0].short.as_deref().unwrap_or_default();
+        assert!(short.starts_with("http://localhost:8080/"));
+        assert!(results[1].short.is_none());
+        assert_eq!(
+            Some("URL scheme is not permitted: ftp"),
+            results[1].error.as_deref()
+        );
+        assert!(results[2].short.is_some());
+
+        let request = TestRequest::get()
+            .uri(short.trim_start_matches("http://localhost:8080"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::FOUND, response.status());
+
+        for (payload, status) in [
+            ("[]", StatusCode::BAD_REQUEST),
+            ("{}", StatusCode::BAD_REQUEST),
+            (r#"["a", "b", "c", "d"]"#, StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let request = TestRequest::post()
+                .uri("/urls")
+                .insert_header((header::CONTENT_TYPE, "application/json"))
+                .set_payload(payload)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_checksum() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_checksum");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let checksum = util::sha256_digest("checksum".as_bytes())?;
+        let response = test::call_service(
+            &app,
+            get_multipart_request("checksum", "file", "checksum.txt")
+                .insert_header((crate::header::CONTENT_SHA256, checksum.to_uppercase()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/checksum.txt\n").await?;
+        assert_eq!(
+            "checksum",
+            fs::read_to_string(upload_path.join("checksum.txt"))?
+        );
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("corrupted", "file", "corrupted.txt")
+                .insert_header((crate::header::CONTENT_SHA256, checksum))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+        assert_body(response.into_body(), "SHA256 checksum mismatch\n").await?;
+        assert!(!upload_path.join("corrupted.txt").exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("checksum", "file", "invalid.txt")
+                .insert_header((crate::header::CONTENT_SHA256, "abc"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(response.into_body(), "invalid SHA256 checksum\n").await?;
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_dry_run() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_dry_run_upload");
+        config.paste.allowed_extensions = vec![String::from("txt")];
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("dry run", "file", "dry_run.txt")
+                .insert_header((crate::header::DRY_RUN, "true"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/dry_run.txt\n").await?;
+        assert!(!upload_path.join("dry_run.txt").exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("https://orhun.dev", "url", "")
+                .insert_header((crate::header::DRY_RUN, "true"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!upload_path.join("url").join("url").exists());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("dry run", "file", "dry_run.exe")
+                .insert_header((crate::header::DRY_RUN, "true"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("dry run", "file", "dry_run.txt")
+                .insert_header((crate::header::DRY_RUN, "maybe"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(response.into_body(), "invalid dry run value\n").await?;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("dry run", "file", "dry_run.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = test::call_service(
+            &app,
+            get_multipart_request("more data", "file", "dry_run.txt")
+                .insert_header((crate::header::DRY_RUN, "true"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        assert_eq!(
+            "dry run",
+            fs::read_to_string(upload_path.join("dry_run.txt"))?
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_multiple_files() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_multiple_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let get_request = |files: &[(&str, &str)]| {
+            let multipart_data = files
+                .iter()
+                .map(|(file_name, data)| {
+                    format!(
+                        "--multipart_bound\r\n\
+                         Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+                         Content-Type: text/plain\r\n\r\n\
+                         {data}\r\n"
+                    )
+                })
+                .collect::<String>()
+                + "--multipart_bound--\r\n";
+            TestRequest::post()
+                .insert_header((
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static(
+                        "multipart/mixed; boundary=\"multipart_bound\"",
+                    ),
+                ))
+                .set_payload(multipart_data)
+                .to_request()
+        };
+
+        let response = test::call_service(
+            &app,
+            get_request(&[("first.txt", "first"), ("second.txt", "second")]),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "http://localhost:8080/first.txt\nhttp://localhost:8080/second.txt\n",
+        )
+        .await?;
+        assert_eq!("first", fs::read_to_string(upload_path.join("first.txt"))?);
+        assert_eq!(
+            "second",
+            fs::read_to_string(upload_path.join("second.txt"))?
+        );
+
+        let response = test::call_service(
+            &app,
+            get_request(&[
+                ("third.txt", "third"),
+                ("empty.txt", ""),
+                ("fourth.txt", "x"),
+            ]),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(
+            response.into_body(),
+            "file #2 (empty.txt): invalid file size\n",
+        )
+        .await?;
+        assert!(upload_path.join("third.txt").exists());
+        assert!(!upload_path.join("fourth.txt").exists());
+
+        let response = test::call_service(&app, get_request(&[("empty.txt", "")])).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_body(response.into_body(), "invalid file size").await?;
+
+        config.paste.allow_empty = Some(true);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+        let response = test::call_service(&app, get_request(&[("empty.txt", "")])).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(0, fs::metadata(upload_path.join("empty.txt"))?.len());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_append() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?.join("test_append_upload");
+        config.server.max_content_length = Byte::from_u64(10);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_append.log";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("first", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("second", "append", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("+new", "append", file_name)
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+        assert_eq!(
+            "first+new",
+            fs::read_to_string(upload_path.join(file_name))?
+        );
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("+more", "append", file_name)
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+        for name in ["missing.log", "url/test"] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request("data", "append", name)
+                    .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                    .to_request(),
+            )
+            .await;
+            assert!(response.status().is_client_error());
+        }
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_append_content_address() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?.join("test_append_content_address");
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Hash,
+            ..RandomURLConfig::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("original", "file", "original.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = actix_web::body::to_bytes(response.into_body()).await?;
+        let url = str::from_utf8(&body)?.trim();
+        let file_name = url.rsplit('/').next().unwrap_or_default();
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("+more", "append", file_name)
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        assert_eq!("original", fs::read_to_string(upload_path.join(file_name))?);
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_virus_scan() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_virus_scan");
+        config.security = Some(crate::config::SecurityConfig {
+            clamav: Some(crate::clamav::tests::spawn_daemon()?),
+            ..Default::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("harmless", "file", "clean.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(upload_path.join("clean.txt").exists());
 
-    fn get_multipart_request(data: &str, name: &str, filename: &str) -> TestRequest {
-        let multipart_data = format!(
-            "\r\n\
-             --multipart_bound\r\n\
-             Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
-             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n\
-             {}\r\n\
-             --multipart_bound--\r\n",
-            name,
-            filename,
-            data.bytes().len(),
-            data,
-        );
-        TestRequest::post()
-            .insert_header((
-                header::CONTENT_TYPE,
-                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
-            ))
-            .insert_header((
-                header::CONTENT_LENGTH,
-                header::HeaderValue::from_str(&data.bytes().len().to_string())
-                    .expect("cannot create header value"),
-            ))
-            .set_payload(multipart_data)
+        let response = test::call_service(
+            &app,
+            get_multipart_request("virus", "oneshot", "infected.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+        assert!(!PasteType::Oneshot
+            .get_path(&upload_path)?
+            .join("infected.txt")
+            .exists());
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
     }
 
-    async fn assert_body(body: BoxBody, expected: &str) -> Result<(), Error> {
-        if let BodySize::Sized(size) = body.size() {
-            assert_eq!(size, expected.as_bytes().len() as u64);
-            let body_bytes = actix_web::body::to_bytes(body).await?;
-            let body_text = str::from_utf8(&body_bytes)?;
-            assert_eq!(expected, body_text);
-            Ok(())
-        } else {
-            Err(error::ErrorInternalServerError("unexpected body type"))
-        }
+    #[actix_web::test]
+    async fn test_upload_append_virus_scan() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.delete_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?.join("test_append_virus_scan");
+        config.security = Some(crate::config::SecurityConfig {
+            clamav: Some(crate::clamav::tests::spawn_daemon()?),
+            ..Default::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_append_virus.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("clean", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("virus", "append", file_name)
+                .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+        assert_eq!("clean", fs::read_to_string(upload_path.join(file_name))?);
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index() {
-        let config = Config::default();
+    async fn test_upload_file_metadata() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_metadata_server_upload");
+        config.paste.store_metadata = Some(true);
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
+
+        let file_name = "test_metadata.txt";
+        let response = test::call_service(
+            &app,
+            get_multipart_request("metadata", "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let file_path = upload_path.join(file_name);
+        let mut metadata = Metadata::read(&storage::LocalStorage, &upload_path, &file_path)?
+            .ok_or_else(|| error::ErrorInternalServerError("metadata is not found"))?;
+        assert_eq!(Some(file_name), metadata.original_name.as_deref());
+        assert_eq!(Some("text/plain"), metadata.content_type.as_deref());
+        assert_eq!(8, metadata.size);
+        assert_eq!(None, metadata.expires_at);
+        assert!(!metadata.oneshot);
+
+        metadata.content_type = Some(String::from("text/x-metadata"));
+        metadata.write(&storage::LocalStorage, &upload_path, &file_path)?;
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
             .to_request();
-        let response = test::call_service(&app, request).await;
-        assert_eq!(StatusCode::FOUND, response.status());
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/x-metadata"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "metadata").await?;
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_with_landing_page() -> Result<(), Error> {
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                text: Some(String::from("landing page")),
+    async fn test_serve_conditional() -> Result<(), Error> {
+        let test_upload_dir = "test_conditional_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        for compression in [CompressionType::None, CompressionType::Gzip] {
+            let mut config = Config::default();
+            config.server.upload_path = PathBuf::from(test_upload_dir);
+            config.storage = Some(StorageConfig {
+                compression,
                 ..Default::default()
-            }),
-            ..Default::default()
-        };
+            });
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::new(RwLock::new(config)))
+                    .app_data(Data::new(Client::default()))
+                    .configure(configure_routes),
+            )
+            .await;
+
+            let file_name = format!("{}.txt", compression.as_str());
+            let response = test::call_service(
+                &app,
+                get_multipart_request("conditional", "file", &file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let uri = format!("/{file_name}");
+            let response =
+                test::call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(StatusCode::OK, response.status());
+            let etag = response
+                .headers()
+                .get(header::ETAG)
+                .cloned()
+                .ok_or_else(|| error::ErrorInternalServerError("ETag is not found"))?;
+            let last_modified = response
+                .headers()
+                .get(header::LAST_MODIFIED)
+                .cloned()
+                .ok_or_else(|| error::ErrorInternalServerError("Last-Modified is not found"))?;
+
+            let request = TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::IF_NONE_MATCH, etag))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+            let request = TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::IF_MODIFIED_SINCE, last_modified))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+            let request = TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::IF_NONE_MATCH, "\"other\""))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "conditional").await?;
+        }
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        fs::create_dir_all(PasteType::Oneshot.get_path(&config.server.upload_path)?)?;
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
+        let response = test::call_service(
+            &app,
+            get_multipart_request("oneshot", "oneshot", "oneshot.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let request = TestRequest::get()
+            .uri("/oneshot.txt")
+            .insert_header((header::IF_NONE_MATCH, "*"))
             .to_request();
         let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "landing page").await?;
+        assert!(response.headers().get(header::ETAG).is_none());
+        assert_body(response.into_body(), "oneshot").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_with_landing_page_file() -> Result<(), Error> {
-        let filename = "landing_page.txt";
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                file: Some(filename.to_string()),
+    async fn test_serve_range() -> Result<(), Error> {
+        let test_upload_dir = "test_range_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        for compression in [CompressionType::None, CompressionType::Gzip] {
+            let mut config = Config::default();
+            config.server.upload_path = PathBuf::from(test_upload_dir);
+            config.storage = Some(StorageConfig {
+                compression,
                 ..Default::default()
-            }),
-            ..Default::default()
-        };
-        let mut file = File::create(filename)?;
-        file.write_all("landing page from file".as_bytes())?;
+            });
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::new(RwLock::new(config)))
+                    .app_data(Data::new(Client::default()))
+                    .configure(configure_routes),
+            )
+            .await;
+
+            let file_name = format!("{}.txt", compression.as_str());
+            let response = test::call_service(
+                &app,
+                get_multipart_request("0123456789", "file", &file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let uri = format!("/{file_name}");
+            let response =
+                test::call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some("bytes"),
+                response
+                    .headers()
+                    .get(header::ACCEPT_RANGES)
+                    .and_then(|v| v.to_str().ok())
+            );
+            assert_body(response.into_body(), "0123456789").await?;
+
+            for (range, content_range, body) in [
+                ("bytes=2-5", "bytes 2-5/10", "2345"),
+                ("bytes=7-", "bytes 7-9/10", "789"),
+                ("bytes=-3", "bytes 7-9/10", "789"),
+            ] {
+                let request = TestRequest::get()
+                    .uri(&uri)
+                    .insert_header((header::RANGE, range))
+                    .insert_header((header::ACCEPT_ENCODING, "gzip"))
+                    .to_request();
+                let response = test::call_service(&app, request).await;
+                assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+                assert_eq!(
+                    Some(content_range),
+                    response
+                        .headers()
+                        .get(header::CONTENT_RANGE)
+                        .and_then(|v| v.to_str().ok())
+                );
+                assert_ne!(
+                    Some("gzip"),
+                    response
+                        .headers()
+                        .get(header::CONTENT_ENCODING)
+                        .and_then(|v| v.to_str().ok())
+                );
+                assert_body(response.into_body(), body).await?;
+            }
+
+            let request = TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::RANGE, "bytes=20-30"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::RANGE_NOT_SATISFIABLE, response.status());
+        }
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_alias() -> Result<(), Error> {
+        let test_upload_dir = "test_alias_server_upload";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        fs::create_dir_all(PasteType::Oneshot.get_path(&config.server.upload_path)?)?;
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
+
+        for (data, name, file_name) in [
+            ("alias", "file", "file.txt"),
+            ("other", "file", "other.txt"),
+            ("oneshot", "oneshot", "oneshot.txt"),
+        ] {
+            let response = test::call_service(
+                &app,
+                get_multipart_request(data, name, file_name).to_request(),
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        for (uri, alias, status) in [
+            ("/file.txt/alias", "friendly.txt", StatusCode::OK),
+            ("/file.txt/alias", "friendly.txt", StatusCode::CONFLICT),
+            ("/file.txt/alias", "other.txt", StatusCode::CONFLICT),
+            ("/file.txt/alias", "..", StatusCode::BAD_REQUEST),
+            (
+                "/missing.txt/alias",
+                "missing-alias.txt",
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                "/oneshot.txt/alias",
+                "oneshot-alias.txt",
+                StatusCode::BAD_REQUEST,
+            ),
+            ("/friendly.txt/alias", "chained.txt", StatusCode::OK),
+        ] {
+            let request = TestRequest::post().uri(uri).set_payload(alias).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status(), "{uri} -> {alias}");
+        }
+
+        for uri in ["/friendly.txt", "/chained.txt"] {
+            let response = test::call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "alias").await?;
+        }
+        assert_eq!(
+            "file.txt",
+            fs::read_to_string(
+                PathBuf::from(test_upload_dir)
+                    .join(crate::alias::ALIAS_DIR)
+                    .join("chained.txt")
+            )?
+        );
+
+        fs::remove_dir_all(test_upload_dir)?;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_serve_original_name() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_original_name_upload");
+        config.paste.preserve_original_name = Some(true);
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            length: Some(8),
+            ..RandomURLConfig::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("original", "file", "original.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = actix_web::body::to_bytes(response.into_body()).await?;
+        let file_name = str::from_utf8(&body)?
+            .trim()
+            .trim_start_matches("http://localhost:8080/")
+            .to_string();
+        assert_ne!("original.txt", file_name);
+
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
             .to_request();
         let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "landing page from file").await?;
-        fs::remove_file(filename)?;
+        assert_eq!(
+            Some("original.txt"),
+            response
+                .headers()
+                .get(crate::header::ORIGINAL_FILENAME)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!(
+            Some("inline; filename=\"original.txt\""),
+            response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_body(response.into_body(), "original").await?;
+
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?download"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(
+            Some("attachment; filename=\"original.txt\""),
+            response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        fs::remove_dir_all(upload_path)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_with_landing_page_file_not_found() -> Result<(), Error> {
-        let filename = "landing_page.txt";
-        let config = Config {
-            landing_page: Some(LandingPageConfig {
-                text: Some(String::from("landing page")),
-                file: Some(filename.to_string()),
-                ..Default::default()
-            }),
-            ..Default::default()
-        };
+    async fn test_thumbnail() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_thumbnail_server_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let mut data = Vec::new();
+        image::DynamicImage::new_rgb8(300, 150)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(error::ErrorInternalServerError)?;
+        fs::write(upload_path.join("image.png"), data)?;
+        fs::write(upload_path.join("text.txt"), "text")?;
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
-                .service(index),
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
         )
         .await;
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
+
+        let request = TestRequest::get()
+            .uri("/image.png/thumbnail?w=100")
             .to_request();
         let response = test::call_service(&app, request).await;
-        assert_eq!(StatusCode::FOUND, response.status());
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("image/png"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = actix_web::body::to_bytes(response.into_body()).await?;
+        let thumbnail = image::load_from_memory(&body).map_err(error::ErrorInternalServerError)?;
+        assert_eq!((100, 50), (thumbnail.width(), thumbnail.height()));
+        assert!(upload_path
+            .join(crate::thumbnail::THUMBNAIL_DIR)
+            .join("image.png.100.png")
+            .is_file());
+
+        let request = TestRequest::get()
+            .uri("/image.png/thumbnail?w=150")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!upload_path
+            .join(crate::thumbnail::THUMBNAIL_DIR)
+            .join("image.png.150.png")
+            .exists());
+
+        for (uri, status) in [
+            ("/image.png/thumbnail", StatusCode::OK),
+            ("/image.png/thumbnail?w=0", StatusCode::BAD_REQUEST),
+            ("/text.txt/thumbnail", StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            ("/missing.png/thumbnail", StatusCode::NOT_FOUND),
+        ] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
+
+        fs::remove_dir_all(upload_path)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version_without_auth() -> Result<(), Error> {
+    async fn test_upload_file_compressed() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+        config.storage = Some(StorageConfig {
+            compression: CompressionType::Gzip,
+            ..Default::default()
+        });
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
@@ -564,40 +6117,155 @@ mod tests {
         )
         .await;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
+        let file_name = "test_file_compressed.txt";
+        let data = "compressed\n".repeat(100);
+        let response = test::call_service(
+            &app,
+            get_multipart_request(&data, "file", file_name).to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(fs::metadata(file_name)?.len() < data.len() as u64);
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
             .to_request();
-        let response = test::call_service(&app, request).await;
-        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
-        assert_body(response.into_body(), "unauthorized\n").await?;
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_body(response.into_body(), &data).await?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .insert_header((header::ACCEPT_ENCODING, "gzip, deflate"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("gzip"),
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = actix_web::body::to_bytes(response.into_body()).await?;
+        assert_eq!(
+            data.as_bytes(),
+            storage::decompress(CompressionType::Gzip, &body)?
+        );
+
+        fs::remove_file(file_name)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version_without_config() -> Result<(), Error> {
+    async fn test_upload_file_with_password() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_password_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        fs::create_dir_all(PasteType::Oneshot.get_path(&config.server.upload_path)?)?;
+        let upload_path = config.server.upload_path.clone();
+
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(RwLock::new(config)))
                 .app_data(Data::new(Client::default()))
                 .configure(configure_routes),
         )
         .await;
 
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
+        for (field, file_name) in [
+            ("file", "protected.txt"),
+            ("oneshot", "protected_oneshot.txt"),
+        ] {
+            let request = get_multipart_request("secret data", field, file_name)
+                .insert_header((crate::header::PASSWORD, "hunter2"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+            assert_body(response.into_body(), "password is required\n").await?;
+
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}?password=wrong"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+            assert_body(response.into_body(), "invalid password\n").await?;
+
+            let request = TestRequest::get()
+                .uri(&format!("/{file_name}"))
+                .insert_header((crate::header::PASSWORD, "hunter2"))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(response.into_body(), "secret data").await?;
+        }
+
+        let request = TestRequest::get()
+            .uri("/protected.txt?password=hunter2")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get()
+            .uri("/protected_oneshot.txt?password=hunter2")
             .to_request();
         let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::NOT_FOUND, response.status());
-        assert_body(response.into_body(), "").await?;
+
+        let request = get_multipart_request("secret data", "file", "empty_password.txt")
+            .insert_header((crate::header::PASSWORD, ""))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        // The file is not stored without its password.
+        fs::create_dir_all(
+            upload_path
+                .join(crate::metadata::METADATA_DIR)
+                .join("unprotected.txt.json"),
+        )?;
+        let request = get_multipart_request("secret data", "file", "unprotected.txt")
+            .insert_header((crate::header::PASSWORD, "hunter2"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let request = TestRequest::get().uri("/unprotected.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert!(!upload_path.join("unprotected.txt").exists());
+
+        fs::remove_dir_all(upload_path)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_version() -> Result<(), Error> {
+    async fn test_upload_private_file() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.expose_version = Some(true);
+        config.server.upload_path = env::current_dir()?.join("test_private_upload");
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config.clone())))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+        let request = get_multipart_request("private data", "file", "private.txt")
+            .insert_header((crate::header::PRIVATE, "true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        config.server.auth_tokens = Some(["test".to_string()].into());
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
@@ -605,29 +6273,85 @@ mod tests {
                 .configure(configure_routes),
         )
         .await;
+        for (file_name, private) in [("private.txt", "true"), ("public.txt", "false")] {
+            let request = get_multipart_request("test data", "file", file_name)
+                .insert_header((AUTHORIZATION, "test"))
+                .insert_header((crate::header::PRIVATE, private))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        let request = TestRequest::get().uri("/public.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let request = TestRequest::get().uri("/private.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .uri("/private.txt")
+            .insert_header((AUTHORIZATION, "invalid"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
 
         let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/version")
+            .method(Method::HEAD)
+            .uri("/private.txt")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let request = TestRequest::get()
+            .uri("/private.txt")
+            .insert_header((AUTHORIZATION, "test"))
             .to_request();
         let response = test::call_service(&app, request).await;
         assert_eq!(StatusCode::OK, response.status());
-        assert_body(
-            response.into_body(),
-            &(env!("CARGO_PKG_VERSION").to_owned() + "\n"),
-        )
-        .await?;
+        assert_body(response.into_body(), "test data").await?;
+
+        let request = get_multipart_request("https://example.com", "url", "")
+            .insert_header((AUTHORIZATION, "test"))
+            .insert_header((crate::header::PRIVATE, "true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let request = TestRequest::get().uri("/url").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        // The file is not stored without its metadata.
+        fs::create_dir_all(
+            upload_path
+                .join(crate::metadata::METADATA_DIR)
+                .join("failed.txt.json"),
+        )?;
+        let request = get_multipart_request("test data", "file", "failed.txt")
+            .insert_header((AUTHORIZATION, "test"))
+            .insert_header((crate::header::PRIVATE, "true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let request = TestRequest::get().uri("/failed.txt").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        fs::remove_dir_all(upload_path)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_list() -> Result<(), Error> {
+    async fn test_upload_file_url_length() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.expose_list = Some(true);
-
-        let test_upload_dir = "test_upload";
-        fs::create_dir(test_upload_dir)?;
-        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.upload_path = env::current_dir()?;
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            min_length: Some(4),
+            max_length: Some(10),
+            ..RandomURLConfig::default()
+        });
 
         let app = test::init_service(
             App::new()
@@ -637,39 +6361,38 @@ mod tests {
         )
         .await;
 
-        let filename = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        test::call_service(
-            &app,
-            get_multipart_request(&timestamp, "file", filename).to_request(),
-        )
-        .await;
-
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/list")
-            .to_request();
-        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(
-            result.first().expect("json object").file_name,
-            PathBuf::from(filename)
-        );
+        for (url_length, file_name_length) in [("6", 6), ("50", 10)] {
+            let request = get_multipart_request("url length", "file", "test_url_length.txt")
+                .insert_header((crate::header::URL_LENGTH, url_length))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            let body = actix_web::body::to_bytes(response.into_body()).await?;
+            let file_name = str::from_utf8(&body)?
+                .trim()
+                .trim_start_matches("http://localhost:8080/")
+                .to_string();
+            assert_eq!(file_name_length + ".txt".len(), file_name.len());
+            fs::remove_file(file_name)?;
+        }
 
-        fs::remove_dir_all(test_upload_dir)?;
+        for url_length in ["2", "invalid"] {
+            let request = get_multipart_request("url length", "file", "test_url_length.txt")
+                .insert_header((crate::header::URL_LENGTH, url_length))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        }
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_list_expired() -> Result<(), Error> {
+    async fn test_upload_response_template() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.expose_list = Some(true);
-
-        let test_upload_dir = "test_upload";
-        fs::create_dir(test_upload_dir)?;
-        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.server.upload_path = env::current_dir()?.join("test_upload_response_template");
+        config.server.url = Some(String::from("https://example.com"));
+        config.server.response_template = Some(String::from("{filename} ({size} bytes): {url}"));
 
         let app = test::init_service(
             App::new()
@@ -679,38 +6402,28 @@ mod tests {
         )
         .await;
 
-        let filename = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
-        test::call_service(
+        let file_name = "test_file.txt";
+        let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", filename)
-                .insert_header((
-                    header::HeaderName::from_static("expire"),
-                    header::HeaderValue::from_static("50ms"),
-                ))
-                .to_request(),
+            get_multipart_request("test data", "file", file_name).to_request(),
         )
         .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("{file_name} (9 bytes): https://example.com/{file_name}\n"),
+        )
+        .await?;
 
-        thread::sleep(Duration::from_millis(500));
-
-        let request = TestRequest::default()
-            .insert_header(("content-type", "text/plain"))
-            .uri("/list")
-            .to_request();
-        let result: Vec<ListItem> = test::call_and_read_body_json(&app, request).await;
-
-        assert!(result.is_empty());
-
-        fs::remove_dir_all(test_upload_dir)?;
-
+        fs::remove_dir_all(env::current_dir()?.join("test_upload_response_template"))?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_auth() -> Result<(), Error> {
+    async fn test_upload_file_json() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.auth_tokens = Some(["test".to_string()].into());
+        config.server.upload_path = env::current_dir()?;
+        config.server.url = Some(String::from("https://example.com"));
 
         let app = test::init_service(
             App::new()
@@ -720,41 +6433,140 @@ mod tests {
         )
         .await;
 
-        let response =
-            test::call_service(&app, get_multipart_request("", "", "").to_request()).await;
-        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
-        assert_body(response.into_body(), "unauthorized\n").await?;
+        let file_name = "test_file_json.txt";
+        let request = get_multipart_request("test data", "file", file_name)
+            .insert_header((header::ACCEPT, "text/html, application/json;q=0.9"))
+            .insert_header((
+                header::HeaderName::from_static("expire"),
+                header::HeaderValue::from_static("1h"),
+            ))
+            .to_request();
+        let result: Vec<UploadItem> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(1, result.len());
+        let item = result.first().expect("json object");
+        assert_eq!(file_name, item.file_name);
+        assert_eq!(format!("https://example.com/{file_name}"), item.url);
+        assert_eq!(9, item.file_size);
+        assert!(item.expires_at_utc.is_some());
+        assert!(item.delete_token.is_none());
 
+        fs::remove_file(util::glob_match_file(PathBuf::from(file_name))?)?;
         Ok(())
     }
 
+    #[test]
+    fn test_get_server_url() {
+        let request = TestRequest::default().to_http_request();
+        let mut config = Config::default();
+        assert_eq!("http://localhost:8080", get_server_url(&request, &config));
+        for url in ["https://example.com", "https://example.com/"] {
+            config.server.url = Some(String::from(url));
+            assert_eq!("https://example.com", get_server_url(&request, &config));
+        }
+    }
+
     #[actix_web::test]
-    async fn test_payload_limit() -> Result<(), Error> {
+    async fn test_qr_code() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+        config.server.auth_tokens = Some(["test".to_string()].into());
+
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(RwLock::new(Config::default())))
+                .app_data(Data::new(RwLock::new(config)))
                 .app_data(Data::new(Client::default()))
-                .wrap(ContentLengthLimiter::new(Byte::from_u64(1)))
                 .configure(configure_routes),
         )
         .await;
 
-        let response = test::call_service(
+        let file_name = "test_qr_code.txt";
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}/qr"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        test::call_service(
             &app,
-            get_multipart_request("test", "file", "test").to_request(),
+            get_multipart_request("test data", "file", file_name)
+                .insert_header((AUTHORIZATION, "test"))
+                .to_request(),
         )
         .await;
-        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
-        assert_body(response.into_body().boxed(), "upload limit exceeded").await?;
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}/qr"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("image/png"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = response.into_body().try_into_bytes().unwrap_or_default();
+        assert!(body.starts_with(b"\x89PNG"));
+
+        let file_name = "test_qr_code_protected.txt";
+        let request = get_multipart_request("secret data", "file", file_name)
+            .insert_header((AUTHORIZATION, "test"))
+            .insert_header((crate::header::PASSWORD, "hunter2"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        for (uri, status) in [
+            (format!("/{file_name}/qr"), StatusCode::UNAUTHORIZED),
+            (format!("/{file_name}/qr?password=invalid"), StatusCode::UNAUTHORIZED),
+            (format!("/{file_name}/qr?password=hunter2"), StatusCode::OK),
+        ] {
+            let request = TestRequest::get().uri(&uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
+        fs::remove_file(file_name)?;
+        let upload_path = env::current_dir()?;
+        Metadata::delete(
+            &storage::LocalStorage,
+            &upload_path,
+            &upload_path.join(file_name),
+        )?;
+
+        let file_name = "test_qr_code_private.txt";
+        let request = get_multipart_request("private data", "file", file_name)
+            .insert_header((AUTHORIZATION, "test"))
+            .insert_header((crate::header::PRIVATE, "true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}/qr"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}/qr"))
+            .insert_header((AUTHORIZATION, "test"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        fs::remove_file(file_name)?;
+        let upload_path = env::current_dir()?;
+        Metadata::delete(
+            &storage::LocalStorage,
+            &upload_path,
+            &upload_path.join(file_name),
+        )?;
 
+        fs::remove_file("test_qr_code.txt")?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_delete_file() -> Result<(), Error> {
+    async fn test_serve_highlighted_file() -> Result<(), Error> {
         let mut config = Config::default();
-        config.server.delete_tokens = Some(["test".to_string()].into());
         config.server.upload_path = env::current_dir()?;
+        config.paste.max_highlight_size = Some(Byte::from_u64(20));
 
         let app = test::init_service(
             App::new()
@@ -764,34 +6576,105 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let file_name = "test_highlight.rs";
+        test::call_service(
+            &app,
+            get_multipart_request("fn main() {}", "file", file_name).to_request(),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?highlight=true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+        );
+        let body = response.into_body().try_into_bytes().unwrap_or_default();
+        assert!(str::from_utf8(&body)
+            .map_err(error::ErrorInternalServerError)?
+            .contains("<pre"));
+        fs::remove_file(file_name)?;
+
+        let file_name = "test_highlight_password.rs";
+        test::call_service(
+            &app,
+            get_multipart_request("fn main() {}", "file", file_name)
+                .insert_header((crate::header::PASSWORD, "hunter2"))
+                .to_request(),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?highlight&password=hunter2"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = response.into_body().try_into_bytes().unwrap_or_default();
+        assert!(str::from_utf8(&body)
+            .map_err(error::ErrorInternalServerError)?
+            .contains("<pre"));
+        let upload_path = env::current_dir()?;
+        Metadata::delete(
+            &storage::LocalStorage,
+            &upload_path,
+            &upload_path.join(file_name),
+        )?;
+        fs::remove_file(file_name)?;
+
+        let file_name = "test_highlight_large.txt";
         test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name).to_request(),
+            get_multipart_request(&"x".repeat(21), "file", file_name).to_request(),
         )
         .await;
-
-        let request = TestRequest::delete()
-            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
-            .uri(&format!("/{file_name}"))
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?highlight=true"))
             .to_request();
         let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        fs::remove_file(file_name)?;
 
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), "file deleted\n").await?;
-
-        let path = PathBuf::from(file_name);
-        assert!(!path.exists());
+        let file_name = "test_highlight_binary.txt";
+        test::call_service(
+            &app,
+            get_multipart_request("\0\0", "file", file_name).to_request(),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri(&format!("/{file_name}?highlight=true"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert_body(response.into_body(), "binary files cannot be highlighted\n").await?;
+        fs::remove_file(file_name)?;
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_delete_file_without_token_in_config() -> Result<(), Error> {
-        let mut config = Config::default();
-        config.server.upload_path = env::current_dir()?;
+    async fn test_serve_rendered_markdown() -> Result<(), Error> {
+        let test_upload_dir = "test_render_upload";
+        fs::create_dir(test_upload_dir)?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("notes.md"),
+            "# Notes\n\n<script>alert(1)</script>\n",
+        )?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("large.md"),
+            "x".repeat(41),
+        )?;
+        fs::write(
+            PathBuf::from(test_upload_dir).join("main.rs"),
+            "fn main() {}",
+        )?;
 
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(test_upload_dir);
+        config.paste.max_render_size = Some(Byte::from_u64(40));
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(RwLock::new(config)))
@@ -800,21 +6683,48 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let request = TestRequest::delete()
-            .insert_header((AUTHORIZATION, header::HeaderValue::from_static("test")))
-            .uri(&format!("/{file_name}"))
-            .to_request();
+        for uri in ["/notes.md?render", "/notes.md.html"] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(
+                Some("text/html; charset=utf-8"),
+                response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+            );
+            let body = response.into_body().try_into_bytes().unwrap_or_default();
+            let body = str::from_utf8(&body).map_err(error::ErrorInternalServerError)?;
+            assert!(body.contains("<h1>Notes</h1>"));
+            assert!(!body.contains("<script>"));
+        }
+
+        let request = TestRequest::get().uri("/notes.md").to_request();
         let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            "# Notes\n\n<script>alert(1)</script>\n",
+        )
+        .await?;
 
-        assert_eq!(StatusCode::NOT_FOUND, response.status());
-        assert_body(response.into_body(), "").await?;
+        for (uri, status) in [
+            ("/large.md?render", StatusCode::PAYLOAD_TOO_LARGE),
+            ("/main.rs?render", StatusCode::BAD_REQUEST),
+            ("/main.rs.html", StatusCode::NOT_FOUND),
+        ] {
+            let request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(status, response.status());
+        }
 
+        fs::remove_dir_all(test_upload_dir)?;
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_upload_file() -> Result<(), Error> {
+    async fn test_upload_file_override_filename() -> Result<(), Error> {
         let mut config = Config::default();
         config.server.upload_path = env::current_dir()?;
 
@@ -827,29 +6737,35 @@ mod tests {
         .await;
 
         let file_name = "test_file.txt";
+        let header_filename = "fn_from_header.txt";
         let timestamp = util::get_system_time()?.as_secs().to_string();
         let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name).to_request(),
+            get_multipart_request(&timestamp, "file", file_name)
+                .insert_header((
+                    header::HeaderName::from_static("filename"),
+                    header::HeaderValue::from_static("fn_from_header.txt"),
+                ))
+                .to_request(),
         )
         .await;
         assert_eq!(StatusCode::OK, response.status());
         assert_body(
             response.into_body(),
-            &format!("http://localhost:8080/{file_name}\n"),
+            &format!("http://localhost:8080/{header_filename}\n"),
         )
         .await?;
 
         let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
+            .uri(&format!("/{header_filename}"))
             .to_request();
         let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::OK, response.status());
         assert_body(response.into_body(), &timestamp).await?;
 
-        fs::remove_file(file_name)?;
+        fs::remove_file(header_filename)?;
         let serve_request = TestRequest::get()
-            .uri(&format!("/{file_name}"))
+            .uri(&format!("/{header_filename}"))
             .to_request();
         let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::NOT_FOUND, response.status());
@@ -858,9 +6774,13 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_upload_file_override_filename() -> Result<(), Error> {
+    async fn test_upload_file_override_filename_form_field() -> Result<(), Error> {
         let mut config = Config::default();
         config.server.upload_path = env::current_dir()?;
+        config.paste.random_url = Some(RandomURLConfig {
+            type_: RandomURLType::Alphanumeric,
+            ..Default::default()
+        });
 
         let app = test::init_service(
             App::new()
@@ -870,39 +6790,45 @@ mod tests {
         )
         .await;
 
-        let file_name = "test_file.txt";
-        let header_filename = "fn_from_header.txt";
-        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let file_name = "fn_from_form_field.txt";
+        let multipart_data = format!(
+            "\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"filename\"\r\n\r\n\
+             {file_name}\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"test.txt\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\r\n\
+             test data\r\n\
+             --multipart_bound--\r\n"
+        );
+        let request = TestRequest::post()
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .set_payload(multipart_data)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+        fs::remove_file(file_name)?;
+
         let response = test::call_service(
             &app,
-            get_multipart_request(&timestamp, "file", file_name)
+            get_multipart_request("test data", "file", "test.txt")
                 .insert_header((
                     header::HeaderName::from_static("filename"),
-                    header::HeaderValue::from_static("fn_from_header.txt"),
+                    header::HeaderValue::from_static("../fn_from_header.txt"),
                 ))
                 .to_request(),
         )
         .await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(
-            response.into_body(),
-            &format!("http://localhost:8080/{header_filename}\n"),
-        )
-        .await?;
-
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{header_filename}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::OK, response.status());
-        assert_body(response.into_body(), &timestamp).await?;
-
-        fs::remove_file(header_filename)?;
-        let serve_request = TestRequest::get()
-            .uri(&format!("/{header_filename}"))
-            .to_request();
-        let response = test::call_service(&app, serve_request).await;
-        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
 
         Ok(())
     }
@@ -1007,6 +6933,120 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_upload_duplicate_oneshot_file() -> Result<(), Error> {
+        let test_upload_dir = "test_upload_duplicate_oneshot";
+        fs::create_dir(test_upload_dir)?;
+
+        let mut config = Config::default();
+        config.server.upload_path = PathBuf::from(&test_upload_dir);
+        config.paste.duplicate_files = Some(false);
+        let oneshot_upload_path = PasteType::Oneshot
+            .get_path(&config.server.upload_path)
+            .expect("Bad upload path");
+        fs::create_dir_all(&oneshot_upload_path)?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "oneshot", "oneshot.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test::call_service(
+            &app,
+            get_multipart_request("test", "file", "file.txt").to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), "http://localhost:8080/file.txt\n").await?;
+
+        fs::remove_dir_all(test_upload_dir)?;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_retention() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?.join("test_upload_retention");
+        config.paste.default_expiry = Some(Duration::from_secs(3600));
+        config.paste.retention = Some(crate::config::RetentionConfig {
+            oneshot: Some(Duration::from_secs(60)),
+            url: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        fs::create_dir_all(&config.server.upload_path)?;
+        let upload_path = config.server.upload_path.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let time = util::get_system_time()?.as_millis();
+        for (paste_type, field, data, file_name, expected) in [
+            (PasteType::File, "file", "file", "file.txt", Some(3_600_000)),
+            (
+                PasteType::Oneshot,
+                "oneshot",
+                "oneshot",
+                "oneshot.txt",
+                Some(60_000),
+            ),
+            (PasteType::Url, "url", "https://orhun.dev", "", None),
+        ] {
+            let request = get_multipart_request(data, field, file_name)
+                .insert_header((header::ACCEPT, "application/json"))
+                .to_request();
+            let items: Vec<UploadItem> = test::call_and_read_body_json(&app, request).await;
+            let item = items.first().expect("uploaded item");
+            assert_eq!(expected.is_some(), item.expires_at_utc.is_some());
+            let type_path = paste_type.get_path(&upload_path)?;
+            let path = glob(&format!("{}/{}*", type_path.display(), item.file_name))
+                .map_err(error::ErrorInternalServerError)?
+                .next()
+                .transpose()
+                .map_err(error::ErrorInternalServerError)?
+                .expect("stored file");
+            let expires_at = path
+                .extension()
+                .and_then(|v| v.to_str())
+                .and_then(|v| v.parse::<u128>().ok());
+            match expected {
+                Some(expected) => {
+                    let expires_at = expires_at.expect("expiry timestamp");
+                    assert!((time + expected..time + expected + 60_000).contains(&expires_at));
+                }
+                None => assert_eq!(None, expires_at),
+            }
+        }
+
+        let request = get_multipart_request("https://orhun.dev/", "url", "")
+            .insert_header((
+                header::HeaderName::from_static("expire"),
+                header::HeaderValue::from_static("10s"),
+            ))
+            .insert_header((header::ACCEPT, "application/json"))
+            .to_request();
+        let items: Vec<UploadItem> = test::call_and_read_body_json(&app, request).await;
+        assert!(items.first().is_some_and(|v| v.expires_at_utc.is_some()));
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_upload_expiring_file() -> Result<(), Error> {
         let mut config = Config::default();
@@ -1064,6 +7104,75 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_upload_expiring_file_form_field() -> Result<(), Error> {
+        let mut config = Config::default();
+        config.server.upload_path = env::current_dir()?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(RwLock::new(config)))
+                .app_data(Data::new(Client::default()))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let file_name = "test_file_form_field.txt";
+        let timestamp = util::get_system_time()?.as_secs().to_string();
+        let multipart_data = format!(
+            "\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"expire\"\r\n\r\n\
+             20ms\r\n\
+             --multipart_bound\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n\
+             {}\r\n\
+             --multipart_bound--\r\n",
+            file_name,
+            timestamp.len(),
+            timestamp,
+        );
+        let request = TestRequest::post()
+            .insert_header((
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("multipart/mixed; boundary=\"multipart_bound\""),
+            ))
+            .set_payload(multipart_data)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(
+            response.into_body(),
+            &format!("http://localhost:8080/{file_name}\n"),
+        )
+        .await?;
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_body(response.into_body(), &timestamp).await?;
+
+        thread::sleep(Duration::from_millis(40));
+
+        let serve_request = TestRequest::get()
+            .uri(&format!("/{file_name}"))
+            .to_request();
+        let response = test::call_service(&app, serve_request).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        if let Some(glob_path) = glob(&format!("{file_name}.[0-9]*"))
+            .map_err(error::ErrorInternalServerError)?
+            .next()
+        {
+            fs::remove_file(glob_path.map_err(error::ErrorInternalServerError)?)?;
+        }
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_upload_remote_file() -> Result<(), Error> {
         let mut config = Config::default();
@@ -1153,6 +7262,24 @@ mod tests {
         let serve_request = TestRequest::get().uri("/url").to_request();
         let response = test::call_service(&app, serve_request).await;
         assert_eq!(StatusCode::FOUND, response.status());
+        assert_eq!(
+            Some(env!("CARGO_PKG_HOMEPAGE")),
+            response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+        );
+
+        for uri in ["/url?raw=true", "/url?raw"] {
+            let serve_request = TestRequest::get().uri(uri).to_request();
+            let response = test::call_service(&app, serve_request).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_body(
+                response.into_body(),
+                &format!("{}\n", env!("CARGO_PKG_HOMEPAGE")),
+            )
+            .await?;
+        }
 
         fs::remove_file(url_upload_path.join("url"))?;
         fs::remove_dir(url_upload_path)?;
@@ -1224,6 +7351,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_consume_oneshot() -> Result<(), Error> {
+        let file_name = "consume_oneshot.txt";
+        let path = env::current_dir()?.join(file_name);
+        fs::write(&path, "oneshot")?;
+
+        let consumed_path = consume_oneshot(&path, file_name)?;
+        assert!(!path.exists());
+        assert_eq!("oneshot", fs::read_to_string(&consumed_path)?);
+
+        let response = consume_oneshot(&path, file_name)
+            .err()
+            .map(|e| e.error_response());
+        assert_eq!(
+            Some(StatusCode::NOT_FOUND),
+            response.as_ref().map(HttpResponse::status)
+        );
+
+        fs::remove_file(consumed_path)?;
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_upload_oneshot_url() -> Result<(), Error> {
         let mut config = Config::default();
@@ -0,0 +1,321 @@
+use crate::paste::PasteType;
+use crate::util;
+use lazy_regex::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Duration for reusing the collected statistics.
+pub const CACHE_DURATION: Duration = Duration::from_secs(10);
+
+/// Last collected statistics of the upload paths with the time of the collection.
+static CACHE: Lazy<Mutex<HashMap<PathBuf, (Instant, Stats)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Last calculated disk usage of the upload paths with the time of the calculation.
+static USAGE_CACHE: Lazy<Mutex<HashMap<PathBuf, (Instant, u64)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Statistics of the stored files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Number of the unexpired files.
+    pub file_count: usize,
+    /// Total size of the unexpired files in bytes.
+    pub total_bytes: u64,
+    /// Number of the regular files.
+    pub files: usize,
+    /// Number of the oneshot files.
+    pub oneshot_files: usize,
+    /// Number of the shortened URLs.
+    pub urls: usize,
+    /// Number of the oneshot URLs.
+    pub oneshot_urls: usize,
+    /// Least recently modified file.
+    pub oldest: Option<StatsFile>,
+    /// Most recently modified file.
+    pub newest: Option<StatsFile>,
+}
+
+/// File entry of the statistics.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsFile {
+    /// File name.
+    pub file_name: String,
+    /// Type of the paste.
+    #[serde(rename = "type")]
+    pub type_: PasteType,
+    /// ISO8601 formatted date-time string of the modification timestamp.
+    pub modified_at_utc: String,
+    /// Modification timestamp in seconds.
+    #[serde(skip)]
+    modified_at: u64,
+}
+
+impl Stats {
+    /// Returns the statistics of the upload path.
+    ///
+    /// The statistics are collected again if they are older than [`CACHE_DURATION`].
+    pub fn get(upload_path: &Path) -> IoResult<Self> {
+        let mut cache = CACHE.lock().map_err(|_| {
+            std::io::Error::other(String::from("cannot acquire the statistics cache"))
+        })?;
+        if let Some((time, stats)) = cache.get(upload_path) {
+            if time.elapsed() < CACHE_DURATION {
+                return Ok(stats.clone());
+            }
+        }
+        let stats = Self::collect(upload_path)?;
+        cache.insert(upload_path.to_path_buf(), (Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+
+    /// Returns the combined statistics of the given upload paths.
+    pub fn get_all<'a>(upload_paths: impl IntoIterator<Item = &'a Path>) -> IoResult<Self> {
+        let mut stats = Self::default();
+        for upload_path in upload_paths {
+            stats.merge(Self::get(upload_path)?);
+        }
+        Ok(stats)
+    }
+
+    /// Adds the given statistics.
+    fn merge(&mut self, other: Self) {
+        self.file_count += other.file_count;
+        self.total_bytes += other.total_bytes;
+        self.files += other.files;
+        self.oneshot_files += other.oneshot_files;
+        self.urls += other.urls;
+        self.oneshot_urls += other.oneshot_urls;
+        if let Some(file) = other.oldest {
+            if self
+                .oldest
+                .as_ref()
+                .is_none_or(|v| v.modified_at > file.modified_at)
+            {
+                self.oldest = Some(file);
+            }
+        }
+        if let Some(file) = other.newest {
+            if self
+                .newest
+                .as_ref()
+                .is_none_or(|v| v.modified_at < file.modified_at)
+            {
+                self.newest = Some(file);
+            }
+        }
+    }
+
+    /// Collects the statistics by scanning the upload path.
+    ///
+    /// The expired files are omitted.
+    pub fn collect(upload_path: &Path) -> IoResult<Self> {
+        let mut stats = Self::default();
+        let now = util::get_system_time()
+            .map_err(|e| std::io::Error::other(e.to_string()))?
+            .as_millis();
+        for paste_type in [
+            PasteType::File,
+            PasteType::Oneshot,
+            PasteType::Url,
+            PasteType::OneshotUrl,
+        ] {
+            let path = paste_type.get_path(upload_path)?;
+            if !path.is_dir() {
+                continue;
+            }
+            let mut dirs = util::get_shard_dirs(&path);
+            dirs.push(path);
+            for entry in dirs
+                .into_iter()
+                .filter_map(|v| fs::read_dir(v).ok())
+                .flatten()
+                .filter_map(Result::ok)
+            {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                let mut file_name = PathBuf::from(entry.file_name());
+                if let Some(expiration) = file_name
+                    .extension()
+                    .and_then(|v| v.to_str())
+                    .and_then(|v| v.parse::<u128>().ok())
+                {
+                    if now > expiration {
+                        continue;
+                    }
+                    file_name.set_extension("");
+                }
+                stats.file_count += 1;
+                stats.total_bytes += metadata.len();
+                match paste_type {
+                    PasteType::File | PasteType::RemoteFile => stats.files += 1,
+                    PasteType::Oneshot => stats.oneshot_files += 1,
+                    PasteType::Url => stats.urls += 1,
+                    PasteType::OneshotUrl => stats.oneshot_urls += 1,
+                }
+                let modified_at = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+                    .map(|v| v.as_secs())
+                    .unwrap_or_default();
+                let file = StatsFile {
+                    file_name: file_name.to_string_lossy().to_string(),
+                    type_: paste_type,
+                    modified_at_utc: uts2ts::uts2ts(i64::try_from(modified_at).unwrap_or_default())
+                        .as_string(),
+                    modified_at,
+                };
+                if stats
+                    .oldest
+                    .as_ref()
+                    .is_none_or(|v| v.modified_at > modified_at)
+                {
+                    stats.oldest = Some(file.clone());
+                }
+                if stats
+                    .newest
+                    .as_ref()
+                    .is_none_or(|v| v.modified_at < modified_at)
+                {
+                    stats.newest = Some(file);
+                }
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// Returns the total size of the stored files (including the expired ones) in the upload path.
+///
+/// The upload path is scanned again if the usage is older than [`CACHE_DURATION`].
+/// The sizes of the files that are stored in the meantime are added via [`add_usage`].
+///
+/// Fail-safe, omits errors.
+pub fn get_usage(upload_path: &Path) -> u64 {
+    let Ok(mut cache) = USAGE_CACHE.lock() else {
+        return 0;
+    };
+    if let Some((time, usage)) = cache.get(upload_path) {
+        if time.elapsed() < CACHE_DURATION {
+            return *usage;
+        }
+    }
+    let usage = util::get_stored_files(upload_path)
+        .iter()
+        .filter_map(|v| fs::metadata(v).ok())
+        .map(|v| v.len())
+        .sum();
+    cache.insert(upload_path.to_path_buf(), (Instant::now(), usage));
+    usage
+}
+
+/// Adds the given size to the cached usage of the upload path (see [`get_usage`]).
+pub fn add_usage(upload_path: &Path, size: u64) {
+    if let Ok(mut cache) = USAGE_CACHE.lock() {
+        if let Some((_, usage)) = cache.get_mut(upload_path) {
+            *usage = usage.saturating_add(size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_collect_stats() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_stats_upload");
+        fs::create_dir_all(PasteType::Oneshot.get_path(&upload_path)?)?;
+        fs::create_dir_all(PasteType::Url.get_path(&upload_path)?)?;
+        fs::write(upload_path.join("old.txt"), "old")?;
+        fs::write(upload_path.join("new.txt"), "newer")?;
+        fs::write(upload_path.join("expired.txt.1"), "expired")?;
+        fs::write(upload_path.join("oneshot").join("once.txt"), "once")?;
+        fs::write(upload_path.join("url").join("link"), "https://orhun.dev")?;
+        for (file_name, modified_at) in [("old.txt", 1_000_000_000), ("new.txt", 4_000_000_000)] {
+            fs::File::options()
+                .write(true)
+                .open(upload_path.join(file_name))?
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified_at))?;
+        }
+
+        let stats = Stats::collect(&upload_path)?;
+        assert_eq!(4, stats.file_count);
+        assert_eq!(29, stats.total_bytes);
+        assert_eq!(2, stats.files);
+        assert_eq!(1, stats.oneshot_files);
+        assert_eq!(1, stats.urls);
+        assert_eq!(0, stats.oneshot_urls);
+        assert_eq!(
+            Some("old.txt"),
+            stats.oldest.as_ref().map(|v| v.file_name.as_str())
+        );
+        assert_eq!(
+            Some("new.txt"),
+            stats.newest.as_ref().map(|v| v.file_name.as_str())
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_stats() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_merge_stats_upload");
+        let overflow_path = env::current_dir()?.join("test_merge_stats_upload_2");
+        fs::create_dir_all(&upload_path)?;
+        fs::create_dir_all(&overflow_path)?;
+        fs::write(upload_path.join("first.txt"), "first")?;
+        fs::write(overflow_path.join("second.txt"), "second")?;
+        fs::File::options()
+            .write(true)
+            .open(overflow_path.join("second.txt"))?
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))?;
+
+        let stats = Stats::get_all([upload_path.as_path(), overflow_path.as_path()])?;
+        assert_eq!(2, stats.file_count);
+        assert_eq!(11, stats.total_bytes);
+        assert_eq!(2, stats.files);
+        assert_eq!(
+            Some("second.txt"),
+            stats.oldest.as_ref().map(|v| v.file_name.as_str())
+        );
+        assert_eq!(
+            Some("first.txt"),
+            stats.newest.as_ref().map(|v| v.file_name.as_str())
+        );
+
+        fs::remove_dir_all(upload_path)?;
+        fs::remove_dir_all(overflow_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_usage() -> IoResult<()> {
+        let upload_path = env::current_dir()?.join("test_usage_upload");
+        fs::create_dir_all(&upload_path)?;
+        fs::write(upload_path.join("file.txt"), "file")?;
+        fs::write(upload_path.join("expired.txt.1"), "expired")?;
+
+        assert_eq!(11, get_usage(&upload_path));
+        fs::write(upload_path.join("new.txt"), "new")?;
+        assert_eq!(11, get_usage(&upload_path));
+        add_usage(&upload_path, 3);
+        assert_eq!(14, get_usage(&upload_path));
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,792 @@
+use crate::config::{CompressionType, Config, StorageType};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use infer::MatcherType;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "s3")]
+use {
+    crate::config::StorageConfig,
+    s3::creds::Credentials,
+    s3::error::S3Error,
+    s3::{Bucket, Region},
+};
+
+/// Backend for storing the uploaded files.
+///
+/// Paths are located in the [`upload path`](crate::config::ServerConfig::upload_path).
+pub trait StorageBackend: Send + Sync {
+    /// Writes the data to the given path.
+    fn store(&self, path: &Path, data: &[u8]) -> IoResult<()>;
+
+    /// Writes the data to the given path if there is no data at it yet.
+    ///
+    /// Returns an [`AlreadyExists`](IoErrorKind::AlreadyExists) error otherwise.
+    fn store_new(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        if self.exists(path)? {
+            return Err(IoError::new(
+                IoErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        self.store(path, data)
+    }
+
+    /// Reads the data from the given path.
+    fn fetch(&self, path: &Path) -> IoResult<Vec<u8>>;
+
+    /// Removes the data at the given path.
+    fn delete(&self, path: &Path) -> IoResult<()>;
+
+    /// Checks if there is data at the given path.
+    ///
+    /// Returns an error if it cannot be determined (e.g. the storage is unreachable).
+    fn exists(&self, path: &Path) -> IoResult<bool>;
+
+    /// Returns `true` if the files are stored in the local filesystem.
+    ///
+    /// Features that rely on renaming the files (e.g. expiration and oneshot files)
+    /// are only available for the local storage.
+    fn is_local(&self) -> bool;
+
+    /// Returns `true` if the stored data is encrypted.
+    ///
+    /// Encrypted files cannot be served directly from the filesystem.
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if the stored data might be compressed.
+    ///
+    /// Compressed files cannot be served directly from the filesystem.
+    fn is_compressed(&self) -> bool {
+        false
+    }
+
+    /// Reads the data from the given path without decompressing it.
+    ///
+    /// Returns the compression method of the data along with it.
+    fn fetch_encoded(&self, path: &Path) -> IoResult<(Vec<u8>, CompressionType)> {
+        Ok((self.fetch(path)?, CompressionType::None))
+    }
+}
+
+/// Storage backend for the local filesystem.
+#[derive(Debug, Default)]
+pub struct LocalStorage;
+
+impl StorageBackend for LocalStorage {
+    /// Writes the data to a temporary file first and renames it into place.
+    ///
+    /// This way, a partially written file is never visible at the given path.
+    fn store(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        let temp_path = get_temp_path(path)?;
+        let result = File::create(&temp_path)
+            .and_then(|mut buffer| {
+                buffer.write_all(data)?;
+                buffer.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp_path, path));
+        if result.is_err() && temp_path.exists() {
+            // Do not leave a partially written file behind.
+            if let Err(e) = fs::remove_file(&temp_path) {
+                error!("cannot remove temporary file: {}", e);
+            }
+        }
+        result
+    }
+
+    /// Reserves the path with [`create_new`](fs::OpenOptions::create_new) before writing the data.
+    ///
+    /// This way, the same path cannot be claimed by concurrent uploads.
+    fn store_new(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        let result = self.store(path, data);
+        if result.is_err() {
+            if let Err(e) = fs::remove_file(path) {
+                error!("cannot remove reserved file: {}", e);
+            }
+        }
+        result
+    }
+
+    fn fetch(&self, path: &Path) -> IoResult<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn delete(&self, path: &Path) -> IoResult<()> {
+        fs::remove_file(path)
+    }
+
+    fn exists(&self, path: &Path) -> IoResult<bool> {
+        Ok(path.is_file())
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// Returns a unique temporary path next to the given path (e.g. `.file.txt.<nanos>.tmp`).
+fn get_temp_path(path: &Path) -> IoResult<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|v| v.to_string_lossy())
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidInput, "path has no file name"))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(IoError::other)?
+        .as_nanos();
+    Ok(path.with_file_name(format!(".{file_name}.{nanos}.tmp")))
+}
+
+/// Storage backend for S3 compatible object storage.
+///
+/// Object keys are the paths relative to the upload path.
+#[cfg(feature = "s3")]
+pub struct S3Storage {
+    /// Bucket to store the objects in.
+    bucket: Box<Bucket>,
+    /// Base path of the object keys.
+    base_path: PathBuf,
+}
+
+#[cfg(feature = "s3")]
+impl S3Storage {
+    /// Constructs a new instance from the storage configuration.
+    pub fn new(config: &StorageConfig, base_path: &Path) -> IoResult<Self> {
+        let bucket_name = config.bucket.as_deref().ok_or_else(|| {
+            IoError::new(IoErrorKind::InvalidInput, "[storage].bucket is not set")
+        })?;
+        let region_name = config
+            .region
+            .clone()
+            .unwrap_or_else(|| String::from("us-east-1"));
+        let region = match &config.endpoint {
+            Some(endpoint) => Region::Custom {
+                region: region_name,
+                endpoint: endpoint.to_string(),
+            },
+            None => region_name.parse().map_err(IoError::other)?,
+        };
+        let credentials = Credentials::new(
+            config.access_key.as_deref(),
+            config.secret_key.as_deref(),
+            None,
+            None,
+            None,
+        )
+        .map_err(IoError::other)?;
+        let mut bucket = Bucket::new(bucket_name, region, credentials).map_err(IoError::other)?;
+        if config.path_style.unwrap_or(false) {
+            bucket = bucket.with_path_style();
+        }
+        Ok(Self {
+            bucket,
+            base_path: base_path.to_path_buf(),
+        })
+    }
+
+    /// Returns the object key of the given path.
+    fn get_key(&self, path: &Path) -> IoResult<String> {
+        path.strip_prefix(&self.base_path)
+            .map(|v| v.to_string_lossy().replace('\\', "/"))
+            .map_err(|_| {
+                IoError::new(
+                    IoErrorKind::InvalidInput,
+                    format!(
+                        "{} is outside of {}",
+                        path.display(),
+                        self.base_path.display()
+                    ),
+                )
+            })
+    }
+}
+
+/// Converts the S3 errors into I/O errors whilst keeping the "not found" status.
+#[cfg(feature = "s3")]
+fn into_io_error(e: S3Error) -> IoError {
+    match e {
+        S3Error::HttpFailWithBody(404, _) => IoError::new(IoErrorKind::NotFound, e.to_string()),
+        e => IoError::other(e.to_string()),
+    }
+}
+
+#[cfg(feature = "s3")]
+impl StorageBackend for S3Storage {
+    fn store(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        self.bucket
+            .put_object(self.get_key(path)?, data)
+            .map(|_| ())
+            .map_err(into_io_error)
+    }
+
+    fn fetch(&self, path: &Path) -> IoResult<Vec<u8>> {
+        self.bucket
+            .get_object(self.get_key(path)?)
+            .map(|response| response.bytes().to_vec())
+            .map_err(into_io_error)
+    }
+
+    fn delete(&self, path: &Path) -> IoResult<()> {
+        self.bucket
+            .delete_object(self.get_key(path)?)
+            .map(|_| ())
+            .map_err(into_io_error)
+    }
+
+    /// Only the "not found" status is treated as a missing object,
+    /// the other errors (e.g. network failures) are returned.
+    fn exists(&self, path: &Path) -> IoResult<bool> {
+        match self.bucket.head_object(self.get_key(path)?) {
+            Ok((_, status_code)) => Ok(status_code == 200),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(false),
+            Err(e) => Err(into_io_error(e)),
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Storage backend that encrypts the data with AES-256-GCM.
+///
+/// The random nonce is prepended to the stored data.
+pub struct EncryptedStorage {
+    /// Backend to store the encrypted data in.
+    inner: Box<dyn StorageBackend>,
+    /// Encryption key.
+    key: LessSafeKey,
+}
+
+impl EncryptedStorage {
+    /// Constructs a new instance from the hex encoded 256-bit key.
+    pub fn new(inner: Box<dyn StorageBackend>, key: &str) -> IoResult<Self> {
+        let invalid_key = || {
+            IoError::new(
+                IoErrorKind::InvalidInput,
+                "[storage].encryption_key must be a hex encoded 256-bit key (64 characters)",
+            )
+        };
+        let key = key.trim();
+        if key.len() != AES_256_GCM.key_len() * 2 || !key.is_ascii() {
+            return Err(invalid_key());
+        }
+        let key = (0..key.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&key[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid_key())?;
+        let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| invalid_key())?;
+        Ok(Self {
+            inner,
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// Encrypts the data with a random nonce.
+    fn encrypt(&self, data: &[u8]) -> IoResult<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| IoError::other("cannot generate nonce"))?;
+        let mut in_out = data.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| IoError::other("cannot encrypt data"))?;
+        let mut encrypted = nonce.to_vec();
+        encrypted.append(&mut in_out);
+        Ok(encrypted)
+    }
+}
+
+impl StorageBackend for EncryptedStorage {
+    fn store(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        self.inner.store(path, &self.encrypt(data)?)
+    }
+
+    fn store_new(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        self.inner.store_new(path, &self.encrypt(data)?)
+    }
+
+    fn fetch(&self, path: &Path) -> IoResult<Vec<u8>> {
+        let mut data = self.inner.fetch(path)?;
+        if data.len() < NONCE_LEN {
+            return Err(IoError::new(
+                IoErrorKind::InvalidData,
+                "encrypted data is too short",
+            ));
+        }
+        let mut in_out = data.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&data)
+            .map_err(|_| IoError::new(IoErrorKind::InvalidData, "invalid nonce"))?;
+        let decrypted_len = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| IoError::new(IoErrorKind::InvalidData, "cannot decrypt data"))?
+            .len();
+        in_out.truncate(decrypted_len);
+        Ok(in_out)
+    }
+
+    fn delete(&self, path: &Path) -> IoResult<()> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> IoResult<bool> {
+        self.inner.exists(path)
+    }
+
+    fn is_local(&self) -> bool {
+        self.inner.is_local()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+/// Header of the compressed data.
+///
+/// Followed by a byte that denotes the [`compression method`](CompressionType).
+const COMPRESSION_MAGIC: &[u8] = b"\0RPZ";
+
+/// Storage backend that compresses the data before storing it.
+///
+/// The compression method is stored in a small header so that compressed and
+/// uncompressed files can live in the same directory. Data that is already compressed
+/// (e.g. images and archives) or does not get smaller is stored as is.
+pub struct CompressedStorage {
+    /// Backend to store the compressed data in.
+    inner: Box<dyn StorageBackend>,
+    /// Compression method for the new files.
+    compression: CompressionType,
+}
+
+impl CompressedStorage {
+    /// Constructs a new instance.
+    pub fn new(inner: Box<dyn StorageBackend>, compression: CompressionType) -> Self {
+        Self { inner, compression }
+    }
+
+    /// Compresses the data with the header if it gets smaller.
+    fn encode(&self, data: &[u8]) -> IoResult<Vec<u8>> {
+        let is_compressed_type = infer::get(data)
+            .map(|v| {
+                matches!(
+                    v.matcher_type(),
+                    MatcherType::Archive
+                        | MatcherType::Audio
+                        | MatcherType::Image
+                        | MatcherType::Video
+                )
+            })
+            .unwrap_or(false);
+        if self.compression == CompressionType::None || is_compressed_type {
+            return Ok(data.to_vec());
+        }
+        let mut compressed = COMPRESSION_MAGIC.to_vec();
+        compressed.push(compression_id(self.compression));
+        compressed.append(&mut compress(self.compression, data)?);
+        if compressed.len() >= data.len() {
+            return Ok(data.to_vec());
+        }
+        Ok(compressed)
+    }
+}
+
+/// Returns the identifier byte of the compression method in the header.
+fn compression_id(compression: CompressionType) -> u8 {
+    match compression {
+        CompressionType::None => 0,
+        CompressionType::Gzip => 1,
+        CompressionType::Zstd => 2,
+    }
+}
+
+/// Compresses the data with the given method.
+pub fn compress(compression: CompressionType, data: &[u8]) -> IoResult<Vec<u8>> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        CompressionType::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL),
+    }
+}
+
+/// Decompresses the data with the given method.
+pub fn decompress(compression: CompressionType, data: &[u8]) -> IoResult<Vec<u8>> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::Gzip => {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        CompressionType::Zstd => zstd::decode_all(data),
+    }
+}
+
+impl StorageBackend for CompressedStorage {
+    fn store(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        self.inner.store(path, &self.encode(data)?)
+    }
+
+    fn store_new(&self, path: &Path, data: &[u8]) -> IoResult<()> {
+        self.inner.store_new(path, &self.encode(data)?)
+    }
+
+    fn fetch(&self, path: &Path) -> IoResult<Vec<u8>> {
+        let (data, compression) = self.fetch_encoded(path)?;
+        decompress(compression, &data)
+    }
+
+    fn delete(&self, path: &Path) -> IoResult<()> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> IoResult<bool> {
+        self.inner.exists(path)
+    }
+
+    fn is_local(&self) -> bool {
+        self.inner.is_local()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.inner.is_encrypted()
+    }
+
+    fn is_compressed(&self) -> bool {
+        true
+    }
+
+    fn fetch_encoded(&self, path: &Path) -> IoResult<(Vec<u8>, CompressionType)> {
+        let mut data = self.inner.fetch(path)?;
+        if data.len() <= COMPRESSION_MAGIC.len() || !data.starts_with(COMPRESSION_MAGIC) {
+            return Ok((data, CompressionType::None));
+        }
+        let compression = match data[COMPRESSION_MAGIC.len()] {
+            1 => CompressionType::Gzip,
+            2 => CompressionType::Zstd,
+            _ => return Ok((data, CompressionType::None)),
+        };
+        Ok((data.split_off(COMPRESSION_MAGIC.len() + 1), compression))
+    }
+}
+
+/// Storage backend that is built once and shared between the requests.
+///
+/// It is stored in the [`configuration`](Config::storage_backend) by [`init_backend`].
+#[derive(Clone)]
+pub struct SharedBackend {
+    /// Upload path that the backend is built for.
+    upload_path: PathBuf,
+    /// The backend.
+    backend: Arc<dyn StorageBackend>,
+}
+
+impl fmt::Debug for SharedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedBackend")
+            .field("upload_path", &self.upload_path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builds the storage backend and stores it in the configuration.
+///
+/// This is done at startup and on reload so that the backend (e.g. the S3 bucket
+/// and its credentials) is not built for every request.
+pub fn init_backend(config: &mut Config) -> IoResult<()> {
+    config.storage_backend = Some(SharedBackend {
+        upload_path: config.server.upload_path.clone(),
+        backend: build_backend(config)?,
+    });
+    Ok(())
+}
+
+/// Returns the configured storage backend.
+///
+/// The [shared backend](init_backend) is returned if it is built for the same upload path.
+pub fn get_backend(config: &Config) -> IoResult<Arc<dyn StorageBackend>> {
+    match config
+        .storage_backend
+        .as_ref()
+        .filter(|v| v.upload_path == config.server.upload_path)
+    {
+        Some(shared) => Ok(Arc::clone(&shared.backend)),
+        None => build_backend(config),
+    }
+}
+
+/// Builds the configured storage backend.
+///
+/// - Defaults to [`LocalStorage`] if `[storage]` is not configured.
+/// - Wraps the backend with [`EncryptedStorage`] if an encryption key is set.
+/// - Wraps the backend with [`CompressedStorage`] if compression is enabled.
+fn build_backend(config: &Config) -> IoResult<Arc<dyn StorageBackend>> {
+    let backend: Box<dyn StorageBackend> =
+        match config.storage.as_ref().map(|v| v.type_).unwrap_or_default() {
+            StorageType::Local => Box::new(LocalStorage),
+            #[cfg(feature = "s3")]
+            StorageType::S3 => Box::new(S3Storage::new(
+                config.storage.as_ref().ok_or_else(|| {
+                    IoError::new(IoErrorKind::InvalidInput, "[storage] is not configured")
+                })?,
+                &config.server.upload_path,
+            )?),
+            #[cfg(not(feature = "s3"))]
+            StorageType::S3 => {
+                return Err(IoError::new(
+                    IoErrorKind::Unsupported,
+                    "S3 storage is not supported, enable the s3 feature",
+                ))
+            }
+        };
+    let backend: Box<dyn StorageBackend> = match config
+        .storage
+        .as_ref()
+        .and_then(|v| v.encryption_key.as_deref())
+    {
+        Some(key) => Box::new(EncryptedStorage::new(backend, key)?),
+        None => backend,
+    };
+    match config
+        .storage
+        .as_ref()
+        .map(|v| v.compression)
+        .unwrap_or_default()
+    {
+        CompressionType::None => Ok(Arc::from(backend)),
+        compression => Ok(Arc::new(CompressedStorage::new(backend, compression))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StorageConfig;
+    use std::env;
+
+    #[test]
+    fn test_local_storage() -> IoResult<()> {
+        let config = Config::default();
+        let storage = get_backend(&config)?;
+        assert!(storage.is_local());
+
+        let path = env::current_dir()?.join("test_local_storage.txt");
+        assert!(!storage.exists(&path)?);
+        storage.store(&path, b"local storage")?;
+        assert!(storage.exists(&path)?);
+        assert_eq!(b"local storage".to_vec(), storage.fetch(&path)?);
+        assert_eq!(
+            Some(IoErrorKind::AlreadyExists),
+            storage
+                .store_new(&path, b"new data")
+                .err()
+                .map(|e| e.kind())
+        );
+        assert_eq!(b"local storage".to_vec(), storage.fetch(&path)?);
+        storage.delete(&path)?;
+        assert!(!storage.exists(&path)?);
+        storage.store_new(&path, b"new data")?;
+        assert_eq!(b"new data".to_vec(), storage.fetch(&path)?);
+        storage.delete(&path)?;
+
+        let missing_path = env::current_dir()?.join("test_local_storage_missing/file.txt");
+        assert!(storage.store(&missing_path, b"local storage").is_err());
+        assert!(!missing_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_backend() -> IoResult<()> {
+        let mut config = Config::default();
+        init_backend(&mut config)?;
+        assert!(Arc::ptr_eq(&get_backend(&config)?, &get_backend(&config)?));
+        let mut overflow_config = config.clone();
+        overflow_config.server.upload_path = PathBuf::from("./overflow");
+        assert!(!Arc::ptr_eq(
+            &get_backend(&config)?,
+            &get_backend(&overflow_config)?
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_temp_path() -> IoResult<()> {
+        let temp_path = get_temp_path(Path::new("upload/file.txt"))?;
+        assert_eq!(Some(Path::new("upload")), temp_path.parent());
+        let temp_name = temp_path
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default();
+        assert!(temp_name.starts_with(".file.txt."));
+        assert!(temp_name.ends_with(".tmp"));
+        assert!(get_temp_path(Path::new("/")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_s3_storage_without_bucket() {
+        let config = Config {
+            storage: Some(StorageConfig {
+                type_: StorageType::S3,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(get_backend(&config).is_err());
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_s3_storage_exists() -> IoResult<()> {
+        use std::net::TcpListener;
+        use std::thread;
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        thread::spawn(move || {
+            let statuses = ["404 Not Found", "500 Internal Server Error", "200 OK"];
+            for (mut stream, status) in listener.incoming().filter_map(Result::ok).zip(statuses) {
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(
+                    format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                        .as_bytes(),
+                );
+            }
+        });
+        let storage = S3Storage::new(
+            &StorageConfig {
+                type_: StorageType::S3,
+                bucket: Some(String::from("test")),
+                endpoint: Some(endpoint),
+                access_key: Some(String::from("access")),
+                secret_key: Some(String::from("secret")),
+                path_style: Some(true),
+                ..Default::default()
+            },
+            Path::new("upload"),
+        )?;
+        let path = Path::new("upload/file.txt");
+        assert!(!storage.exists(path)?);
+        assert!(storage.exists(path).is_err());
+        assert!(storage.exists(path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_storage() -> IoResult<()> {
+        let mut config = Config {
+            storage: Some(StorageConfig {
+                encryption_key: Some("00".repeat(32)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let storage = get_backend(&config)?;
+        assert!(storage.is_local());
+        assert!(storage.is_encrypted());
+
+        let path = env::current_dir()?.join("test_encrypted_storage.txt");
+        storage.store(&path, b"encrypted storage")?;
+        let encrypted = fs::read(&path)?;
+        assert_ne!(b"encrypted storage".to_vec(), encrypted);
+        assert_eq!(
+            NONCE_LEN + b"encrypted storage".len() + AES_256_GCM.tag_len(),
+            encrypted.len()
+        );
+        assert_eq!(b"encrypted storage".to_vec(), storage.fetch(&path)?);
+
+        if let Some(storage_config) = config.storage.as_mut() {
+            storage_config.encryption_key = Some("11".repeat(32));
+        }
+        assert!(get_backend(&config)?.fetch(&path).is_err());
+        storage.delete(&path)?;
+
+        for key in ["00", "zz".repeat(32).as_str(), "00".repeat(33).as_str()] {
+            if let Some(storage_config) = config.storage.as_mut() {
+                storage_config.encryption_key = Some(key.to_string());
+            }
+            assert!(get_backend(&config).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_storage() -> IoResult<()> {
+        let data = "compressed storage\n".repeat(100);
+        for compression in [CompressionType::Gzip, CompressionType::Zstd] {
+            let mut config = Config {
+                storage: Some(StorageConfig {
+                    compression,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let storage = get_backend(&config)?;
+            assert!(storage.is_compressed());
+
+            let path = env::current_dir()?.join(format!(
+                "test_compressed_storage_{}.txt",
+                compression.as_str()
+            ));
+            storage.store(&path, data.as_bytes())?;
+            let compressed = fs::read(&path)?;
+            assert!(compressed.starts_with(COMPRESSION_MAGIC));
+            assert!(compressed.len() < data.len());
+            assert_eq!(data.as_bytes().to_vec(), storage.fetch(&path)?);
+            let (encoded, encoded_compression) = storage.fetch_encoded(&path)?;
+            assert_eq!(compression, encoded_compression);
+            assert_eq!(data.as_bytes().to_vec(), decompress(compression, &encoded)?);
+
+            // Files that are stored without compression are still readable.
+            if let Some(storage_config) = config.storage.as_mut() {
+                storage_config.compression = CompressionType::None;
+            }
+            get_backend(&config)?.store(&path, data.as_bytes())?;
+            let (plain, plain_compression) = storage.fetch_encoded(&path)?;
+            assert_eq!(CompressionType::None, plain_compression);
+            assert_eq!(data.as_bytes().to_vec(), plain);
+            storage.delete(&path)?;
+        }
+
+        // Already compressed data (e.g. PNG) is stored as is.
+        let config = Config {
+            storage: Some(StorageConfig {
+                compression: CompressionType::Gzip,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let png = [
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let path = env::current_dir()?.join("test_compressed_storage.png");
+        get_backend(&config)?.store(&path, &png)?;
+        assert_eq!(png.to_vec(), fs::read(&path)?);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
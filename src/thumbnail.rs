@@ -0,0 +1,180 @@
+use crate::util;
+use actix_web::{error, Error};
+use image::imageops::FilterType;
+use image::ImageFormat;
+use path_clean::PathClean;
+use std::fs;
+use std::io::{Cursor, Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+
+/// Directory of the cached thumbnails in the upload path.
+pub const THUMBNAIL_DIR: &str = ".thumbnails";
+
+/// Default width of the thumbnails.
+pub const DEFAULT_WIDTH: u32 = 200;
+
+/// Maximum width of the thumbnails.
+pub const MAX_WIDTH: u32 = 2048;
+
+/// Widths of the thumbnails that are cached.
+///
+/// The thumbnails of the other widths are generated on each request so that the cache
+/// cannot grow with every possible width.
+pub const CACHED_WIDTHS: &[u32] = &[100, 200, 400, 800];
+
+/// Generates a PNG thumbnail of the image with the given width.
+///
+/// The aspect ratio is preserved and the images that are narrower than the given width are
+/// not upscaled.
+///
+/// Returns an error if the data is not an image or the image format is not supported.
+pub fn generate(data: &[u8], width: u32) -> Result<Vec<u8>, Error> {
+    if !infer::is_image(data) {
+        return Err(error::ErrorUnsupportedMediaType(
+            "thumbnails are only supported for images\n",
+        ));
+    }
+    let image = image::load_from_memory(data)
+        .map_err(|_| error::ErrorUnsupportedMediaType("image format is not supported\n"))?;
+    let image = if image.width() > width {
+        let height = u64::from(image.height()) * u64::from(width) / u64::from(image.width());
+        image.resize_exact(
+            width,
+            u32::try_from(height).unwrap_or(u32::MAX).max(1),
+            FilterType::Triangle,
+        )
+    } else {
+        image
+    };
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(bytes)
+}
+
+/// Returns the path of the cached thumbnail of the file at the given path.
+///
+/// e.g. `upload/image.png` maps to `upload/.thumbnails/image.png.200.png` for the width of 200.
+pub fn get_path(upload_path: &Path, file_path: &Path, width: u32) -> IoResult<PathBuf> {
+    let file_path = file_path.clean();
+    let relative_path = file_path.strip_prefix(upload_path.clean()).map_err(|_| {
+        IoError::new(
+            IoErrorKind::InvalidInput,
+            format!(
+                "{} is outside of {}",
+                file_path.display(),
+                upload_path.display()
+            ),
+        )
+    })?;
+    util::safe_path_join(
+        upload_path.join(THUMBNAIL_DIR),
+        format!("{}.{width}.png", relative_path.to_string_lossy()),
+    )
+}
+
+/// Reads the cached thumbnail if it is not older than the file.
+pub fn read_cached(thumbnail_path: &Path, file_path: &Path) -> Option<Vec<u8>> {
+    let thumbnail_modified = fs::metadata(thumbnail_path).ok()?.modified().ok()?;
+    let file_modified = fs::metadata(file_path).ok()?.modified().ok()?;
+    if thumbnail_modified < file_modified {
+        return None;
+    }
+    fs::read(thumbnail_path).ok()
+}
+
+/// Writes the thumbnail to the cache.
+pub fn write_cached(thumbnail_path: &Path, data: &[u8]) -> IoResult<()> {
+    if let Some(parent) = thumbnail_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(thumbnail_path, data)
+}
+
+/// Returns the paths of the cached thumbnails of the file at the given path.
+fn get_cached_paths(upload_path: &Path, file_path: &Path) -> IoResult<Vec<PathBuf>> {
+    let path = get_path(upload_path, file_path, 0)?
+        .to_string_lossy()
+        .to_string();
+    let pattern = format!(
+        "{}.*.png",
+        glob::Pattern::escape(path.trim_end_matches(".0.png"))
+    );
+    Ok(glob::glob(&pattern)
+        .map_err(|e| IoError::new(IoErrorKind::InvalidInput, e))?
+        .filter_map(Result::ok)
+        .collect())
+}
+
+/// Returns the total size of the cached thumbnails of the file at the given path.
+pub fn get_cached_size(upload_path: &Path, file_path: &Path) -> IoResult<u64> {
+    Ok(get_cached_paths(upload_path, file_path)?
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum())
+}
+
+/// Removes the cached thumbnails of the file at the given path.
+pub fn delete(upload_path: &Path, file_path: &Path) -> IoResult<()> {
+    for thumbnail_path in get_cached_paths(upload_path, file_path)? {
+        fs::remove_file(thumbnail_path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, GenericImageView};
+    use std::env;
+
+    #[test]
+    fn test_thumbnail() -> Result<(), Error> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgb8(400, 100)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(error::ErrorInternalServerError)?;
+        let thumbnail = image::load_from_memory(&generate(&data, 200)?)
+            .map_err(error::ErrorInternalServerError)?;
+        assert_eq!((200, 50), thumbnail.dimensions());
+        let thumbnail = image::load_from_memory(&generate(&data, 1000)?)
+            .map_err(error::ErrorInternalServerError)?;
+        assert_eq!((400, 100), thumbnail.dimensions());
+        assert!(generate(b"text", 200).is_err());
+        for format in [ImageFormat::Jpeg, ImageFormat::Gif, ImageFormat::WebP] {
+            let mut data = Vec::new();
+            DynamicImage::new_rgb8(400, 100)
+                .write_to(&mut Cursor::new(&mut data), format)
+                .map_err(error::ErrorInternalServerError)?;
+            let thumbnail = image::load_from_memory(&generate(&data, 100)?)
+                .map_err(error::ErrorInternalServerError)?;
+            assert_eq!((100, 25), thumbnail.dimensions());
+        }
+
+        let upload_path = env::current_dir()?.join("test_thumbnail_upload");
+        let file_path = upload_path.join("image.png");
+        let thumbnail_path = get_path(&upload_path, &file_path, 200)?;
+        assert_eq!(
+            upload_path.join(".thumbnails").join("image.png.200.png"),
+            thumbnail_path
+        );
+        fs::create_dir_all(&upload_path)?;
+        fs::write(&file_path, &data)?;
+        assert_eq!(None, read_cached(&thumbnail_path, &file_path));
+        write_cached(&thumbnail_path, b"thumbnail")?;
+        assert_eq!(
+            Some(b"thumbnail".to_vec()),
+            read_cached(&thumbnail_path, &file_path)
+        );
+        write_cached(&get_path(&upload_path, &file_path, 400)?, b"thumb")?;
+        assert_eq!(14, get_cached_size(&upload_path, &file_path)?);
+        delete(&upload_path, &file_path)?;
+        assert!(!thumbnail_path.exists());
+        assert_eq!(0, get_cached_size(&upload_path, &file_path)?);
+
+        fs::remove_dir_all(upload_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::config::TlsConfig;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use std::fs::File;
+use std::io::{BufReader, Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::path::Path;
+
+/// Loads the certificate chain and the private key for serving over HTTPS.
+pub fn load_server_config(config: &TlsConfig) -> IoResult<ServerConfig> {
+    let certs = load_certs(&config.cert_path)?;
+    let key = load_key(&config.key_path)?;
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| {
+            IoError::new(
+                IoErrorKind::InvalidData,
+                format!("cannot use the TLS certificate: {e}"),
+            )
+        })
+}
+
+/// Opens the file at the given path for reading the PEM sections.
+fn open_file(path: &Path) -> IoResult<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| IoError::new(e.kind(), format!("cannot open {}: {e}", path.display())))
+}
+
+/// Reads the certificates from the PEM file at the given path.
+fn load_certs(path: &Path) -> IoResult<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut open_file(path)?)?;
+    if certs.is_empty() {
+        return Err(IoError::new(
+            IoErrorKind::InvalidData,
+            format!("no certificates found in {}", path.display()),
+        ));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Reads the first private key from the PEM file at the given path.
+fn load_key(path: &Path) -> IoResult<PrivateKey> {
+    rustls_pemfile::read_all(&mut open_file(path)?)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            IoError::new(
+                IoErrorKind::InvalidData,
+                format!("no private key found in {}", path.display()),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_load_server_config() {
+        let fixture_dir = PathBuf::from("fixtures").join("test-server-tls");
+        let cert_path = fixture_dir.join("cert.pem");
+        let key_path = fixture_dir.join("key.pem");
+        let get_error = |cert_path: &PathBuf, key_path: &PathBuf| {
+            load_server_config(&TlsConfig {
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+            })
+            .err()
+            .map(|e| (e.kind(), e.to_string()))
+        };
+        assert_eq!(None, get_error(&cert_path, &key_path));
+
+        let error = get_error(&fixture_dir.join("missing.pem"), &key_path);
+        assert_eq!(Some(IoErrorKind::NotFound), error.as_ref().map(|e| e.0));
+        assert!(error.is_some_and(|e| e.1.contains("missing.pem")));
+
+        let error = get_error(&key_path, &key_path);
+        assert!(error.is_some_and(|e| e.1.starts_with("no certificates found")));
+
+        let error = get_error(&cert_path, &cert_path);
+        assert!(error.is_some_and(|e| e.1.starts_with("no private key found")));
+    }
+}
@@ -1,19 +1,65 @@
+use crate::config::Config;
 use crate::paste::PasteType;
-use actix_web::{error, Error as ActixError};
+use crate::storage::StorageBackend;
+use actix_web::{error, web, Error as ActixError};
 use glob::glob;
 use lazy_regex::{lazy_regex, Lazy, Regex};
 use path_clean::PathClean;
 use ring::digest::{Context, SHA256};
+use ring::hmac;
 use std::fmt::Write;
+use std::fs;
 use std::io::{BufReader, Read};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Maximum length of a file name in bytes.
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Size of the JSON body fields other than the base64 encoded data.
+const JSON_BODY_OVERHEAD: u64 = 4096;
+
 /// Regex for matching the timestamp extension of a path.
 pub static TIMESTAMP_EXTENSION_REGEX: Lazy<Regex> = lazy_regex!(r#"\.[0-9]{10,}$"#);
 
+/// Runs the blocking function on the thread pool if the storage is not local.
+///
+/// The storage operations of the remote backends (e.g. S3) are network requests,
+/// so they should not hold the worker thread. The errors are passed back with
+/// their status code and message since they cannot be sent between the threads.
+pub async fn run_blocking<F, R>(
+    config: &Config,
+    storage: &Arc<dyn StorageBackend>,
+    f: F,
+) -> Result<R, ActixError>
+where
+    F: FnOnce(&Config, &dyn StorageBackend) -> Result<R, ActixError> + Send + 'static,
+    R: Send + 'static,
+{
+    if storage.is_local() {
+        return f(config, &**storage);
+    }
+    let config = config.clone();
+    let storage = Arc::clone(storage);
+    block(move || f(&config, &*storage)).await
+}
+
+/// Runs the given closure on the thread pool for blocking operations.
+///
+/// The status code and the message of the returned error are preserved.
+pub async fn block<F, R>(f: F) -> Result<R, ActixError>
+where
+    F: FnOnce() -> Result<R, ActixError> + Send + 'static,
+    R: Send + 'static,
+{
+    web::block(move || f().map_err(|e| (e.as_response_error().status_code(), e.to_string())))
+        .await?
+        .map_err(|(status, message)| error::InternalError::new(message, status).into())
+}
+
 /// Returns the system time as [`Duration`](Duration).
 pub fn get_system_time() -> Result<Duration, ActixError> {
     SystemTime::now()
@@ -54,10 +100,8 @@ pub fn glob_match_file(mut path: PathBuf) -> Result<PathBuf, ActixError> {
     Ok(path)
 }
 
-/// Returns the found expired files in the possible upload locations.
-///
-/// Fail-safe, omits errors.
-pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
+/// Returns the possible upload locations including the shard directories.
+fn get_upload_dirs(base_path: &Path) -> Vec<PathBuf> {
     [
         PasteType::File,
         PasteType::Oneshot,
@@ -66,24 +110,159 @@ pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
     ]
     .into_iter()
     .filter_map(|v| v.get_path(base_path).ok())
-    .filter_map(|v| glob(&v.join("*.[0-9]*").to_string_lossy()).ok())
-    .flat_map(|glob| glob.filter_map(|v| v.ok()).collect::<Vec<PathBuf>>())
-    .filter(|path| {
-        if let Some(extension) = path
-            .extension()
-            .and_then(|v| v.to_str())
-            .and_then(|v| v.parse().ok())
-        {
-            get_system_time()
-                .map(|system_time| system_time > Duration::from_millis(extension))
-                .unwrap_or(false)
-        } else {
-            false
-        }
+    .flat_map(|v| {
+        let mut paths = get_shard_dirs(&v);
+        paths.push(v);
+        paths
     })
     .collect()
 }
 
+/// Returns all of the files (including the expired ones) in the possible upload locations.
+///
+/// Fail-safe, omits errors.
+pub fn get_stored_files(base_path: &Path) -> Vec<PathBuf> {
+    get_upload_dirs(base_path)
+        .into_iter()
+        .filter_map(|v| fs::read_dir(v).ok())
+        .flat_map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect::<Vec<PathBuf>>()
+        })
+        .collect()
+}
+
+/// Returns the found expired files in the possible upload locations.
+///
+/// Fail-safe, omits errors.
+pub fn get_expired_files(base_path: &Path) -> Vec<PathBuf> {
+    get_upload_dirs(base_path)
+        .into_iter()
+        .filter_map(|v| glob(&v.join("*.[0-9]*").to_string_lossy()).ok())
+        .flat_map(|glob| glob.filter_map(|v| v.ok()).collect::<Vec<PathBuf>>())
+        .filter(|path| {
+            if let Some(extension) = path
+                .extension()
+                .and_then(|v| v.to_str())
+                .and_then(|v| v.parse().ok())
+            {
+                get_system_time()
+                    .map(|system_time| system_time > Duration::from_millis(extension))
+                    .unwrap_or(false)
+            } else {
+                false
+            }
+        })
+        .collect()
+}
+
+/// Returns the path of the file in its shard directory.
+///
+/// The shard directory is named after the first `shard_length` characters of the file name
+/// (e.g. `ab/abcdef.txt`). The file is not sharded if:
+///
+/// - `shard_length` is not set or zero.
+/// - The file name is not longer than `shard_length` or starts with a dot.
+/// - The shard directory would contain a dot or clash with the directory of a paste type.
+pub fn get_shard_path(
+    dir: &Path,
+    file_name: &str,
+    shard_length: Option<usize>,
+) -> IoResult<PathBuf> {
+    let shard = shard_length
+        .filter(|length| {
+            *length > 0 && !file_name.starts_with('.') && file_name.chars().count() > *length
+        })
+        .map(|length| file_name.chars().take(length).collect::<String>())
+        .filter(|shard| !shard.contains('.') && !is_paste_type_dir(shard));
+    match shard {
+        Some(shard) => safe_path_join(safe_path_join(dir, shard)?, file_name),
+        None => safe_path_join(dir, file_name),
+    }
+}
+
+/// Returns the shard directories in the given directory.
+///
+/// Hidden directories and the directories of the paste types are skipped.
+pub fn get_shard_dirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    !name.starts_with('.') && !is_paste_type_dir(&name)
+                })
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Checks if the given name is the directory of a paste type.
+fn is_paste_type_dir(name: &str) -> bool {
+    [PasteType::Oneshot, PasteType::Url, PasteType::OneshotUrl]
+        .iter()
+        .any(|v| v.get_dir() == name)
+}
+
+/// Returns the first _unexpired_ path of the file in the given directory.
+///
+/// The [`shard directory`](get_shard_path) is checked first, files that are not sharded
+/// (e.g. the ones uploaded before enabling sharding) are still found.
+pub fn find_file_path(
+    storage: &dyn StorageBackend,
+    dir: &Path,
+    file_name: &str,
+    shard_length: Option<usize>,
+) -> Result<PathBuf, ActixError> {
+    let shard_path = glob_match_file(get_shard_path(dir, file_name, shard_length)?)?;
+    if storage.exists(&shard_path)? {
+        return Ok(shard_path);
+    }
+    glob_match_file(safe_path_join(dir, file_name)?)
+}
+
+/// Checks if the given directory is writable by creating and removing a temporary file.
+pub fn check_writable(dir: &Path) -> IoResult<()> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(IoError::other)?
+        .as_nanos();
+    let path = dir.join(format!(".health.{}.{}", std::process::id(), nanos));
+    fs::write(&path, []).and_then(|_| fs::remove_file(&path))
+}
+
+/// Returns the maximum size of a JSON body with the base64 encoded data of the given size.
+///
+/// The other fields of the body (e.g. the file name) are allowed to take up to 4 KiB.
+pub fn get_json_body_limit(data_limit: u64) -> u64 {
+    data_limit
+        .div_ceil(3)
+        .saturating_mul(4)
+        .saturating_add(JSON_BODY_OVERHEAD)
+}
+
+/// Sets the permissions of the file at the given path (e.g. `0o640`).
+///
+/// Does nothing on the platforms other than Unix.
+pub fn set_file_mode(path: &Path, mode: u32) -> IoResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
+
 /// Returns the SHA256 digest of the given input.
 pub fn sha256_digest<R: Read>(input: R) -> Result<String, ActixError> {
     let mut reader = BufReader::new(input);
@@ -97,15 +276,21 @@ pub fn sha256_digest<R: Read>(input: R) -> Result<String, ActixError> {
             break;
         }
     }
-    Ok(context
-        .finish()
-        .as_ref()
-        .iter()
-        .collect::<Vec<&u8>>()
+    hex_encode(context.finish().as_ref())
+}
+
+/// Returns the HMAC-SHA256 of the given input using the given key.
+pub fn hmac_sha256_digest(key: &[u8], input: &[u8]) -> Result<String, ActixError> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hex_encode(hmac::sign(&key, input).as_ref())
+}
+
+/// Returns the lowercase hexadecimal representation of the given bytes.
+fn hex_encode(bytes: &[u8]) -> Result<String, ActixError> {
+    Ok(bytes
         .iter()
         .try_fold::<String, _, IoResult<String>>(String::new(), |mut output, b| {
-            write!(output, "{b:02x}")
-                .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+            write!(output, "{b:02x}").map_err(|e| IoError::other(e.to_string()))?;
             Ok(output)
         })?)
 }
@@ -131,12 +316,82 @@ pub fn safe_path_join<B: AsRef<Path>, P: AsRef<Path>>(base: B, part: P) -> IoRes
     Ok(new_path)
 }
 
+/// Sanitizes a user supplied file name.
+///
+/// - Path separators and control characters are stripped.
+/// - File names that contain `..` or exceed [`MAX_FILENAME_LENGTH`] are rejected.
+pub fn sanitize_filename(file_name: &str) -> Result<String, ActixError> {
+    let file_name = file_name
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\') && !c.is_control())
+        .collect::<String>();
+    let file_name = file_name.trim();
+    if file_name.is_empty() || file_name == "." {
+        return Err(error::ErrorBadRequest("invalid file name\n"));
+    }
+    if file_name.contains("..") {
+        return Err(error::ErrorBadRequest("file name cannot contain \"..\"\n"));
+    }
+    if file_name.len() > MAX_FILENAME_LENGTH {
+        return Err(error::ErrorBadRequest("file name is too long\n"));
+    }
+    Ok(file_name.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::env;
     use std::fs;
     use std::thread;
+
+    /// Storage backend that is not local for testing [`run_blocking`].
+    struct RemoteStorage;
+
+    impl StorageBackend for RemoteStorage {
+        fn store(&self, _: &Path, _: &[u8]) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn fetch(&self, _: &Path) -> IoResult<Vec<u8>> {
+            Ok(Vec::new())
+        }
+
+        fn delete(&self, _: &Path) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn exists(&self, _: &Path) -> IoResult<bool> {
+            Ok(false)
+        }
+
+        fn is_local(&self) -> bool {
+            false
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_run_blocking() -> Result<(), ActixError> {
+        let config = Config::default();
+        let storage: Arc<dyn StorageBackend> = Arc::new(RemoteStorage);
+        let worker = thread::current().id();
+        assert_ne!(
+            worker,
+            run_blocking(&config, &storage, |_, _| Ok(thread::current().id())).await?
+        );
+        let error = run_blocking(&config, &storage, |_, _| {
+            Err::<(), _>(error::ErrorNotFound("file is not found\n"))
+        })
+        .await
+        .expect_err("not found");
+        assert_eq!(
+            actix_web::http::StatusCode::NOT_FOUND,
+            error.as_response_error().status_code()
+        );
+        assert_eq!("file is not found\n", error.to_string());
+        Ok(())
+    }
+
     #[test]
     fn test_system_time() -> Result<(), ActixError> {
         let system_time = get_system_time()?.as_millis();
@@ -177,6 +432,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hmac_sha256() -> Result<(), ActixError> {
+        assert_eq!(
+            "02afb56304902c656fcb737cdd03de6205bb6d401da2812efd9b2d36a08af159",
+            hmac_sha256_digest(b"key", b"test")?
+        );
+        assert_ne!(
+            hmac_sha256_digest(b"key", b"test")?,
+            hmac_sha256_digest(b"another_key", b"test")?
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_get_expired_files() -> Result<(), ActixError> {
         let current_dir = env::current_dir()?;
@@ -213,4 +481,64 @@ mod tests {
         assert!(safe_path_join("/foo/bar", "..").is_err());
         assert!(safe_path_join("/foo/bar", "../").is_err());
     }
+
+    #[test]
+    fn test_shard_path() -> IoResult<()> {
+        let dir = Path::new("/upload");
+        assert_eq!(
+            PathBuf::from("/upload/ab/abcdef.txt"),
+            get_shard_path(dir, "abcdef.txt", Some(2))?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/abcdef.txt"),
+            get_shard_path(dir, "abcdef.txt", None)?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/abcdef.txt"),
+            get_shard_path(dir, "abcdef.txt", Some(0))?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/ab"),
+            get_shard_path(dir, "ab", Some(2))?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/.env"),
+            get_shard_path(dir, ".env", Some(2))?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/a.txt"),
+            get_shard_path(dir, "a.txt", Some(2))?
+        );
+        assert_eq!(
+            PathBuf::from("/upload/url.txt"),
+            get_shard_path(dir, "url.txt", Some(3))?
+        );
+        assert!(get_shard_path(dir, "../file.txt", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shard_dirs() -> IoResult<()> {
+        let dir = env::current_dir()?.join("test_shard_dirs");
+        for name in ["ab", ".metadata", "url", "oneshot"] {
+            fs::create_dir_all(dir.join(name))?;
+        }
+        fs::write(dir.join("cd"), "")?;
+        assert_eq!(vec![dir.join("ab")], get_shard_dirs(&dir));
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_filename() -> Result<(), ActixError> {
+        assert_eq!("file.txt", sanitize_filename("file.txt")?);
+        assert_eq!("etcpasswd", sanitize_filename("/etc/passwd")?);
+        assert_eq!("ab.txt", sanitize_filename("a\\b\n.txt")?);
+        assert_eq!(".env", sanitize_filename(" .env ")?);
+        assert!(sanitize_filename("../file.txt").is_err());
+        assert!(sanitize_filename("..").is_err());
+        assert!(sanitize_filename("/").is_err());
+        assert!(sanitize_filename(&"a".repeat(MAX_FILENAME_LENGTH + 1)).is_err());
+        Ok(())
+    }
 }
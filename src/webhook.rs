@@ -0,0 +1,193 @@
+use crate::config::WebhookConfig;
+use crate::paste::PasteType;
+use crate::util;
+use actix_web::{error, rt, Error};
+use awc::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Header of the HMAC-SHA256 signature of the webhook payload.
+pub const SIGNATURE: &str = "x-rustypaste-signature";
+
+/// Default value of [`retries`](crate::config::WebhookConfig::retries).
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// Type of the webhook event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventType {
+    /// A paste is created.
+    Upload,
+    /// A paste is deleted.
+    Delete,
+}
+
+/// Payload of the webhook request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    /// Type of the event.
+    pub event: EventType,
+    /// File name of the paste.
+    pub file_name: String,
+    /// Size of the paste in bytes (if known).
+    pub file_size: Option<u64>,
+    /// Type of the paste.
+    #[serde(rename = "type")]
+    pub type_: PasteType,
+    /// ISO8601 formatted date-time string of the event.
+    pub timestamp_utc: String,
+}
+
+impl Event {
+    /// Creates a new event for the paste that happened at the current time.
+    pub fn new(
+        event: EventType,
+        file_name: &str,
+        file_size: Option<u64>,
+        type_: PasteType,
+    ) -> Result<Self, Error> {
+        let time = util::get_system_time()?;
+        Ok(Self {
+            event,
+            file_name: file_name.to_string(),
+            file_size,
+            type_,
+            timestamp_utc: uts2ts::uts2ts(i64::try_from(time.as_secs()).unwrap_or(i64::MAX))
+                .as_string(),
+        })
+    }
+}
+
+/// Returns the value of the [`signature header`](SIGNATURE) for the given payload.
+pub fn sign(secret: &str, payload: &[u8]) -> Result<String, Error> {
+    Ok(format!(
+        "sha256={}",
+        util::hmac_sha256_digest(secret.as_bytes(), payload)?
+    ))
+}
+
+/// Sends the event to the configured webhook URLs in the background.
+///
+/// Failed requests are retried up to [`retries`] times with an increasing delay.
+///
+/// [`retries`]: crate::config::WebhookConfig::retries
+pub fn send(client: &Client, config: &WebhookConfig, event: &Event) -> Result<(), Error> {
+    if config.urls.is_empty() {
+        return Ok(());
+    }
+    let payload = serde_json::to_vec(event).map_err(error::ErrorInternalServerError)?;
+    let signature = config
+        .secret
+        .as_deref()
+        .map(|secret| sign(secret, &payload))
+        .transpose()?;
+    let retries = config.retries.unwrap_or(DEFAULT_RETRIES);
+    for url in config.urls.clone() {
+        let client = client.clone();
+        let payload = payload.clone();
+        let signature = signature.clone();
+        rt::spawn(async move {
+            for attempt in 0..=retries {
+                if attempt > 0 {
+                    rt::time::sleep(Duration::from_secs(attempt as u64)).await;
+                }
+                let mut request = client
+                    .post(&url)
+                    .content_type(mime::APPLICATION_JSON.essence_str());
+                if let Some(signature) = &signature {
+                    request = request.insert_header((SIGNATURE, signature.as_str()));
+                }
+                match request.send_body(payload.clone()).await {
+                    Ok(response) if response.status().is_success() => return,
+                    Ok(response) => {
+                        warn!("webhook {} returned {}", url, response.status());
+                    }
+                    Err(e) => {
+                        warn!("cannot send webhook to {}: {}", url, e);
+                    }
+                }
+            }
+            error!("webhook {} failed after {} retries", url, retries);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::{self, Bytes, Data};
+    use actix_web::{App, HttpRequest, HttpResponse, HttpServer};
+    use std::sync::Mutex;
+
+    /// Signatures and payloads of the received webhook requests.
+    type Received = Mutex<Vec<(Option<String>, Bytes)>>;
+
+    #[test]
+    fn test_sign() -> Result<(), Error> {
+        assert_eq!(
+            "sha256=b82fcb791acec57859b989b430a826488ce2e479fdf92326bd0a2e8375a42ba4",
+            sign("secret", b"payload")?
+        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_send() -> Result<(), Error> {
+        let received = Data::new(Received::default());
+        let app_data = Data::clone(&received);
+        let server = HttpServer::new(move || {
+            App::new().app_data(Data::clone(&app_data)).route(
+                "/",
+                web::post().to(
+                    |request: HttpRequest, body: Bytes, received: Data<Received>| async move {
+                        let signature = request
+                            .headers()
+                            .get(SIGNATURE)
+                            .and_then(|v| v.to_str().ok())
+                            .map(String::from);
+                        if let Ok(mut received) = received.lock() {
+                            received.push((signature, body));
+                        }
+                        HttpResponse::Ok().finish()
+                    },
+                ),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+        let address = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        rt::spawn(server);
+
+        let config = WebhookConfig {
+            urls: vec![format!("http://{address}/")],
+            secret: Some(String::from("secret")),
+            retries: Some(0),
+        };
+        let event = Event::new(EventType::Upload, "file.txt", Some(3), PasteType::File)?;
+        send(&Client::default(), &config, &event)?;
+        for _ in 0..50 {
+            if received.lock().map(|v| !v.is_empty()).unwrap_or(false) {
+                break;
+            }
+            rt::time::sleep(Duration::from_millis(100)).await;
+        }
+        let (signature, body) = received
+            .lock()
+            .map_err(|_| error::ErrorInternalServerError("cannot acquire lock"))?
+            .pop()
+            .ok_or_else(|| error::ErrorInternalServerError("webhook is not received"))?;
+        assert_eq!(Some(sign("secret", &body)?), signature);
+        let payload: serde_json::Value =
+            serde_json::from_slice(&body).map_err(error::ErrorInternalServerError)?;
+        assert_eq!("upload", payload["event"]);
+        assert_eq!("file.txt", payload["file_name"]);
+        assert_eq!(3, payload["file_size"]);
+        assert_eq!("file", payload["type"]);
+
+        handle.stop(true).await;
+        Ok(())
+    }
+}